@@ -1,4 +1,4 @@
-use std::{env, fs, path::PathBuf, process::Command};
+use std::{env, fs, path::Path, path::PathBuf, process::Command};
 
 fn check_program(name: &str, install_hint: &str) {
     match Command::new(name).arg("--version").output() {
@@ -11,6 +11,63 @@ fn check_program(name: &str, install_hint: &str) {
     }
 }
 
+/// Check that every `#[template_child]` field in `src/ui` has a matching
+/// object id in its compiled template. A mismatch otherwise only shows up
+/// at runtime as a bare GLib-CRITICAL/panic from `init_template`.
+fn check_template_children(ui_out: &Path) {
+    const RESOURCE_PREFIX: &str = "/dev/neoden/wlcontrol/ui/";
+    let mut errors = Vec::new();
+
+    for entry in fs::read_dir("src/ui").unwrap() {
+        let path = entry.unwrap().path();
+        if !path.extension().is_some_and(|e| e == "rs") {
+            continue;
+        }
+        let source = fs::read_to_string(&path).unwrap();
+
+        let mut template: Option<(String, String)> = None; // (ui file, compiled xml)
+        let mut struct_name = String::new();
+        let mut expect_child = false;
+        for line in source.lines().map(str::trim) {
+            if let Some(rest) = line.strip_prefix("#[template(resource = \"") {
+                let resource = rest.trim_end_matches("\")]");
+                let ui_name = resource.trim_start_matches(RESOURCE_PREFIX).to_string();
+                let xml = fs::read_to_string(ui_out.join(&ui_name)).unwrap_or_default();
+                template = Some((ui_name, xml));
+            } else if let Some(rest) = line.strip_prefix("pub struct ") {
+                struct_name = rest.trim_end_matches(" {").to_string();
+            } else if line == "#[template_child]" {
+                expect_child = true;
+            } else if expect_child {
+                expect_child = false;
+                let Some((ui_name, xml)) = &template else { continue };
+                let field = line
+                    .trim_start_matches("pub ")
+                    .split(':')
+                    .next()
+                    .unwrap_or_default()
+                    .trim();
+                if !xml.contains(&format!("id=\"{field}\"")) {
+                    let blp = ui_name.trim_end_matches(".ui");
+                    errors.push(format!(
+                        "{}: {struct_name}.{field} has no object with id `{field}` in {blp}.blp",
+                        path.display()
+                    ));
+                }
+            }
+        }
+    }
+
+    if !errors.is_empty() {
+        eprintln!("error: template children out of sync with blueprints:");
+        for e in &errors {
+            eprintln!("  {e}");
+        }
+        eprintln!("Rename the field or the .blp object id so they match.");
+        std::process::exit(1);
+    }
+}
+
 fn main() {
     check_program(
         "blueprint-compiler",
@@ -45,6 +102,9 @@ fn main() {
         }
     }
 
+    check_template_children(&ui_out);
+    println!("cargo:rerun-if-changed=src/ui");
+
     println!("cargo:rerun-if-changed=data/resources/resources.gresource.xml");
     println!("cargo:rerun-if-changed=data/resources/style.css");
 
@@ -7,11 +7,14 @@ use crate::ui::WlcontrolWindow;
 
 mod imp {
     use super::*;
-    use std::cell::OnceCell;
+    use std::cell::{OnceCell, RefCell};
 
     #[derive(Default)]
     pub struct WlcontrolApplication {
+        /// Lives as long as the application, across window close/reopen
         pub manager: OnceCell<WlcontrolManager>,
+        /// Keeps the application alive without a window ("run in background")
+        pub hold_guard: RefCell<Option<gio::ApplicationHoldGuard>>,
     }
 
     #[glib::object_subclass]
@@ -35,15 +38,27 @@ mod imp {
                 &provider,
                 gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
             );
+
+            crate::ui::apply_color_scheme(&crate::settings::get_string("color-scheme", "auto"));
+
+            self.obj().setup_actions();
         }
 
         fn activate(&self) {
             let app = self.obj();
 
-            // Initialize manager on first activation
+            // Initialize manager on first activation; later activations
+            // (reopening after the window was closed) reuse the running backend
             let manager = self.manager.get_or_init(|| {
                 let manager = WlcontrolManager::new();
                 manager.start();
+                app.update_hold(&manager);
+                let app_weak = app.downgrade();
+                manager.connect_notify_local(Some("run-in-background"), move |manager, _| {
+                    if let Some(app) = app_weak.upgrade() {
+                        app.update_hold(manager);
+                    }
+                });
                 manager
             });
 
@@ -56,6 +71,14 @@ mod imp {
 
             window.present();
         }
+
+        fn shutdown(&self) {
+            // Closing the window doesn't end the backend, quitting does
+            if let Some(manager) = self.manager.get() {
+                manager.shutdown();
+            }
+            self.parent_shutdown();
+        }
     }
 
     impl GtkApplicationImpl for WlcontrolApplication {}
@@ -75,4 +98,43 @@ impl WlcontrolApplication {
             .property("flags", flags)
             .build()
     }
+
+    fn setup_actions(&self) {
+        let quit = gio::ActionEntry::builder("quit")
+            .activate(|app: &Self, _, _| app.quit())
+            .build();
+        // For scripts: gapplication action dev.neoden.wlcontrol wifi-connect "('SSID', 'passphrase')"
+        let wifi_connect = gio::ActionEntry::builder("wifi-connect")
+            .parameter_type(Some(&<(String, String)>::static_variant_type()))
+            .activate(|app: &Self, _, param| {
+                let Some((ssid, passphrase)) = param.and_then(|p| p.get::<(String, String)>())
+                else {
+                    return;
+                };
+                if app.imp().manager.get().is_none() {
+                    app.activate();
+                }
+                if let Some(manager) = app.imp().manager.get() {
+                    manager.request_wifi_connect_with_passphrase(&ssid, &passphrase);
+                }
+            })
+            .build();
+        self.add_action_entries([quit, wifi_connect]);
+        self.set_accels_for_action("app.quit", &["<Control>q"]);
+        self.set_accels_for_action("win.toggle-last-bt", &["<Control>b"]);
+        self.set_accels_for_action("win.refresh", &["F5", "<Control>r"]);
+    }
+
+    /// Hold the application while "run in background" is on, so closing the
+    /// window leaves the backend running; activating again opens a new window.
+    fn update_hold(&self, manager: &WlcontrolManager) {
+        let imp = self.imp();
+        if manager.run_in_background() {
+            if imp.hold_guard.borrow().is_none() {
+                imp.hold_guard.replace(Some(self.hold()));
+            }
+        } else {
+            imp.hold_guard.take();
+        }
+    }
 }
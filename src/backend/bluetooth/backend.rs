@@ -1,11 +1,34 @@
 use async_channel::Sender;
 use bluer::agent::{Agent, AgentHandle, ReqError, ReqResult};
-use bluer::{Adapter, AdapterEvent, AdapterProperty, Address, Device, DeviceEvent, DeviceProperty, Session};
+use bluer::{
+    Adapter, AdapterEvent, AdapterProperty, Address, Device, DeviceEvent, DeviceProperty, Session,
+    SessionEvent,
+};
 use futures::stream::{SelectAll, StreamExt};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
 
-use super::super::types::{BackendEvent, BtDeviceData};
+use super::super::types::{
+    finish_request, BackendEvent, BtAgentCapability, BtAudioRole, BtDeviceData, RequestId,
+};
+use super::battery_proxy::BatteryProxy;
+use super::rfkill;
+
+/// A2DP Audio Sink service class (headphones, speakers)
+const A2DP_SINK_UUID: bluer::Uuid = bluer::Uuid::from_u128(0x0000110b_0000_1000_8000_00805f9b34fb);
+/// HFP Hands-Free service class (headsets with a microphone)
+const HFP_HF_UUID: bluer::Uuid = bluer::Uuid::from_u128(0x0000111e_0000_1000_8000_00805f9b34fb);
+
+/// Profile to connect for an audio role, and the one it replaces
+fn role_profiles(role: BtAudioRole) -> (bluer::Uuid, bluer::Uuid) {
+    match role {
+        BtAudioRole::Listen => (A2DP_SINK_UUID, HFP_HF_UUID),
+        BtAudioRole::Call => (HFP_HF_UUID, A2DP_SINK_UUID),
+    }
+}
 
 /// Convert a bluer error to a user-friendly message
 fn format_bt_error(e: &bluer::Error) -> String {
@@ -82,19 +105,140 @@ pub type BtDiscoveryStream = Pin<Box<dyn futures::Stream<Item = AdapterEvent> +
 /// Type alias for the always-on adapter event stream (not tied to discovery)
 pub type BtAdapterEventStream = Pin<Box<dyn futures::Stream<Item = AdapterEvent> + Send>>;
 
+/// Type alias for the stream of adapters being added and removed (hot-plug)
+pub type BtSessionEventStream = Pin<Box<dyn futures::Stream<Item = SessionEvent> + Send>>;
+
 /// Type alias for a single device's event stream tagged with its address
 pub type BtDeviceEventStream =
     Pin<Box<dyn futures::Stream<Item = (Address, DeviceEvent)> + Send>>;
 
+/// Event streams of the tracked devices, merged into one. Each device's
+/// stream can be dropped on its own when the device stops being tracked,
+/// so no stream outlives its device.
+#[derive(Default)]
+pub struct BtDeviceStreams {
+    streams: SelectAll<BtDeviceEventStream>,
+    /// Dropping a device's sender ends its stream
+    stops: HashMap<Address, tokio::sync::oneshot::Sender<()>>,
+}
+
+impl BtDeviceStreams {
+    /// Add a device's stream, replacing any it already had
+    fn push(&mut self, addr: Address, stream: BtDeviceEventStream) {
+        let (stop_tx, stop_rx) = tokio::sync::oneshot::channel();
+        self.stops.insert(addr, stop_tx);
+        self.streams.push(Box::pin(stream.take_until(stop_rx)));
+    }
+
+    /// End a device's stream
+    fn remove(&mut self, addr: &Address) {
+        self.stops.remove(addr);
+    }
+
+    /// Whether exactly these devices have a stream
+    pub fn tracks(&self, devices: &HashSet<Address>) -> bool {
+        self.stops.len() == devices.len() && devices.iter().all(|a| self.stops.contains_key(a))
+    }
+
+    pub async fn next(&mut self) -> Option<(Address, DeviceEvent)> {
+        self.streams.next().await
+    }
+}
+
+/// Max concurrent device property reads when populating initial state
+const DEVICE_READ_CONCURRENCY: usize = 8;
+
+/// A connect that neither succeeds nor fails by then (device wandered off)
+/// is reported as timed out so the row leaves its busy state
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Run a connect for at most `limit`; None when it timed out. BlueZ keeps
+/// trying a connect nobody waits for any more, so a timed-out one is
+/// called off with `disconnect` before the timeout is reported.
+async fn connect_within(
+    limit: Duration,
+    connect: impl std::future::Future<Output = bluer::Result<()>>,
+    disconnect: impl std::future::Future<Output = bluer::Result<()>>,
+) -> Option<bluer::Result<()>> {
+    match tokio::time::timeout(limit, connect).await {
+        Ok(result) => Some(result),
+        Err(_) => {
+            if let Err(e) = disconnect.await {
+                tracing::debug!("Calling off the timed-out connect failed: {}", e);
+            }
+            None
+        }
+    }
+}
+
+/// Removal attempts before giving up on a device that keeps coming back paired
+const REMOVE_ATTEMPTS: u32 = 3;
+/// Checks after each removal that the device stays gone (about a second)
+const REMOVE_CONFIRM_CHECKS: u32 = 5;
+const REMOVE_CONFIRM_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Registration attempts while the previous agent is still unregistering
+const AGENT_REGISTER_ATTEMPTS: u32 = 5;
+const AGENT_REGISTER_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Last device data sent to the UI per address, so property changes that
+/// don't alter it (chatty devices) aren't sent again. Every path that sends
+/// device data records it here; bounded, and overflowing just starts over.
+#[derive(Clone, Default)]
+struct SentDeviceData(Arc<Mutex<HashMap<Address, BtDeviceData>>>);
+
+impl SentDeviceData {
+    const MAX_ENTRIES: usize = 256;
+
+    /// Record `data` as sent; false if it equals what was sent last
+    fn update(&self, addr: Address, data: &BtDeviceData) -> bool {
+        let mut sent = self.0.lock().unwrap();
+        if sent.get(&addr) == Some(data) {
+            return false;
+        }
+        if sent.len() >= Self::MAX_ENTRIES && !sent.contains_key(&addr) {
+            sent.clear();
+        }
+        sent.insert(addr, data.clone());
+        true
+    }
+
+    fn forget(&self, addr: &Address) {
+        self.0.lock().unwrap().remove(addr);
+    }
+
+    fn clear(&self) {
+        self.0.lock().unwrap().clear();
+    }
+}
+
 pub struct BluetoothBackend {
+    /// System bus, for interfaces bluer doesn't cover (Battery1)
+    conn: zbus::Connection,
     adapter: Option<Adapter>,
     evt_tx: Sender<BackendEvent>,
-    _agent_handle: Option<AgentHandle>,
+    session: Session,
+    /// Kept to rebuild the agent when its capability changes
+    pairing_tx: async_channel::Sender<BtPairingRequest>,
+    agent_capability: BtAgentCapability,
+    /// Unregisters the agent when dropped
+    agent_handle: Option<AgentHandle>,
+    /// Devices with a connect() in flight, so a second request doesn't
+    /// start a parallel attempt
+    connecting: Arc<Mutex<HashSet<Address>>>,
+    /// Devices being removed; their adapter add/remove events are left to
+    /// the removal task so the row doesn't flash back
+    removing: Arc<Mutex<HashSet<Address>>>,
+    sent: SentDeviceData,
+    /// List discovered devices that have no name, instead of treating them
+    /// as BLE advertisement noise
+    show_unnamed: bool,
 }
 
 impl BluetoothBackend {
     /// Create and initialize. Returns the backend + pairing request receiver.
     pub async fn new(
+        conn: zbus::Connection,
         evt_tx: Sender<BackendEvent>,
     ) -> Result<
         (Self, async_channel::Receiver<BtPairingRequest>),
@@ -104,13 +248,19 @@ impl BluetoothBackend {
 
         // Set up pairing agent
         let (pairing_tx, pairing_rx) = async_channel::unbounded::<BtPairingRequest>();
-        let agent = Self::create_agent(pairing_tx);
+        let agent_capability = BtAgentCapability::default();
+        let agent = Self::create_agent(pairing_tx.clone(), agent_capability);
         let agent_handle = session.register_agent(agent).await?;
 
         // Get default adapter (may not exist)
         let adapter = match session.default_adapter().await {
             Ok(a) => {
                 tracing::info!("Bluetooth adapter: {}", a.name());
+                // BlueZ lists a blocked adapter but can't power it on
+                if let Some(block) = rfkill::bluetooth_block() {
+                    tracing::warn!("Bluetooth radio is rfkill-blocked ({:?})", block);
+                    let _ = evt_tx.send(BackendEvent::BtError(block.message().into())).await;
+                }
                 Some(a)
             }
             Err(e) => {
@@ -126,21 +276,75 @@ impl BluetoothBackend {
         };
 
         let backend = Self {
+            conn,
             adapter,
             evt_tx,
-            _agent_handle: Some(agent_handle),
+            session,
+            pairing_tx,
+            agent_capability,
+            agent_handle: Some(agent_handle),
+            connecting: Arc::new(Mutex::new(HashSet::new())),
+            removing: Arc::new(Mutex::new(HashSet::new())),
+            sent: SentDeviceData::default(),
+            show_unnamed: false,
         };
 
         Ok((backend, pairing_rx))
     }
 
-    fn create_agent(pairing_tx: async_channel::Sender<BtPairingRequest>) -> Agent {
+    /// Switch the pairing agent's IO capability. BlueZ allows one agent per
+    /// D-Bus connection, and dropping the old handle unregisters it in the
+    /// background, so registering the new one is retried briefly.
+    pub async fn set_agent_capability(&mut self, capability: BtAgentCapability) {
+        if capability == self.agent_capability && self.agent_handle.is_some() {
+            return;
+        }
+        self.agent_handle = None;
+
+        let mut attempt = 1;
+        loop {
+            let agent = Self::create_agent(self.pairing_tx.clone(), capability);
+            match self.session.register_agent(agent).await {
+                Ok(handle) => {
+                    tracing::info!("Pairing agent registered as {}", capability.as_key());
+                    self.agent_handle = Some(handle);
+                    self.agent_capability = capability;
+                    return;
+                }
+                Err(e) if attempt < AGENT_REGISTER_ATTEMPTS => {
+                    tracing::debug!("Agent registration attempt {} failed: {}", attempt, e);
+                    attempt += 1;
+                    tokio::time::sleep(AGENT_REGISTER_RETRY_DELAY).await;
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to register pairing agent: {}", e);
+                    let _ = self
+                        .evt_tx
+                        .send(BackendEvent::BtError(format!(
+                            "Failed to register pairing agent: {}",
+                            e
+                        )))
+                        .await;
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Build the pairing agent. bluer derives the capability it reports to
+    /// BlueZ from which callbacks are set, so each level leaves out the ones
+    /// it can't handle. Any yes/no callback makes bluer report DisplayYesNo,
+    /// so NoInputNoOutput drops authorization as well.
+    fn create_agent(
+        pairing_tx: async_channel::Sender<BtPairingRequest>,
+        capability: BtAgentCapability,
+    ) -> Agent {
         let tx1 = pairing_tx.clone();
         let tx2 = pairing_tx.clone();
         let tx3 = pairing_tx.clone();
         let tx4 = pairing_tx.clone();
         let tx5 = pairing_tx.clone();
-        Agent {
+        let mut agent = Agent {
             request_default: true,
             request_confirmation: Some(Box::new(move |req| {
                 let tx = tx1.clone();
@@ -216,48 +420,175 @@ impl BluetoothBackend {
                 })
             })),
             ..Default::default()
+        };
+        match capability {
+            BtAgentCapability::KeyboardDisplay => {}
+            BtAgentCapability::DisplayYesNo => {
+                agent.request_pin_code = None;
+                agent.request_passkey = None;
+            }
+            BtAgentCapability::NoInputNoOutput => {
+                agent.request_confirmation = None;
+                agent.request_pin_code = None;
+                agent.request_passkey = None;
+                agent.display_passkey = None;
+                agent.display_pin_code = None;
+                agent.request_authorization = None;
+                agent.authorize_service = None;
+            }
         }
+        agent
     }
 
     /// Send initial adapter state and return streams for already-known devices.
     pub async fn send_initial_state(
         &self,
-        device_events: &mut SelectAll<BtDeviceEventStream>,
+        device_events: &mut BtDeviceStreams,
         tracked_devices: &mut HashSet<Address>,
     ) {
         let Some(ref adapter) = self.adapter else {
             return;
         };
 
+        self.send_adapter_state().await;
+
+        // Send already-paired/connected devices. Each read is ~8 sequential D-Bus
+        // round trips, so read up to DEVICE_READ_CONCURRENCY devices at once:
+        // startup then takes about one device read per batch of that many
+        // instead of one per paired device.
+        if let Ok(addrs) = adapter.device_addresses().await {
+            let devices: Vec<Device> = addrs
+                .into_iter()
+                .filter_map(|addr| adapter.device(addr).ok())
+                .collect();
+            let conn = &self.conn;
+            let mut results: Vec<(Device, BtDeviceData)> = futures::stream::iter(devices)
+                .map(|device| async move {
+                    let data = Self::read_device_data(conn, &device).await;
+                    data.map(|d| (device, d))
+                })
+                .buffer_unordered(DEVICE_READ_CONCURRENCY)
+                .filter_map(|result| async move { result })
+                .collect()
+                .await;
+
+            // buffer_unordered yields in completion order; restore a stable
+            // order with connected devices first, then paired
+            results.retain(|(_, data)| data.paired || data.connected);
+            results.sort_by(|(_, a), (_, b)| {
+                (!a.connected, !a.paired, &a.address).cmp(&(!b.connected, !b.paired, &b.address))
+            });
+
+            for (device, data) in results {
+                Self::start_tracking_device(
+                    &self.conn,
+                    device.address(),
+                    &device,
+                    device_events,
+                    tracked_devices,
+                )
+                .await;
+                self.sent.update(device.address(), &data);
+                let _ = self.evt_tx.send(BackendEvent::BtDeviceAdded(data)).await;
+            }
+        }
+    }
+
+    /// Send the adapter's powered, discoverable and pairable state as BlueZ
+    /// has it now
+    pub async fn send_adapter_state(&self) {
+        let Some(ref adapter) = self.adapter else {
+            return;
+        };
         if let Ok(powered) = adapter.is_powered().await {
             let _ = self.evt_tx.send(BackendEvent::BtPowered(powered)).await;
         }
-
         if let Ok(discoverable) = adapter.is_discoverable().await {
             let _ = self
                 .evt_tx
                 .send(BackendEvent::BtDiscoverable(discoverable))
                 .await;
         }
+        if let Ok(pairable) = adapter.is_pairable().await {
+            let _ = self.evt_tx.send(BackendEvent::BtPairable(pairable)).await;
+        }
+    }
 
-        // Send already-paired/connected devices
+    /// Re-read the adapter and its devices, for changes made outside
+    /// wlcontrol (bluetoothctl). Devices BlueZ no longer knows are dropped;
+    /// the rest are sent again as at startup.
+    pub async fn refresh(
+        &self,
+        device_events: &mut BtDeviceStreams,
+        tracked_devices: &mut HashSet<Address>,
+    ) {
+        let Some(ref adapter) = self.adapter else {
+            return;
+        };
+        // Device streams are dropped while powered off; power-on re-reads all
+        if !adapter.is_powered().await.unwrap_or(false) {
+            let _ = self.evt_tx.send(BackendEvent::BtPowered(false)).await;
+            return;
+        }
         if let Ok(addrs) = adapter.device_addresses().await {
-            for addr in addrs {
-                if let Ok(device) = adapter.device(addr) {
-                    if let Some(data) = Self::read_device_data(&device).await {
-                        if data.paired || data.connected {
-                            Self::start_tracking_device(
-                                addr,
-                                &device,
-                                device_events,
-                                tracked_devices,
-                            )
-                            .await;
-                            let _ =
-                                self.evt_tx.send(BackendEvent::BtDeviceAdded(data)).await;
-                        }
-                    }
-                }
+            let present: HashSet<Address> = addrs.into_iter().collect();
+            let gone: Vec<Address> = tracked_devices.difference(&present).copied().collect();
+            for addr in gone {
+                tracing::debug!("BT device {} is gone, dropping it", addr);
+                tracked_devices.remove(&addr);
+                self.sent.forget(&addr);
+                let _ = self
+                    .evt_tx
+                    .send(BackendEvent::BtDeviceRemoved(addr.to_string()))
+                    .await;
+            }
+        }
+        self.rebuild_device_streams(device_events, tracked_devices).await;
+        self.send_initial_state(device_events, tracked_devices).await;
+    }
+
+    /// Name of the adapter in use (e.g. "hci0"), None without one
+    pub fn adapter_name(&self) -> Option<&str> {
+        self.adapter.as_ref().map(|a| a.name())
+    }
+
+    /// Stop using the current adapter after BlueZ removed it (dongle
+    /// unplugged). Its devices' state is forgotten along with it.
+    pub fn detach_adapter(&mut self) {
+        if let Some(adapter) = self.adapter.take() {
+            tracing::info!("Bluetooth adapter {} removed", adapter.name());
+        }
+        self.connecting.lock().unwrap().clear();
+        self.removing.lock().unwrap().clear();
+        self.sent.clear();
+    }
+
+    /// Take BlueZ's default adapter if none is in use. Returns true when
+    /// an adapter was picked up.
+    pub async fn attach_adapter(&mut self) -> bool {
+        if self.adapter.is_some() {
+            return false;
+        }
+        match self.session.default_adapter().await {
+            Ok(adapter) => {
+                tracing::info!("Bluetooth adapter: {}", adapter.name());
+                self.adapter = Some(adapter);
+                true
+            }
+            Err(e) => {
+                tracing::info!("No Bluetooth adapter to use: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Stream of adapters appearing and disappearing in BlueZ
+    pub async fn session_events(&self) -> Option<BtSessionEventStream> {
+        match self.session.events().await {
+            Ok(stream) => Some(Box::pin(stream)),
+            Err(e) => {
+                tracing::error!("Failed to watch Bluetooth adapters: {}", e);
+                None
             }
         }
     }
@@ -278,8 +609,23 @@ impl BluetoothBackend {
         }
     }
 
-    /// Read all properties from a bluer::Device into a BtDeviceData
-    async fn read_device_data(device: &Device) -> Option<BtDeviceData> {
+    /// Read all properties from a bluer::Device into a BtDeviceData.
+    /// None when BlueZ no longer has the device: `adapter.device()` hands out
+    /// a handle for any address, but reading a property of a device that is
+    /// gone fails.
+    async fn read_device_data(conn: &zbus::Connection, device: &Device) -> Option<BtDeviceData> {
+        let paired = match device.is_paired().await {
+            Ok(paired) => paired,
+            Err(e) => {
+                tracing::debug!("BT device {} not readable: {}", device.address(), e);
+                return None;
+            }
+        };
+        let battery_percentage = match device.battery_percentage().await.ok().flatten() {
+            Some(p) => Some(p),
+            None => Self::read_battery1(conn, device).await,
+        };
+        let uuids = device.uuids().await.ok().flatten().unwrap_or_default();
         Some(BtDeviceData {
             address: device.address().to_string(),
             name: device.name().await.ok().flatten().unwrap_or_default(),
@@ -290,16 +636,12 @@ impl BluetoothBackend {
                 .ok()
                 .flatten()
                 .unwrap_or_else(|| "bluetooth".into()),
-            paired: device.is_paired().await.ok().unwrap_or(false),
+            paired,
             trusted: device.is_trusted().await.ok().unwrap_or(false),
             connected: device.is_connected().await.ok().unwrap_or(false),
-            battery_percentage: device
-                .battery_percentage()
-                .await
-                .ok()
-                .flatten()
-                .map(|p| p as i32)
-                .unwrap_or(-1),
+            audio_sink: uuids.contains(&A2DP_SINK_UUID),
+            hands_free: uuids.contains(&HFP_HF_UUID),
+            battery_percentage: battery_percentage.map(|p| p as i32).unwrap_or(-1),
             rssi: device
                 .rssi()
                 .await
@@ -309,31 +651,89 @@ impl BluetoothBackend {
         })
     }
 
+    /// D-Bus object path of a device, for proxies bluer doesn't provide
+    fn device_object_path(device: &Device) -> String {
+        format!(
+            "/org/bluez/{}/dev_{}",
+            device.adapter_name(),
+            device.address().to_string().replace(':', "_")
+        )
+    }
+
+    /// Read `Battery1.Percentage` directly. None if the device has no battery info.
+    async fn read_battery1(conn: &zbus::Connection, device: &Device) -> Option<u8> {
+        let battery = BatteryProxy::builder(conn)
+            .path(Self::device_object_path(device))
+            .ok()?
+            .cache_properties(zbus::proxy::CacheProperties::No)
+            .build()
+            .await
+            .ok()?;
+        battery.percentage().await.ok()
+    }
+
+    /// Stream of `Battery1.Percentage` changes, shaped like bluer device events.
+    /// Battery1 usually appears only once the device connects; the signal
+    /// subscription doesn't depend on the interface existing yet.
+    async fn battery1_events(
+        conn: &zbus::Connection,
+        device: &Device,
+    ) -> Option<impl futures::Stream<Item = (Address, DeviceEvent)> + Send> {
+        let addr = device.address();
+        let battery = BatteryProxy::builder(conn)
+            .path(Self::device_object_path(device))
+            .ok()?
+            .build()
+            .await
+            .ok()?;
+        let changes = battery.receive_percentage_changed().await;
+        Some(changes.filter_map(move |change| async move {
+            let percentage = change.get().await.ok()?;
+            Some((
+                addr,
+                DeviceEvent::PropertyChanged(DeviceProperty::BatteryPercentage(percentage)),
+            ))
+        }))
+    }
+
     /// Complete a device operation: re-read state from BlueZ and send BtOperationDone.
     /// Returns true if the operation succeeded.
     async fn complete_device_op(
+        conn: &zbus::Connection,
         evt_tx: &Sender<BackendEvent>,
+        sent: &SentDeviceData,
         device: &Device,
         result: Result<(), bluer::Error>,
+        request: Option<RequestId>,
     ) -> bool {
-        let data = Self::read_device_data(device).await;
+        let data = Self::read_device_data(conn, device).await;
         let error = result.as_ref().err().map(format_bt_error);
         if let Some(data) = data {
+            sent.update(device.address(), &data);
             let _ = evt_tx
-                .send(BackendEvent::BtOperationDone { data, error })
+                .send(BackendEvent::BtOperationDone { data, error, request })
                 .await;
-        } else if let Some(msg) = error {
-            // Can't read device (already removed?), fall back to generic error
-            let _ = evt_tx.send(BackendEvent::BtError(msg)).await;
+        } else {
+            // Can't read device (already removed?), report without data
+            finish_request(evt_tx, request, error, BackendEvent::BtError).await;
         }
         result.is_ok()
     }
 
-    /// Start tracking property changes for a device
+    /// Report a request that ended before it got going, from a sync caller
+    fn spawn_finish_request(&self, request: RequestId, error: Option<String>) {
+        let evt_tx = self.evt_tx.clone();
+        tokio::spawn(async move {
+            finish_request(&evt_tx, Some(request), error, BackendEvent::BtError).await;
+        });
+    }
+
+    /// Start tracking property changes for a device, including Battery1
     async fn start_tracking_device(
+        conn: &zbus::Connection,
         addr: Address,
         device: &Device,
-        device_events: &mut SelectAll<BtDeviceEventStream>,
+        device_events: &mut BtDeviceStreams,
         tracked_devices: &mut HashSet<Address>,
     ) {
         if tracked_devices.contains(&addr) {
@@ -342,7 +742,12 @@ impl BluetoothBackend {
         match device.events().await {
             Ok(events) => {
                 let stream = events.map(move |evt| (addr, evt));
-                device_events.push(Box::pin(stream));
+                // One stream per device, so merge Battery1 changes into it
+                match Self::battery1_events(conn, device).await {
+                    Some(battery) => device_events
+                        .push(addr, Box::pin(futures::stream::select(stream, battery))),
+                    None => device_events.push(addr, Box::pin(stream)),
+                }
                 tracked_devices.insert(addr);
             }
             Err(e) => {
@@ -355,46 +760,88 @@ impl BluetoothBackend {
     /// Drops stale streams for devices that were removed during scan.
     pub async fn rebuild_device_streams(
         &self,
-        device_events: &mut SelectAll<BtDeviceEventStream>,
+        device_events: &mut BtDeviceStreams,
         tracked_devices: &mut HashSet<Address>,
     ) {
         let Some(ref adapter) = self.adapter else {
             return;
         };
         let addrs: Vec<Address> = tracked_devices.drain().collect();
-        *device_events = SelectAll::new();
+        *device_events = BtDeviceStreams::default();
         for addr in addrs {
             if let Ok(device) = adapter.device(addr) {
-                Self::start_tracking_device(addr, &device, device_events, tracked_devices).await;
+                Self::start_tracking_device(&self.conn, addr, &device, device_events, tracked_devices)
+                    .await;
             }
         }
     }
 
+    /// Drop unpaired, unconnected devices left over from a scan.
+    /// Emits BtDeviceRemoved for each so the UI forgets them too.
+    pub async fn clear_discovered_devices(&self, tracked_devices: &mut HashSet<Address>) {
+        let Some(ref adapter) = self.adapter else {
+            return;
+        };
+        let addrs: Vec<Address> = tracked_devices.iter().copied().collect();
+        for addr in addrs {
+            // Connected covers a pairing in progress (ACL link is already up)
+            let keep = match adapter.device(addr) {
+                Ok(device) => {
+                    device.is_paired().await.unwrap_or(false)
+                        || device.is_connected().await.unwrap_or(false)
+                }
+                Err(_) => false,
+            };
+            if !keep {
+                tracked_devices.remove(&addr);
+                self.sent.forget(&addr);
+                let _ = self
+                    .evt_tx
+                    .send(BackendEvent::BtDeviceRemoved(addr.to_string()))
+                    .await;
+            }
+        }
+    }
+
+    pub fn set_show_unnamed(&mut self, show: bool) {
+        self.show_unnamed = show;
+    }
+
     /// Handle AdapterEvent from discovery stream
     pub async fn handle_adapter_event(
         &self,
         event: AdapterEvent,
-        device_events: &mut SelectAll<BtDeviceEventStream>,
+        device_events: &mut BtDeviceStreams,
         tracked_devices: &mut HashSet<Address>,
     ) {
         let Some(ref adapter) = self.adapter else {
             return;
         };
         match event {
+            AdapterEvent::DeviceAdded(addr) if self.removing.lock().unwrap().contains(&addr) => {
+                tracing::debug!("Ignoring DeviceAdded for {} while removing it", addr);
+            }
+            AdapterEvent::DeviceRemoved(addr) if self.removing.lock().unwrap().contains(&addr) => {
+                tracked_devices.remove(&addr);
+                device_events.remove(&addr);
+            }
             AdapterEvent::DeviceAdded(addr) => {
                 if let Ok(device) = adapter.device(addr) {
-                    if let Some(data) = Self::read_device_data(&device).await {
+                    if let Some(data) = Self::read_device_data(&self.conn, &device).await {
                         // Skip devices with no useful name (BLE advertisement noise)
-                        if data.name.is_empty() && data.alias == data.address {
+                        let unnamed = data.name.is_empty() && data.alias == data.address;
+                        if unnamed && !self.show_unnamed {
                             return;
                         }
                         Self::start_tracking_device(
+                            &self.conn,
                             addr,
                             &device,
                             device_events,
                             tracked_devices,
                         )
                         .await;
+                        self.sent.update(addr, &data);
                         let _ =
                             self.evt_tx.send(BackendEvent::BtDeviceAdded(data)).await;
                     }
@@ -402,26 +849,29 @@ impl BluetoothBackend {
             }
             AdapterEvent::DeviceRemoved(addr) => {
                 tracked_devices.remove(&addr);
+                device_events.remove(&addr);
                 // BlueZ may fire DeviceRemoved for paired devices during
                 // discovery cleanup or BLE timeouts. Re-check with the adapter:
                 // if the device still exists and is paired, send an update
-                // instead of removing it from the UI.
-                if let Some(ref adapter) = self.adapter {
-                    if let Ok(device) = adapter.device(addr) {
-                        if device.is_paired().await.unwrap_or(false) {
-                            if let Some(data) = Self::read_device_data(&device).await {
-                                Self::start_tracking_device(
-                                    addr,
-                                    &device,
-                                    device_events,
-                                    tracked_devices,
-                                ).await;
-                                let _ = self.evt_tx.send(BackendEvent::BtDeviceChanged(data)).await;
-                                return;
-                            }
-                        }
+                // instead of removing it from the UI. A device removed for
+                // real (e.g. with bluetoothctl) can't be read anymore.
+                if let Ok(device) = adapter.device(addr) {
+                    let data = Self::read_device_data(&self.conn, &device).await;
+                    if let Some(data) = data.filter(|data| data.paired) {
+                        Self::start_tracking_device(
+                            &self.conn,
+                            addr,
+                            &device,
+                            device_events,
+                            tracked_devices,
+                        )
+                        .await;
+                        self.sent.update(addr, &data);
+                        let _ = self.evt_tx.send(BackendEvent::BtDeviceChanged(data)).await;
+                        return;
                     }
                 }
+                self.sent.forget(&addr);
                 let _ = self
                     .evt_tx
                     .send(BackendEvent::BtDeviceRemoved(addr.to_string()))
@@ -434,7 +884,13 @@ impl BluetoothBackend {
                         .send(BackendEvent::BtDiscoverable(discoverable))
                         .await;
                 }
+                AdapterProperty::Pairable(pairable) => {
+                    let _ = self.evt_tx.send(BackendEvent::BtPairable(pairable)).await;
+                }
                 AdapterProperty::Powered(powered) => {
+                    // The UI resets device state on power changes without
+                    // hearing from us, so what it shows is no longer known
+                    self.sent.clear();
                     let _ = self.evt_tx.send(BackendEvent::BtPowered(powered)).await;
                 }
                 _ => {}
@@ -458,6 +914,7 @@ impl BluetoothBackend {
                 | DeviceProperty::Connected(_)
                 | DeviceProperty::BatteryPercentage(_)
                 | DeviceProperty::Rssi(_)
+                | DeviceProperty::Uuids(_)
         );
         if !dominated {
             return;
@@ -468,7 +925,10 @@ impl BluetoothBackend {
         };
 
         if let Ok(device) = adapter.device(addr) {
-            if let Some(data) = Self::read_device_data(&device).await {
+            if let Some(data) = Self::read_device_data(&self.conn, &device).await {
+                if !self.sent.update(addr, &data) {
+                    return;
+                }
                 let _ = self
                     .evt_tx
                     .send(BackendEvent::BtDeviceChanged(data))
@@ -506,87 +966,172 @@ impl BluetoothBackend {
             .await;
     }
 
-    /// Connect to a device by address string
-    pub async fn connect(&self, addr_str: &str) {
+    /// Connect to a device by address string.
+    /// Spawns a separate task so the main loop stays free to process BtCancelPair
+    /// (and agent authorization requests) while the connection is being set up.
+    /// Returns the task, or None if the connect ended before it started.
+    pub fn connect(
+        &self,
+        addr_str: &str,
+        role: Option<BtAudioRole>,
+        request: RequestId,
+    ) -> Option<JoinHandle<()>> {
         let Some(ref adapter) = self.adapter else {
-            return;
+            self.spawn_finish_request(request, None);
+            return None;
         };
-        let addr = match Self::parse_address(addr_str) {
-            Some(a) => a,
-            None => {
-                let _ = self
-                    .evt_tx
-                    .send(BackendEvent::BtError("Invalid Bluetooth address".into()))
-                    .await;
-                return;
-            }
+        let Some(addr) = Self::parse_address(addr_str) else {
+            self.spawn_finish_request(request, Some("Invalid Bluetooth address".into()));
+            return None;
         };
 
-        let _ = self.evt_tx.send(BackendEvent::BtConnecting(addr_str.to_string())).await;
-        match adapter.device(addr) {
-            Ok(device) => {
-                let result = device.connect().await;
-                match &result {
-                    Ok(()) => tracing::info!("Connected to BT device {}", addr),
-                    Err(e) => tracing::error!("BT connect to {} failed: {}", addr, e),
-                }
-                Self::complete_device_op(&self.evt_tx, &device, result).await;
-            }
+        let device = match adapter.device(addr) {
+            Ok(d) => d,
             Err(e) => {
                 tracing::error!("Cannot get device {}: {}", addr, e);
-                let _ = self
-                    .evt_tx
-                    .send(BackendEvent::BtError(format!("Device not found: {}", e)))
+                self.spawn_finish_request(request, Some(format!("Device not found: {}", e)));
+                return None;
+            }
+        };
+
+        let evt_tx = self.evt_tx.clone();
+        let conn = self.conn.clone();
+        let sent = self.sent.clone();
+        let connecting = self.connecting.clone();
+        Some(tokio::spawn(async move {
+            Self::connect_device(&conn, &evt_tx, &sent, &device, &connecting, role, Some(request))
+                .await;
+        }))
+    }
+
+    /// Connect a device and report the result, unless a connect to it is
+    /// already in flight (that one reports for the device). With a role,
+    /// only that audio profile is connected and the other one dropped.
+    async fn connect_device(
+        conn: &zbus::Connection,
+        evt_tx: &Sender<BackendEvent>,
+        sent: &SentDeviceData,
+        device: &Device,
+        connecting: &Mutex<HashSet<Address>>,
+        role: Option<BtAudioRole>,
+        request: Option<RequestId>,
+    ) {
+        let addr = device.address();
+        if !connecting.lock().unwrap().insert(addr) {
+            tracing::debug!("Connect to {} already in progress", addr);
+            finish_request(evt_tx, request, None, BackendEvent::BtError).await;
+            return;
+        }
+        let _ = evt_tx.send(BackendEvent::BtConnecting(addr.to_string())).await;
+        let result = match role {
+            Some(role) => {
+                connect_within(CONNECT_TIMEOUT, Self::connect_role(device, role), device.disconnect())
+                    .await
+            }
+            None => connect_within(CONNECT_TIMEOUT, device.connect(), device.disconnect()).await,
+        };
+        connecting.lock().unwrap().remove(&addr);
+        let Some(result) = result else {
+            tracing::warn!("BT connect to {} timed out after {:?}", addr, CONNECT_TIMEOUT);
+            Self::complete_timed_out_op(conn, evt_tx, sent, device, request).await;
+            return;
+        };
+        match &result {
+            Ok(()) => tracing::info!("Connected to BT device {}", addr),
+            Err(e) => tracing::error!("BT connect to {} failed: {}", addr, e),
+        }
+        Self::complete_device_op(conn, evt_tx, sent, device, result, request).await;
+    }
+
+    /// Connect the profile for an audio role, then drop the other one so
+    /// the sound server doesn't route audio through it. The other profile
+    /// usually isn't connected, so failing to drop it is expected.
+    async fn connect_role(device: &Device, role: BtAudioRole) -> bluer::Result<()> {
+        let (profile, other) = role_profiles(role);
+        device.connect_profile(&profile).await?;
+        if let Err(e) = device.disconnect_profile(&other).await {
+            tracing::debug!("Not dropping {} on {}: {}", other, device.address(), e);
+        }
+        Ok(())
+    }
+
+    /// Report an operation that never returned: re-read the device and send
+    /// BtOperationDone with a timeout error, unless it did end up connected.
+    async fn complete_timed_out_op(
+        conn: &zbus::Connection,
+        evt_tx: &Sender<BackendEvent>,
+        sent: &SentDeviceData,
+        device: &Device,
+        request: Option<RequestId>,
+    ) {
+        let error = "Connection timed out.".to_string();
+        match Self::read_device_data(conn, device).await {
+            Some(data) => {
+                sent.update(device.address(), &data);
+                let error = (!data.connected).then_some(error);
+                let _ = evt_tx
+                    .send(BackendEvent::BtOperationDone { data, error, request })
                     .await;
             }
+            None => {
+                finish_request(evt_tx, request, Some(error), BackendEvent::BtError).await;
+            }
         }
     }
 
     /// Disconnect from a device by address string
-    pub async fn disconnect(&self, addr_str: &str) {
-        let Some(ref adapter) = self.adapter else {
-            return;
+    pub async fn disconnect(&self, addr_str: &str, request: RequestId) {
+        let device = match (&self.adapter, Self::parse_address(addr_str)) {
+            (Some(adapter), Some(addr)) => adapter.device(addr).ok(),
+            _ => None,
         };
-        let Some(addr) = Self::parse_address(addr_str) else {
+        let Some(device) = device else {
+            finish_request(&self.evt_tx, Some(request), None, BackendEvent::BtError).await;
             return;
         };
 
-        if let Ok(device) = adapter.device(addr) {
-            let result = device.disconnect().await;
-            if let Err(ref e) = result {
-                tracing::error!("BT disconnect from {} failed: {}", addr, e);
-            }
-            Self::complete_device_op(&self.evt_tx, &device, result).await;
+        let result = device.disconnect().await;
+        if let Err(ref e) = result {
+            tracing::error!("BT disconnect from {} failed: {}", device.address(), e);
         }
+        Self::complete_device_op(&self.conn, &self.evt_tx, &self.sent, &device, result, Some(request))
+            .await;
     }
 
-    /// Pair with a device by address string.
-    /// This spawns a separate task because pair() may trigger an agent callback,
-    /// and the main select! loop needs to be free to process the BtPairingResponse command.
-    pub fn pair(&self, addr_str: &str) {
+    /// Pair with a device by address string, mark it trusted, then connect
+    /// to it if `connect_after` is set. Runs in its own task because pair()
+    /// may trigger an agent callback, and the main select! loop needs to be
+    /// free to process the BtPairingResponse command. Returns the task, or
+    /// None if pairing ended before it started.
+    pub fn pair(
+        &self,
+        addr_str: &str,
+        connect_after: bool,
+        request: RequestId,
+    ) -> Option<JoinHandle<()>> {
         let Some(ref adapter) = self.adapter else {
-            return;
+            self.spawn_finish_request(request, None);
+            return None;
         };
         let Some(addr) = Self::parse_address(addr_str) else {
-            return;
+            self.spawn_finish_request(request, None);
+            return None;
         };
 
         let device = match adapter.device(addr) {
             Ok(d) => d,
             Err(e) => {
                 tracing::error!("Cannot get device {} for pairing: {}", addr, e);
-                let evt_tx = self.evt_tx.clone();
-                tokio::spawn(async move {
-                    let _ = evt_tx
-                        .send(BackendEvent::BtError(format!("Device not found: {}", e)))
-                        .await;
-                });
-                return;
+                self.spawn_finish_request(request, Some(format!("Device not found: {}", e)));
+                return None;
             }
         };
 
         let evt_tx = self.evt_tx.clone();
-        tokio::spawn(async move {
+        let conn = self.conn.clone();
+        let sent = self.sent.clone();
+        let connecting = self.connecting.clone();
+        Some(tokio::spawn(async move {
             let _ = evt_tx.send(BackendEvent::BtConnecting(addr.to_string())).await;
             tracing::info!("Starting pairing with {}", addr);
             let result = device.pair().await;
@@ -594,47 +1139,179 @@ impl BluetoothBackend {
                 Ok(()) => tracing::info!("Paired with BT device {}", addr),
                 Err(e) => tracing::error!("BT pair with {} failed: {}", addr, e),
             }
-            if Self::complete_device_op(&evt_tx, &device, result).await {
-                // Trust the device after pairing so it can auto-connect
-                if let Err(e) = device.set_trusted(true).await {
-                    tracing::warn!("Failed to set trusted for {}: {}", addr, e);
-                }
+            if result.is_err() {
+                Self::complete_device_op(&conn, &evt_tx, &sent, &device, result, Some(request))
+                    .await;
+                return;
             }
-        });
+            // Trust the device after pairing so it can auto-connect
+            if let Err(e) = device.set_trusted(true).await {
+                tracing::warn!("Failed to set trusted for {}: {}", addr, e);
+            }
+            // BlueZ doesn't always connect after pairing; input devices
+            // often do on their own, so skip if that already happened.
+            // The connect finishes the pair request, so the device goes
+            // from pairing to connecting without showing as paired between.
+            if connect_after && !device.is_connected().await.unwrap_or(false) {
+                Self::connect_device(
+                    &conn,
+                    &evt_tx,
+                    &sent,
+                    &device,
+                    &connecting,
+                    None,
+                    Some(request),
+                )
+                .await;
+            } else {
+                Self::complete_device_op(&conn, &evt_tx, &sent, &device, Ok(()), Some(request))
+                    .await;
+            }
+        }))
     }
 
-    /// Remove (unpair) a device by address string
-    pub async fn remove(&self, addr_str: &str) {
+    /// Cancel an in-progress pairing or connection attempt by address string.
+    /// The spawned pair()/connect() task reports the final state when it unwinds.
+    pub async fn cancel_pair(&self, addr_str: &str) {
         let Some(ref adapter) = self.adapter else {
             return;
         };
         let Some(addr) = Self::parse_address(addr_str) else {
             return;
         };
+        let Ok(device) = adapter.device(addr) else {
+            return;
+        };
 
-        match adapter.remove_device(addr).await {
-            Ok(()) => {
-                tracing::info!("BT device {} removed from BlueZ", addr);
-                let _ = self
-                    .evt_tx
-                    .send(BackendEvent::BtDeviceRemoved(addr_str.to_string()))
-                    .await;
+        tracing::info!("Cancelling pairing/connection with {}", addr);
+        let result = if device.is_paired().await.unwrap_or(false) {
+            // Disconnect also aborts a pending connection attempt
+            device.disconnect().await
+        } else {
+            device.cancel_pairing().await
+        };
+        if let Err(e) = result {
+            // Nothing was in progress anymore — re-read state so the row is not stuck
+            tracing::warn!("BT cancel pairing for {} failed: {}", addr, e);
+            Self::complete_device_op(&self.conn, &self.evt_tx, &self.sent, &device, Ok(()), None)
+                .await;
+        }
+    }
+
+    /// Remove (unpair) a device by address string. Runs in its own task
+    /// because confirming the removal takes a moment; the UI only drops the
+    /// device once BlueZ has really let go of it. Returns the task, which
+    /// yields the device's address if it is still there and needs tracking
+    /// again (see `retrack_device`), or None if removal never started.
+    pub fn remove(
+        &self,
+        addr_str: &str,
+        request: RequestId,
+    ) -> Option<JoinHandle<Option<Address>>> {
+        let Some(ref adapter) = self.adapter else {
+            self.spawn_finish_request(request, None);
+            return None;
+        };
+        let Some(addr) = Self::parse_address(addr_str) else {
+            self.spawn_finish_request(request, None);
+            return None;
+        };
+        if !self.removing.lock().unwrap().insert(addr) {
+            tracing::debug!("Removal of {} already in progress", addr);
+            self.spawn_finish_request(request, None);
+            return None;
+        }
+
+        let adapter = adapter.clone();
+        let evt_tx = self.evt_tx.clone();
+        let sent = self.sent.clone();
+        let removing = self.removing.clone();
+        let addr_str = addr_str.to_string();
+        Some(tokio::spawn(async move {
+            let result = Self::remove_confirmed(&adapter, addr).await;
+            removing.lock().unwrap().remove(&addr);
+            match result {
+                Ok(()) => {
+                    sent.forget(&addr);
+                    let _ = evt_tx.send(BackendEvent::BtDeviceRemoved(addr_str)).await;
+                    finish_request(&evt_tx, Some(request), None, BackendEvent::BtError).await;
+                    None
+                }
+                Err(msg) => {
+                    finish_request(&evt_tx, Some(request), Some(msg), BackendEvent::BtError).await;
+                    Some(addr)
+                }
             }
-            Err(e) if e.to_string().contains("Does Not Exist") => {
-                tracing::info!("BT device {} already gone from BlueZ, removing from UI", addr);
-                let _ = self
-                    .evt_tx
-                    .send(BackendEvent::BtDeviceRemoved(addr_str.to_string()))
-                    .await;
+        }))
+    }
+
+    /// Track a device again after a failed removal. Its events were left
+    /// to the removal task, which may have untracked it, while BlueZ still
+    /// has it paired.
+    pub async fn retrack_device(
+        &self,
+        addr: Address,
+        device_events: &mut BtDeviceStreams,
+        tracked_devices: &mut HashSet<Address>,
+    ) {
+        let Some(ref adapter) = self.adapter else {
+            return;
+        };
+        let Ok(device) = adapter.device(addr) else {
+            return;
+        };
+        let Some(data) = Self::read_device_data(&self.conn, &device).await else {
+            return;
+        };
+        Self::start_tracking_device(&self.conn, addr, &device, device_events, tracked_devices)
+            .await;
+        self.sent.update(addr, &data);
+        let _ = self.evt_tx.send(BackendEvent::BtDeviceChanged(data)).await;
+    }
+
+    /// Remove a device and make sure it stays removed. BlueZ cleans up
+    /// asynchronously, and some devices come back still paired (stale
+    /// keys), which breaks re-pairing; those get removed again.
+    async fn remove_confirmed(adapter: &Adapter, addr: Address) -> Result<(), String> {
+        for attempt in 1..=REMOVE_ATTEMPTS {
+            match adapter.remove_device(addr).await {
+                Ok(()) => tracing::info!("BT device {} removed from BlueZ", addr),
+                Err(e) if e.to_string().contains("Does Not Exist") => {
+                    tracing::info!("BT device {} already gone from BlueZ, removing from UI", addr);
+                    return Ok(());
+                }
+                Err(e) => {
+                    tracing::error!("BT remove {} failed: {}", addr, e);
+                    return Err(format_bt_error(&e));
+                }
             }
-            Err(e) => {
-                tracing::error!("BT remove {} failed: {}", addr, e);
-                let _ = self
-                    .evt_tx
-                    .send(BackendEvent::BtError(format_bt_error(&e)))
-                    .await;
+            if Self::removal_settled(adapter, addr).await {
+                return Ok(());
             }
+            tracing::warn!(
+                "BT device {} came back paired after removal (attempt {}/{})",
+                addr,
+                attempt,
+                REMOVE_ATTEMPTS
+            );
         }
+        Err("Device could not be removed, try again.".into())
+    }
+
+    /// Poll briefly after a removal; false if the device shows up paired again
+    async fn removal_settled(adapter: &Adapter, addr: Address) -> bool {
+        for _ in 0..REMOVE_CONFIRM_CHECKS {
+            tokio::time::sleep(REMOVE_CONFIRM_INTERVAL).await;
+            // A handle to a removed device fails its property reads
+            let paired = match adapter.device(addr) {
+                Ok(device) => device.is_paired().await.unwrap_or(false),
+                Err(_) => false,
+            };
+            if paired {
+                return false;
+            }
+        }
+        true
     }
 
     /// Set alias (display name) for a device by address string
@@ -651,7 +1328,7 @@ impl BluetoothBackend {
                 tracing::error!("BT set alias for {} failed: {}", addr, e);
                 let _ = self
                     .evt_tx
-                    .send(BackendEvent::BtError(format_bt_error(&e)))
+                    .send(BackendEvent::BtError(format!("Rename failed. {}", format_bt_error(&e))))
                     .await;
             }
         }
@@ -677,24 +1354,29 @@ impl BluetoothBackend {
         }
     }
 
-    /// Set adapter powered state
-    pub async fn set_powered(&self, powered: bool) {
+    /// Set adapter powered state. Returns true if BlueZ accepted the change.
+    pub async fn set_powered(&self, powered: bool) -> bool {
         let Some(ref adapter) = self.adapter else {
-            return;
+            return false;
         };
         if let Err(e) = adapter.set_powered(powered).await {
             tracing::error!("BT set powered {} failed: {}", powered, e);
-            let _ = self
-                .evt_tx
-                .send(BackendEvent::BtError(format_bt_error(&e)))
-                .await;
+            // The radio may have been blocked since startup
+            let block = if powered { rfkill::bluetooth_block() } else { None };
+            let message = match block {
+                Some(block) => block.message().to_string(),
+                None => format_bt_error(&e),
+            };
+            let _ = self.evt_tx.send(BackendEvent::BtError(message)).await;
             // Send actual state back so UI can roll back the optimistic update
             if let Ok(actual) = adapter.is_powered().await {
                 let _ = self.evt_tx.send(BackendEvent::BtPowered(actual)).await;
             }
-            return;
+            return false;
         }
+        self.sent.clear();
         let _ = self.evt_tx.send(BackendEvent::BtPowered(powered)).await;
+        true
     }
 
     /// Set adapter discoverable state
@@ -723,6 +1405,27 @@ impl BluetoothBackend {
             .await;
     }
 
+    /// Set whether the adapter accepts new pairings. Paired devices can
+    /// still connect while it doesn't.
+    pub async fn set_pairable(&self, pairable: bool) {
+        let Some(ref adapter) = self.adapter else {
+            return;
+        };
+        if let Err(e) = adapter.set_pairable(pairable).await {
+            tracing::error!("BT set pairable {} failed: {}", pairable, e);
+            let _ = self
+                .evt_tx
+                .send(BackendEvent::BtError(format_bt_error(&e)))
+                .await;
+            // Send actual state back so UI can roll back the optimistic update
+            if let Ok(actual) = adapter.is_pairable().await {
+                let _ = self.evt_tx.send(BackendEvent::BtPairable(actual)).await;
+            }
+            return;
+        }
+        let _ = self.evt_tx.send(BackendEvent::BtPairable(pairable)).await;
+    }
+
     fn parse_address(addr_str: &str) -> Option<Address> {
         match addr_str.parse() {
             Ok(a) => Some(a),
@@ -733,3 +1436,37 @@ impl BluetoothBackend {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[tokio::test]
+    async fn timed_out_connect_is_called_off() {
+        let disconnected = AtomicBool::new(false);
+        let result = connect_within(
+            Duration::from_millis(10),
+            std::future::pending(),
+            async {
+                disconnected.store(true, Ordering::SeqCst);
+                Ok(())
+            },
+        )
+        .await;
+        assert!(result.is_none());
+        assert!(disconnected.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn finished_connect_is_left_alone() {
+        let disconnected = AtomicBool::new(false);
+        let result = connect_within(Duration::from_secs(20), async { Ok(()) }, async {
+            disconnected.store(true, Ordering::SeqCst);
+            Ok(())
+        })
+        .await;
+        assert!(matches!(result, Some(Ok(()))));
+        assert!(!disconnected.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,19 @@
+//! zbus proxy for BlueZ's `org.bluez.Battery1` interface
+//!
+//! bluer reads the battery level through the `Device` API, which some devices
+//! don't populate. Battery1 is read directly as a fallback.
+
+use zbus::proxy;
+
+/// org.bluez.Battery1 interface
+/// Object path: /org/bluez/{hci}/dev_{XX_XX_XX_XX_XX_XX}
+#[proxy(
+    interface = "org.bluez.Battery1",
+    default_service = "org.bluez",
+    gen_blocking = false
+)]
+pub trait Battery {
+    /// Battery level in percent (0-100)
+    #[zbus(property)]
+    fn percentage(&self) -> zbus::Result<u8>;
+}
@@ -4,6 +4,8 @@ use gtk::glib;
 use std::cell::{Cell, RefCell};
 use std::sync::OnceLock;
 
+use crate::backend::signal_trend::SignalTrend;
+
 /// Canonical device state, derived from BlueZ properties + local operation flags.
 /// Local operations take priority: if user clicked "forget", state is Removing
 /// even though BlueZ still reports paired=true.
@@ -34,8 +36,14 @@ mod imp {
         pub connecting: Cell<bool>,
         pub disconnecting: Cell<bool>,
         pub removing: Cell<bool>,
+        /// When the latest operation flag was set, for the manager's watchdog
+        pub operation_since: Cell<Option<std::time::Instant>>,
         pub battery_percentage: Cell<i32>, // -1 if not available
         pub rssi: Cell<i16>,                // i16::MIN = no data
+        /// Recent RSSI samples for the trend arrow; kept here as rows get rebuilt
+        pub signal_trend: RefCell<SignalTrend>,
+        /// Offers both A2DP playback and HFP calls
+        pub dual_audio: Cell<bool>,
     }
 
     #[glib::object_subclass]
@@ -83,6 +91,9 @@ mod imp {
                         .default_value(i16::MIN as i32)
                         .read_only()
                         .build(),
+                    glib::ParamSpecBoolean::builder("dual-audio")
+                        .read_only()
+                        .build(),
                 ]
             })
         }
@@ -102,6 +113,7 @@ mod imp {
                 "removing" => self.removing.get().to_value(),
                 "battery-percentage" => self.battery_percentage.get().to_value(),
                 "rssi" => (self.rssi.get() as i32).to_value(),
+                "dual-audio" => self.dual_audio.get().to_value(),
                 _ => unimplemented!(),
             }
         }
@@ -189,6 +201,19 @@ impl BtDevice {
         self.battery_percentage() >= 0
     }
 
+    /// Whether the device can be connected just for listening or just
+    /// for calls
+    pub fn dual_audio(&self) -> bool {
+        self.imp().dual_audio.get()
+    }
+
+    pub fn set_dual_audio(&self, dual_audio: bool) {
+        if self.imp().dual_audio.get() != dual_audio {
+            self.imp().dual_audio.set(dual_audio);
+            self.notify("dual-audio");
+        }
+    }
+
     pub fn set_name(&self, name: &str) {
         if *self.imp().name.borrow() != name {
             self.imp().name.replace(name.to_string());
@@ -231,9 +256,24 @@ impl BtDevice {
         }
     }
 
+    /// When an operation flag was last set; None while none is
+    pub fn operation_since(&self) -> Option<std::time::Instant> {
+        self.imp().operation_since.get()
+    }
+
+    fn note_operation(&self, started: bool) {
+        let imp = self.imp();
+        if started {
+            imp.operation_since.set(Some(std::time::Instant::now()));
+        } else if !(imp.connecting.get() || imp.disconnecting.get() || imp.removing.get()) {
+            imp.operation_since.set(None);
+        }
+    }
+
     pub fn set_connecting(&self, connecting: bool) {
         if self.imp().connecting.get() != connecting {
             self.imp().connecting.set(connecting);
+            self.note_operation(connecting);
             self.notify("connecting");
         }
     }
@@ -245,6 +285,7 @@ impl BtDevice {
     pub fn set_disconnecting(&self, disconnecting: bool) {
         if self.imp().disconnecting.get() != disconnecting {
             self.imp().disconnecting.set(disconnecting);
+            self.note_operation(disconnecting);
             self.notify("disconnecting");
         }
     }
@@ -256,6 +297,7 @@ impl BtDevice {
     pub fn set_removing(&self, removing: bool) {
         if self.imp().removing.get() != removing {
             self.imp().removing.set(removing);
+            self.note_operation(removing);
             self.notify("removing");
         }
     }
@@ -299,10 +341,20 @@ impl BtDevice {
     pub fn set_rssi(&self, rssi: i16) {
         if self.imp().rssi.get() != rssi {
             self.imp().rssi.set(rssi);
+            if rssi == i16::MIN {
+                self.imp().signal_trend.borrow_mut().clear();
+            } else {
+                self.imp().signal_trend.borrow_mut().push(rssi as i32);
+            }
             self.notify("rssi");
         }
     }
 
+    /// Trend arrow icon and tooltip; None when steady or without RSSI data
+    pub fn signal_trend(&self) -> Option<(&'static str, &'static str)> {
+        self.imp().signal_trend.borrow().indicator()
+    }
+
     /// Returns RSSI signal strength icon, or None if no RSSI data
     pub fn rssi_icon(&self) -> Option<&'static str> {
         let rssi = self.rssi();
@@ -361,9 +413,55 @@ impl BtDevice {
     }
 }
 
+/// BlueZ stores aliases like HCI names: at most 248 bytes of UTF-8
+pub const MAX_ALIAS_BYTES: usize = 248;
+
+/// Validate a user-entered device name. Returns the trimmed alias, or a
+/// message explaining why it can't be used.
+pub fn validate_alias(input: &str) -> Result<&str, &'static str> {
+    let alias = input.trim();
+    if alias.is_empty() {
+        Err("Name cannot be empty")
+    } else if alias.len() > MAX_ALIAS_BYTES {
+        Err("Name is too long")
+    } else if alias.chars().any(char::is_control) {
+        Err("Name cannot contain control characters")
+    } else {
+        Ok(alias)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::backend::test_helpers::assert_notifies_once;
+
+    // --- Alias validation ---
+
+    #[test]
+    fn alias_trimmed() {
+        assert_eq!(validate_alias("  My Headphones \t"), Ok("My Headphones"));
+    }
+
+    #[test]
+    fn alias_empty_rejected() {
+        assert!(validate_alias("").is_err());
+        assert!(validate_alias("   ").is_err());
+    }
+
+    #[test]
+    fn alias_max_length() {
+        assert!(validate_alias(&"a".repeat(MAX_ALIAS_BYTES)).is_ok());
+        assert!(validate_alias(&"a".repeat(MAX_ALIAS_BYTES + 1)).is_err());
+        // Limit is in bytes, not characters
+        assert!(validate_alias(&"é".repeat(MAX_ALIAS_BYTES / 2 + 1)).is_err());
+    }
+
+    #[test]
+    fn alias_control_chars_rejected() {
+        assert!(validate_alias("Head\nphones").is_err());
+        assert!(validate_alias("Head\u{7f}phones").is_err());
+    }
 
     fn make_device() -> BtDevice {
         BtDevice::new("/dev/1", "AA:BB:CC:DD:EE:FF", "Test", "audio-headset", false, false)
@@ -444,4 +542,61 @@ mod tests {
         d.set_disconnecting(true);
         assert_eq!(d.state(), BtDeviceState::Disconnecting);
     }
+
+    #[test]
+    fn connecting_beats_connected() {
+        let d = make_device();
+        d.set_paired(true);
+        d.set_connected(true);
+        d.set_connecting(true);
+        assert_eq!(d.state(), BtDeviceState::Connecting);
+    }
+
+    #[test]
+    fn connected_unpaired_is_connected() {
+        let d = make_device();
+        d.set_connected(true);
+        assert_eq!(d.state(), BtDeviceState::Connected);
+    }
+
+    // --- Property notifications ---
+
+    #[test]
+    fn setters_notify_only_on_change() {
+        let d = make_device();
+        assert_notifies_once(&d, "name", |d| d.set_name("Renamed"));
+        assert_notifies_once(&d, "alias", |d| d.set_alias("Kitchen"));
+        assert_notifies_once(&d, "icon", |d| d.set_icon("input-mouse"));
+        assert_notifies_once(&d, "paired", |d| d.set_paired(true));
+        assert_notifies_once(&d, "trusted", |d| d.set_trusted(true));
+        assert_notifies_once(&d, "connected", |d| d.set_connected(true));
+        assert_notifies_once(&d, "connecting", |d| d.set_connecting(true));
+        assert_notifies_once(&d, "disconnecting", |d| d.set_disconnecting(true));
+        assert_notifies_once(&d, "removing", |d| d.set_removing(true));
+        assert_notifies_once(&d, "battery-percentage", |d| d.set_battery_percentage(80));
+        assert_notifies_once(&d, "rssi", |d| d.set_rssi(-60));
+    }
+
+    /// Set every flag, then clear them from highest priority down; each
+    /// step must fall through to the next state in the chain
+    #[test]
+    fn precedence_chain() {
+        let d = make_device();
+        d.set_paired(true);
+        d.set_connected(true);
+        d.set_connecting(true);
+        d.set_disconnecting(true);
+        d.set_removing(true);
+        assert_eq!(d.state(), BtDeviceState::Removing);
+        d.set_removing(false);
+        assert_eq!(d.state(), BtDeviceState::Disconnecting);
+        d.set_disconnecting(false);
+        assert_eq!(d.state(), BtDeviceState::Connecting);
+        d.set_connecting(false);
+        assert_eq!(d.state(), BtDeviceState::Connected);
+        d.set_connected(false);
+        assert_eq!(d.state(), BtDeviceState::Paired);
+        d.set_paired(false);
+        assert_eq!(d.state(), BtDeviceState::Discovered);
+    }
 }
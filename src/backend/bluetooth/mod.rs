@@ -1,5 +1,13 @@
+#[cfg(feature = "bluetooth")]
 pub mod backend;
+#[cfg(feature = "bluetooth")]
+mod battery_proxy;
+// The manager keeps its device list even without BlueZ; it just stays empty
+#[cfg_attr(not(feature = "bluetooth"), allow(dead_code))]
 mod device;
+#[cfg(feature = "bluetooth")]
+mod rfkill;
 
+#[cfg(feature = "bluetooth")]
 pub use backend::BluetoothBackend;
-pub use device::{BtDevice, BtDeviceState};
+pub use device::{validate_alias, BtDevice, BtDeviceState};
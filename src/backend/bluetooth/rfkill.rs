@@ -0,0 +1,80 @@
+//! rfkill state of the Bluetooth radio, read from sysfs.
+//!
+//! BlueZ keeps listing an adapter whose radio is blocked, but powering it
+//! on fails with a generic error. The block is looked up here so the user
+//! is told what is actually in the way.
+
+use std::path::Path;
+
+const RFKILL_CLASS_DIR: &str = "/sys/class/rfkill";
+
+/// How a radio is blocked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RfkillBlock {
+    /// Blocked in software (`rfkill block`, system airplane mode)
+    Soft,
+    /// Blocked by a hardware switch or the firmware
+    Hard,
+}
+
+impl RfkillBlock {
+    /// Why Bluetooth can't be turned on, for the user
+    pub fn message(self) -> &'static str {
+        match self {
+            Self::Soft => {
+                "Bluetooth is blocked by rfkill. Run \"rfkill unblock bluetooth\" to allow it."
+            }
+            Self::Hard => "Bluetooth is disabled by a hardware switch or in the firmware settings.",
+        }
+    }
+}
+
+/// Block described by an rfkill entry's `soft` and `hard` attributes
+fn block_from(soft: &str, hard: &str) -> Option<RfkillBlock> {
+    if hard.trim() == "1" {
+        Some(RfkillBlock::Hard)
+    } else if soft.trim() == "1" {
+        Some(RfkillBlock::Soft)
+    } else {
+        None
+    }
+}
+
+fn read_attribute(entry: &Path, name: &str) -> String {
+    std::fs::read_to_string(entry.join(name)).unwrap_or_default()
+}
+
+/// Strongest block on a Bluetooth radio; None when unblocked or when rfkill
+/// isn't available
+pub fn bluetooth_block() -> Option<RfkillBlock> {
+    let entries = std::fs::read_dir(RFKILL_CLASS_DIR).ok()?;
+    let mut block = None;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if read_attribute(&path, "type").trim() != "bluetooth" {
+            continue;
+        }
+        match block_from(&read_attribute(&path, "soft"), &read_attribute(&path, "hard")) {
+            Some(RfkillBlock::Hard) => return Some(RfkillBlock::Hard),
+            Some(RfkillBlock::Soft) => block = Some(RfkillBlock::Soft),
+            None => {}
+        }
+    }
+    block
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unblocked_radio() {
+        assert_eq!(block_from("0\n", "0\n"), None);
+    }
+
+    #[test]
+    fn hard_block_wins_over_soft() {
+        assert_eq!(block_from("1\n", "1\n"), Some(RfkillBlock::Hard));
+        assert_eq!(block_from("1\n", "0\n"), Some(RfkillBlock::Soft));
+    }
+}
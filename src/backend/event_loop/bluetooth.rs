@@ -0,0 +1,701 @@
+//! The Bluetooth half of the event loop: the BlueZ backend, its streams and
+//! the Bt* commands. Only built with the `bluetooth` feature; without it
+//! `no_bluetooth.rs` stands in with the same interface.
+
+use std::collections::HashSet;
+
+use async_channel::{Receiver, Sender};
+use bluer::{AdapterEvent, Address, DeviceProperty};
+use futures::stream::FuturesUnordered;
+use futures::StreamExt;
+use tokio::sync::oneshot;
+
+use super::super::bluetooth::backend::{
+    BtAdapterEventStream, BtDeviceStreams, BtDiscoveryStream, BtPairingRequest,
+    BtSessionEventStream,
+};
+use super::super::bluetooth::BluetoothBackend;
+use super::super::types::{BackendCommand, BackendEvent, BtPairingKind};
+use super::finish_unhandled_request;
+
+/// How long Bluetooth discovery runs, and for a brief scan
+const BT_SCAN_DURATION: std::time::Duration = std::time::Duration::from_secs(30);
+const BT_BRIEF_SCAN_DURATION: std::time::Duration = std::time::Duration::from_secs(10);
+
+pub enum BtEvent {
+    Discovery(AdapterEvent),
+    Adapter(AdapterEvent),
+    DevicePropertyChanged {
+        address: Address,
+        property: DeviceProperty,
+    },
+    PairingRequest(BtPairingRequest),
+    ScanTimeout,
+    /// A Bluetooth adapter appeared in BlueZ (dongle plugged in)
+    AdapterAdded { name: String },
+    /// A Bluetooth adapter went away (dongle unplugged)
+    AdapterRemoved { name: String },
+    /// A connect or pair that discovery was paused for ended
+    PausedOpDone,
+    /// Removing a device failed and BlueZ still has it
+    RemovalFailed { address: Address },
+}
+
+#[derive(Default)]
+pub struct BtStreams {
+    pub pairing_rx: Option<Receiver<BtPairingRequest>>,
+    pub discovery: Option<BtDiscoveryStream>,
+    pub adapter_events: Option<BtAdapterEventStream>,
+    /// Adapters added to or removed from BlueZ (hot-plug)
+    pub session_events: Option<BtSessionEventStream>,
+    pub device_events: BtDeviceStreams,
+    /// Connects and pairs that discovery was paused for
+    pub paused_ops: FuturesUnordered<tokio::task::JoinHandle<()>>,
+    /// Device removals; a failed one yields the device to track again
+    pub removals: FuturesUnordered<tokio::task::JoinHandle<Option<Address>>>,
+    pub scan_deadline: Option<tokio::time::Instant>,
+}
+
+impl BtStreams {
+    pub async fn next_event(&mut self) -> BtEvent {
+        loop {
+            tokio::select! {
+                // BT scan timeout
+                _ = async {
+                    match self.scan_deadline {
+                        Some(deadline) => tokio::time::sleep_until(deadline).await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    return BtEvent::ScanTimeout;
+                }
+
+                // BT discovery stream events
+                Some(event) = async {
+                    match self.discovery.as_mut() {
+                        Some(s) => s.next().await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    return BtEvent::Discovery(event);
+                }
+
+                // BT always-on adapter events
+                Some(event) = async {
+                    match self.adapter_events.as_mut() {
+                        Some(s) => s.next().await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    return BtEvent::Adapter(event);
+                }
+
+                // BT adapter hot-plug
+                Some(event) = async {
+                    match self.session_events.as_mut() {
+                        Some(s) => s.next().await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    match event {
+                        bluer::SessionEvent::AdapterAdded(name) => {
+                            return BtEvent::AdapterAdded { name };
+                        }
+                        bluer::SessionEvent::AdapterRemoved(name) => {
+                            return BtEvent::AdapterRemoved { name };
+                        }
+                    }
+                }
+
+                // BT per-device property changes
+                Some((addr, event)) = self.device_events.next() => {
+                    let bluer::DeviceEvent::PropertyChanged(property) = event;
+                    return BtEvent::DevicePropertyChanged { address: addr, property };
+                }
+
+                // Connect or pair that discovery was paused for has finished
+                Some(_) = self.paused_ops.next() => {
+                    return BtEvent::PausedOpDone;
+                }
+
+                // Device removal has finished
+                Some(result) = self.removals.next() => {
+                    if let Ok(Some(address)) = result {
+                        return BtEvent::RemovalFailed { address };
+                    }
+                    continue;
+                }
+
+                // BT pairing agent requests
+                Ok(request) = async {
+                    match self.pairing_rx.as_ref() {
+                        Some(rx) => rx.recv().await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    return BtEvent::PairingRequest(request);
+                }
+            }
+        }
+    }
+}
+
+pub struct BtState {
+    evt_tx: Sender<BackendEvent>,
+    backend: Option<BluetoothBackend>,
+    tracked_devices: HashSet<Address>,
+    /// Keep unpaired devices listed after discovery stops (user preference)
+    keep_discovered: bool,
+    /// Connect a device right after pairing it (user preference)
+    connect_on_pair: bool,
+    /// Stop discovery while connecting or pairing (user preference)
+    pause_scan_on_connect: bool,
+    /// Scan time left when discovery was paused for a connect or pair
+    scan_paused: Option<std::time::Duration>,
+    pending_pairing_response: Option<PendingAgentResponse<()>>,
+    pending_pin_response: Option<PendingAgentResponse<String>>,
+    pending_passkey_response: Option<PendingAgentResponse<u32>>,
+}
+
+/// An agent request waiting on the user, and the device it is for
+struct PendingAgentResponse<T> {
+    address: Address,
+    tx: oneshot::Sender<Result<T, bluer::agent::ReqError>>,
+}
+
+impl<T> PendingAgentResponse<T> {
+    fn new(address: Address, tx: oneshot::Sender<Result<T, bluer::agent::ReqError>>) -> Self {
+        Self { address, tx }
+    }
+
+    fn send(self, result: Result<T, bluer::agent::ReqError>) {
+        let _ = self.tx.send(result);
+    }
+
+    /// Reject the request in `slot` if it is for `address`, or whatever
+    /// device it is for when `address` is None
+    fn reject(slot: &mut Option<Self>, address: Option<&str>) {
+        let matches = |p: &Self| address.is_none_or(|a| p.address.to_string() == a);
+        if let Some(pending) = slot.take_if(|p| matches(p)) {
+            pending.send(Err(bluer::agent::ReqError::Rejected));
+        }
+    }
+}
+
+impl BtState {
+    /// Connect to BlueZ, report whether Bluetooth is available and send its
+    /// initial state
+    pub async fn init(
+        conn: &zbus::Connection,
+        evt_tx: Sender<BackendEvent>,
+    ) -> (Self, BtStreams) {
+        let mut streams = BtStreams::default();
+        let backend = match BluetoothBackend::new(conn.clone(), evt_tx.clone()).await {
+            Ok((backend, rx)) => {
+                streams.pairing_rx = Some(rx);
+                Some(backend)
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "bluez service not available: {}. Bluetooth features disabled.",
+                    e
+                );
+                None
+            }
+        };
+
+        // Bluetooth is available if we successfully created the backend
+        let bt_available = backend.is_some();
+        if bt_available {
+            tracing::info!("Bluetooth available via bluez");
+        } else {
+            tracing::info!("Bluetooth unavailable: bluez not running or no adapter found");
+        }
+        let _ = evt_tx.send(BackendEvent::BtAvailable(bt_available)).await;
+
+        let mut state = Self {
+            evt_tx,
+            backend,
+            tracked_devices: HashSet::new(),
+            keep_discovered: true,
+            connect_on_pair: true,
+            pause_scan_on_connect: false,
+            scan_paused: None,
+            pending_pairing_response: None,
+            pending_pin_response: None,
+            pending_passkey_response: None,
+        };
+
+        // Send initial BT state and start always-on adapter event stream
+        if let Some(ref backend) = state.backend {
+            backend
+                .send_initial_state(&mut streams.device_events, &mut state.tracked_devices)
+                .await;
+            streams.adapter_events = backend.adapter_events().await;
+            streams.session_events = backend.session_events().await;
+        }
+
+        (state, streams)
+    }
+
+    pub async fn handle_event(&mut self, event: BtEvent, streams: &mut BtStreams) {
+        match event {
+            BtEvent::ScanTimeout => {
+                tracing::info!("Bluetooth discovery timeout (30s), stopping scan");
+                if streams.discovery.take().is_some() {
+                    if let Some(ref backend) = self.backend {
+                        backend.notify_scan_stopped().await;
+                        if !self.keep_discovered {
+                            backend.clear_discovered_devices(&mut self.tracked_devices).await;
+                            backend
+                                .rebuild_device_streams(
+                                    &mut streams.device_events,
+                                    &mut self.tracked_devices,
+                                )
+                                .await;
+                        }
+                    }
+                }
+                streams.scan_deadline = None;
+            }
+
+            BtEvent::AdapterRemoved { name } => {
+                self.handle_adapter_removed(&name, streams).await;
+            }
+
+            BtEvent::AdapterAdded { name } => {
+                tracing::info!("Bluetooth adapter added: {}", name);
+                self.attach_adapter(streams).await;
+            }
+
+            BtEvent::PausedOpDone => {
+                self.resume_scan(streams).await;
+            }
+
+            BtEvent::RemovalFailed { address } => {
+                if let Some(ref backend) = self.backend {
+                    backend
+                        .retrack_device(
+                            address,
+                            &mut streams.device_events,
+                            &mut self.tracked_devices,
+                        )
+                        .await;
+                }
+            }
+
+            BtEvent::Discovery(adapter_event) | BtEvent::Adapter(adapter_event) => {
+                if let Some(ref backend) = self.backend {
+                    backend
+                        .handle_adapter_event(
+                            adapter_event,
+                            &mut streams.device_events,
+                            &mut self.tracked_devices,
+                        )
+                        .await;
+                }
+            }
+
+            BtEvent::DevicePropertyChanged { address, property } => {
+                if let Some(ref backend) = self.backend {
+                    backend.handle_device_property_change(address, property).await;
+                }
+            }
+
+            BtEvent::PairingRequest(request) => {
+                self.handle_pairing_request(request).await;
+            }
+        }
+    }
+
+    /// Handle a Bt* command; the event loop passes on every command
+    /// `BackendCommand::is_bluetooth` picks out
+    pub async fn handle_command(&mut self, cmd: BackendCommand, streams: &mut BtStreams) {
+        match cmd {
+            BackendCommand::BtScan { brief } => {
+                if streams.discovery.is_none() {
+                    if let Some(ref backend) = self.backend {
+                        streams.discovery = backend.start_scan().await;
+                    }
+                    if streams.discovery.is_some() {
+                        let duration = if brief {
+                            BT_BRIEF_SCAN_DURATION
+                        } else {
+                            BT_SCAN_DURATION
+                        };
+                        streams.scan_deadline = Some(tokio::time::Instant::now() + duration);
+                    } else {
+                        // Lets the UI drop its "starting" state
+                        let _ = self.evt_tx.send(BackendEvent::BtDiscovering(false)).await;
+                    }
+                } else {
+                    let _ = self.evt_tx.send(BackendEvent::BtDiscovering(true)).await;
+                }
+            }
+            BackendCommand::BtRefresh => {
+                if let Some(ref backend) = self.backend {
+                    backend
+                        .refresh(&mut streams.device_events, &mut self.tracked_devices)
+                        .await;
+                }
+            }
+            BackendCommand::BtStopScan => {
+                if streams.discovery.take().is_some() {
+                    streams.scan_deadline = None;
+                    if let Some(ref backend) = self.backend {
+                        backend.notify_scan_stopped().await;
+                        if !self.keep_discovered {
+                            backend.clear_discovered_devices(&mut self.tracked_devices).await;
+                        }
+                        backend
+                            .rebuild_device_streams(
+                                &mut streams.device_events,
+                                &mut self.tracked_devices,
+                            )
+                            .await;
+                    }
+                }
+            }
+            BackendCommand::BtConnect {
+                path,
+                role,
+                request,
+            } => {
+                self.pause_scan(streams).await;
+                if let Some(ref backend) = self.backend {
+                    let task = backend.connect(&path, role, request);
+                    if let (Some(task), Some(_)) = (task, self.scan_paused) {
+                        streams.paused_ops.push(task);
+                    }
+                } else {
+                    finish_unhandled_request(&self.evt_tx, request).await;
+                }
+                self.resume_scan(streams).await;
+            }
+            BackendCommand::BtDisconnect { path, request } => {
+                if let Some(ref backend) = self.backend {
+                    backend.disconnect(&path, request).await;
+                } else {
+                    finish_unhandled_request(&self.evt_tx, request).await;
+                }
+            }
+            BackendCommand::BtPair { path, request } => {
+                self.pause_scan(streams).await;
+                if let Some(ref backend) = self.backend {
+                    let task = backend.pair(&path, self.connect_on_pair, request);
+                    if let (Some(task), Some(_)) = (task, self.scan_paused) {
+                        streams.paused_ops.push(task);
+                    }
+                } else {
+                    finish_unhandled_request(&self.evt_tx, request).await;
+                }
+                self.resume_scan(streams).await;
+            }
+            BackendCommand::BtCancelPair { address } => {
+                self.reject_pending_pairing(Some(&address));
+                if let Some(ref backend) = self.backend {
+                    backend.cancel_pair(&address).await;
+                }
+            }
+            BackendCommand::BtRemove { path, request } => {
+                if let Some(ref backend) = self.backend {
+                    if let Some(task) = backend.remove(&path, request) {
+                        streams.removals.push(task);
+                    }
+                } else {
+                    finish_unhandled_request(&self.evt_tx, request).await;
+                }
+            }
+            BackendCommand::BtSetAlias { path, alias } => {
+                if let Some(ref backend) = self.backend {
+                    backend.set_alias(&path, &alias).await;
+                }
+            }
+            BackendCommand::BtSetTrusted { path, trusted } => {
+                if let Some(ref backend) = self.backend {
+                    backend.set_trusted_flag(&path, trusted).await;
+                }
+            }
+            BackendCommand::BtSetPowered { powered } => {
+                let Some(ref backend) = self.backend else {
+                    return;
+                };
+                // Only drop device streams once power-off actually happened;
+                // a failed toggle leaves the adapter on and its devices live
+                if !backend.set_powered(powered).await {
+                    return;
+                }
+                if !powered {
+                    if streams.discovery.take().is_some() {
+                        backend.notify_scan_stopped().await;
+                    }
+                    streams.scan_deadline = None;
+                    self.scan_paused = None;
+                    self.tracked_devices.clear();
+                    streams.device_events = BtDeviceStreams::default();
+                    // Adapter events stay: they report power and discoverable
+                    // changes made outside wlcontrol while off
+                } else {
+                    // Streams were dropped on power-off: re-track paired and
+                    // connected devices so battery/connection updates resume
+                    backend
+                        .send_initial_state(&mut streams.device_events, &mut self.tracked_devices)
+                        .await;
+                    if streams.adapter_events.is_none() {
+                        streams.adapter_events = backend.adapter_events().await;
+                    }
+                    tracing::debug!(
+                        "Re-tracked {} BT devices after power on",
+                        self.tracked_devices.len()
+                    );
+                    // Every tracked device must have exactly one live event stream
+                    debug_assert!(streams.device_events.tracks(&self.tracked_devices));
+                }
+            }
+            BackendCommand::BtSyncAdapterState => {
+                if let Some(ref backend) = self.backend {
+                    backend.send_adapter_state().await;
+                }
+            }
+            BackendCommand::BtSetDiscoverable { discoverable } => {
+                if let Some(ref backend) = self.backend {
+                    backend.set_discoverable(discoverable).await;
+                }
+            }
+            BackendCommand::BtSetPairable { pairable } => {
+                if let Some(ref backend) = self.backend {
+                    backend.set_pairable(pairable).await;
+                }
+            }
+            BackendCommand::BtSetKeepDiscovered { keep } => {
+                self.keep_discovered = keep;
+            }
+            BackendCommand::BtSetConnectOnPair { enabled } => {
+                self.connect_on_pair = enabled;
+            }
+            BackendCommand::BtSetPauseScanOnConnect { enabled } => {
+                self.pause_scan_on_connect = enabled;
+            }
+            BackendCommand::BtSetShowUnnamed { show } => {
+                if let Some(ref mut backend) = self.backend {
+                    backend.set_show_unnamed(show);
+                }
+            }
+            BackendCommand::BtSetAgentCapability { capability } => {
+                if let Some(ref mut backend) = self.backend {
+                    backend.set_agent_capability(capability).await;
+                }
+            }
+            BackendCommand::BtPairingResponse { accept } => {
+                if let Some(pending) = self.pending_pairing_response.take() {
+                    let result = if accept {
+                        Ok(())
+                    } else {
+                        Err(bluer::agent::ReqError::Rejected)
+                    };
+                    pending.send(result);
+                }
+            }
+            BackendCommand::BtPairingPinResponse { pin } => {
+                if let Some(pending) = self.pending_pin_response.take() {
+                    let result = match pin {
+                        Some(p) => Ok(p),
+                        None => Err(bluer::agent::ReqError::Rejected),
+                    };
+                    pending.send(result);
+                }
+            }
+            BackendCommand::BtPairingPasskeyResponse { passkey } => {
+                if let Some(pending) = self.pending_passkey_response.take() {
+                    let result = match passkey {
+                        Some(k) => Ok(k),
+                        None => Err(bluer::agent::ReqError::Rejected),
+                    };
+                    pending.send(result);
+                }
+            }
+            other => {
+                tracing::warn!("Not a Bluetooth command: {:?}", other);
+            }
+        }
+    }
+
+    /// Stop discovery as the backend shuts down
+    pub fn shutdown(&mut self, streams: &mut BtStreams) {
+        streams.discovery.take();
+    }
+
+    /// Resolve the agent requests still waiting on the user for `address`
+    /// with a rejection; those of every device when it is None. Cancelling
+    /// one device's pairing leaves another's prompt alone.
+    fn reject_pending_pairing(&mut self, address: Option<&str>) {
+        PendingAgentResponse::reject(&mut self.pending_pairing_response, address);
+        PendingAgentResponse::reject(&mut self.pending_pin_response, address);
+        PendingAgentResponse::reject(&mut self.pending_passkey_response, address);
+    }
+
+    /// The adapter in use was unplugged: drop everything tied to it, tell
+    /// the UI Bluetooth is gone, then fall back to another adapter if BlueZ
+    /// still has one. Removal of an adapter not in use is ignored.
+    async fn handle_adapter_removed(&mut self, name: &str, streams: &mut BtStreams) {
+        let Some(ref mut backend) = self.backend else {
+            return;
+        };
+        if backend.adapter_name() != Some(name) {
+            tracing::debug!("Unused Bluetooth adapter {} removed", name);
+            return;
+        }
+        backend.detach_adapter();
+        self.reject_pending_pairing(None);
+        streams.discovery = None;
+        streams.scan_deadline = None;
+        streams.adapter_events = None;
+        streams.device_events = BtDeviceStreams::default();
+        self.tracked_devices.clear();
+        self.scan_paused = None;
+        let _ = self.evt_tx.send(BackendEvent::BtAvailable(false)).await;
+
+        self.attach_adapter(streams).await;
+    }
+
+    /// Start using BlueZ's default adapter when none is in use, at startup
+    /// without one or after the previous one was unplugged
+    async fn attach_adapter(&mut self, streams: &mut BtStreams) {
+        let Some(ref mut backend) = self.backend else {
+            return;
+        };
+        if !backend.attach_adapter().await {
+            return;
+        }
+        let _ = self.evt_tx.send(BackendEvent::BtAvailable(true)).await;
+        backend
+            .send_initial_state(&mut streams.device_events, &mut self.tracked_devices)
+            .await;
+        streams.adapter_events = backend.adapter_events().await;
+    }
+
+    /// Stop discovery ahead of a connect or pair, when the user asked for
+    /// that; some controllers connect unreliably while scanning. The time
+    /// the scan had left is kept for resume_scan.
+    async fn pause_scan(&mut self, streams: &mut BtStreams) {
+        if !self.pause_scan_on_connect || streams.discovery.take().is_none() {
+            return;
+        }
+        let now = tokio::time::Instant::now();
+        let remaining = streams
+            .scan_deadline
+            .take()
+            .map_or(BT_SCAN_DURATION, |deadline| deadline.saturating_duration_since(now));
+        self.scan_paused = Some(remaining);
+        tracing::info!("Pausing Bluetooth discovery for a connect");
+        if let Some(ref backend) = self.backend {
+            backend.notify_scan_stopped().await;
+        }
+    }
+
+    /// Restart paused discovery once no connect or pair it was paused for
+    /// is still running. Skipped if the user started a scan in between.
+    async fn resume_scan(&mut self, streams: &mut BtStreams) {
+        if !streams.paused_ops.is_empty() {
+            return;
+        }
+        let Some(remaining) = self.scan_paused.take() else {
+            return;
+        };
+        if streams.discovery.is_some() {
+            return;
+        }
+        let Some(ref backend) = self.backend else {
+            return;
+        };
+        tracing::info!("Resuming Bluetooth discovery");
+        streams.discovery = backend.start_scan().await;
+        if streams.discovery.is_some() {
+            let duration = remaining.max(BT_BRIEF_SCAN_DURATION);
+            streams.scan_deadline = Some(tokio::time::Instant::now() + duration);
+        }
+    }
+
+    async fn handle_pairing_request(&mut self, request: BtPairingRequest) {
+        let (kind, address) = match request {
+            BtPairingRequest::ConfirmPasskey {
+                address,
+                passkey,
+                response_tx,
+            } => {
+                self.pending_pairing_response =
+                    Some(PendingAgentResponse::new(address, response_tx));
+                (BtPairingKind::ConfirmPasskey(format!("{:06}", passkey)), address)
+            }
+            BtPairingRequest::RequestPinCode {
+                address,
+                response_tx,
+            } => {
+                self.pending_pin_response =
+                    Some(PendingAgentResponse::new(address, response_tx));
+                (BtPairingKind::RequestPin, address)
+            }
+            BtPairingRequest::RequestPasskey {
+                address,
+                response_tx,
+            } => {
+                self.pending_passkey_response =
+                    Some(PendingAgentResponse::new(address, response_tx));
+                (BtPairingKind::RequestPasskey, address)
+            }
+            BtPairingRequest::DisplayPasskey { address, passkey } => {
+                (BtPairingKind::DisplayPasskey(format!("{:06}", passkey)), address)
+            }
+            BtPairingRequest::DisplayPinCode { address, pin_code } => {
+                (BtPairingKind::DisplayPin(pin_code), address)
+            }
+            BtPairingRequest::RequestAuthorization {
+                address,
+                response_tx,
+            } => {
+                self.pending_pairing_response =
+                    Some(PendingAgentResponse::new(address, response_tx));
+                (BtPairingKind::Authorize, address)
+            }
+        };
+        tracing::info!("BT pairing {:?} for {}", kind, address);
+        let _ = self
+            .evt_tx
+            .send(BackendEvent::BtPairing {
+                kind,
+                address: address.to_string(),
+            })
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancel_rejects_only_the_devices_own_prompt() {
+        let (tx, mut rx) = oneshot::channel();
+        let address = Address::new([0x00, 0x11, 0x22, 0x33, 0x44, 0x55]);
+        let mut slot = Some(PendingAgentResponse::<()>::new(address, tx));
+
+        PendingAgentResponse::reject(&mut slot, Some("66:77:88:99:AA:BB"));
+        assert!(slot.is_some());
+        assert!(rx.try_recv().is_err());
+
+        PendingAgentResponse::reject(&mut slot, Some("00:11:22:33:44:55"));
+        assert!(slot.is_none());
+        assert!(matches!(rx.try_recv(), Ok(Err(bluer::agent::ReqError::Rejected))));
+    }
+
+    #[test]
+    fn adapter_gone_rejects_any_prompt() {
+        let (tx, mut rx) = oneshot::channel();
+        let address = Address::new([0x00, 0x11, 0x22, 0x33, 0x44, 0x55]);
+        let mut slot = Some(PendingAgentResponse::<u32>::new(address, tx));
+
+        PendingAgentResponse::reject(&mut slot, None);
+        assert!(slot.is_none());
+        assert!(matches!(rx.try_recv(), Ok(Err(bluer::agent::ReqError::Rejected))));
+    }
+}
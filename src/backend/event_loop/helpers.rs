@@ -1,10 +1,21 @@
 use async_channel::Sender;
 use futures::StreamExt;
+use std::time::Duration;
 use zbus::zvariant::OwnedObjectPath;
 
 use super::super::types::BackendEvent;
+use super::super::util::{property_values, wait_for_property};
 use super::super::wifi::iwd_proxy::{DeviceProxy, StationProxy};
-use super::super::wifi::{get_known_networks, get_wifi_networks};
+use super::super::wifi::{get_known_networks, get_radio_powered, get_wifi_networks};
+
+/// How long to wait for a device to report powered before giving up on Station
+const STATION_WAIT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Checks for the Station interface once the device is powered
+const STATION_READY_CHECKS: u32 = 5;
+const STATION_READY_INTERVAL: Duration = Duration::from_millis(100);
+/// Wait before reading known networks again; right after iwd starts its
+/// object tree can still be incomplete
+const KNOWN_NETWORKS_RETRY_DELAY: Duration = Duration::from_secs(1);
 
 /// Create DeviceProxy safely, returning None on failure.
 pub async fn create_device_proxy(
@@ -32,13 +43,16 @@ pub async fn create_station_proxy(
         .ok()
 }
 
-/// Send initial WiFi state for a device (powered, scanning, networks, known networks).
+/// Send initial WiFi state for a device (radio, powered, scanning, networks, known networks).
 pub async fn send_wifi_initial_state(
     conn: &zbus::Connection,
     device_path: &OwnedObjectPath,
     evt_tx: &Sender<BackendEvent>,
 ) {
     if let Some(device) = create_device_proxy(conn, device_path).await {
+        if let Some(radio_powered) = get_radio_powered(conn, device_path).await {
+            let _ = evt_tx.send(BackendEvent::WifiRadioPowered(radio_powered)).await;
+        }
         if let Ok(powered) = device.powered().await {
             let _ = evt_tx.send(BackendEvent::WifiPowered(powered)).await;
 
@@ -52,36 +66,75 @@ pub async fn send_wifi_initial_state(
                     }
                 }
             }
-            if let Ok(known) = get_known_networks(conn).await {
-                let _ = evt_tx.send(BackendEvent::WifiKnownNetworks(known)).await;
-            }
+        }
+    }
+    // Known networks live on iwd's root object, not the device or station,
+    // so list them even when those aren't answering yet; the saved list
+    // should not stay empty on a cold start
+    let known = match get_known_networks(conn).await {
+        Ok(known) => Ok(known),
+        Err(e) => {
+            tracing::warn!("Failed to read known networks at startup, retrying: {}", e);
+            tokio::time::sleep(KNOWN_NETWORKS_RETRY_DELAY).await;
+            get_known_networks(conn).await
+        }
+    };
+    match known {
+        Ok(known) => {
+            let _ = evt_tx.send(BackendEvent::WifiKnownNetworks(known)).await;
+        }
+        Err(e) => {
+            tracing::error!("Failed to read known networks at startup: {}", e);
+            let _ = evt_tx.send(BackendEvent::WifiKnownNetworksUnreadable).await;
+            let _ = evt_tx
+                .send(BackendEvent::WifiError("Could not read saved networks".into()))
+                .await;
         }
     }
 }
 
-/// Wait for Station interface with exponential backoff retry.
+/// Wait for the Station interface of a device that is being powered up.
+/// iwd only adds Station once `Device.Powered` is true, so wait for that
+/// first, then check the interface answers.
 pub async fn wait_for_station_proxy(
     conn: &zbus::Connection,
     device_path: &OwnedObjectPath,
-    max_attempts: u32,
+    timeout: Duration,
 ) -> Option<StationProxy<'static>> {
-    for attempt in 0..max_attempts {
+    let device = create_device_proxy(conn, device_path).await?;
+    // Subscribe before reading so a change in between isn't missed
+    let powered_changes = device.receive_powered_changed().await;
+    if !device.powered().await.unwrap_or(false)
+        && wait_for_property(property_values(powered_changes), |p| *p, timeout)
+            .await
+            .is_none()
+    {
+        tracing::warn!("Device not powered after {:?}, no Station interface", timeout);
+        return None;
+    }
+
+    // Powered flips just before iwd exports Station; give it a moment
+    for _ in 0..STATION_READY_CHECKS {
         if let Some(station) = create_station_proxy(conn, device_path).await {
-            tracing::debug!("Station interface available after {} attempts", attempt + 1);
-            return Some(station);
-        }
-        if attempt + 1 < max_attempts {
-            let delay = 50 * (1 << attempt.min(4));
-            tokio::time::sleep(tokio::time::Duration::from_millis(delay)).await;
+            if station.state().await.is_ok() {
+                tracing::debug!("Station interface available");
+                return Some(station);
+            }
         }
+        tokio::time::sleep(STATION_READY_INTERVAL).await;
     }
-    tracing::warn!(
-        "Station interface not available after {} attempts",
-        max_attempts
-    );
+    tracing::warn!("Station interface not available on powered device");
     None
 }
 
+/// Whether the device currently reports `Device.Powered`
+pub async fn device_powered(conn: &zbus::Connection, device_path: &OwnedObjectPath) -> bool {
+    match create_device_proxy(conn, device_path).await {
+        Some(device) => device.powered().await.unwrap_or(false),
+        None => false,
+    }
+}
+
 /// Set up Station property change streams (scanning + state).
 pub async fn setup_station_streams(
     conn: &zbus::Connection,
@@ -106,7 +159,7 @@ pub async fn setup_station_streams_with_retry(
     Option<zbus::PropertyStream<'static, bool>>,
     Option<zbus::PropertyStream<'static, String>>,
 ) {
-    let Some(station) = wait_for_station_proxy(conn, device_path, 10).await else {
+    let Some(station) = wait_for_station_proxy(conn, device_path, STATION_WAIT_TIMEOUT).await else {
         return (None, None);
     };
     let scanning = station.receive_scanning_changed().await;
@@ -0,0 +1,52 @@
+//! Stand-in for the Bluetooth half of the event loop in builds without the
+//! `bluetooth` feature. Bluetooth is reported unavailable, no events ever
+//! come, and Bt* commands only answer the requests they carry.
+
+use async_channel::Sender;
+
+use super::super::types::{BackendCommand, BackendEvent};
+use super::finish_unhandled_request;
+
+pub enum BtEvent {}
+
+#[derive(Default)]
+pub struct BtStreams;
+
+impl BtStreams {
+    pub async fn next_event(&mut self) -> BtEvent {
+        std::future::pending().await
+    }
+}
+
+pub struct BtState {
+    evt_tx: Sender<BackendEvent>,
+}
+
+impl BtState {
+    pub async fn init(
+        _conn: &zbus::Connection,
+        evt_tx: Sender<BackendEvent>,
+    ) -> (Self, BtStreams) {
+        tracing::info!("Bluetooth unavailable: built without the bluetooth feature");
+        let _ = evt_tx.send(BackendEvent::BtAvailable(false)).await;
+        (Self { evt_tx }, BtStreams)
+    }
+
+    pub async fn handle_event(&mut self, event: BtEvent, _streams: &mut BtStreams) {
+        match event {}
+    }
+
+    pub async fn handle_command(&mut self, cmd: BackendCommand, _streams: &mut BtStreams) {
+        match cmd {
+            BackendCommand::BtConnect { request, .. }
+            | BackendCommand::BtDisconnect { request, .. }
+            | BackendCommand::BtPair { request, .. }
+            | BackendCommand::BtRemove { request, .. } => {
+                finish_unhandled_request(&self.evt_tx, request).await;
+            }
+            _ => {}
+        }
+    }
+
+    pub fn shutdown(&mut self, _streams: &mut BtStreams) {}
+}
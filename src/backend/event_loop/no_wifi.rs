@@ -0,0 +1,53 @@
+//! Stand-in for the WiFi half of the event loop in builds without the
+//! `wifi` feature. WiFi is reported unavailable, no events ever come, and
+//! Wifi* commands only answer the requests they carry.
+
+use async_channel::Sender;
+
+use super::super::types::{BackendCommand, BackendEvent};
+use super::finish_unhandled_request;
+
+pub enum WifiEvent {}
+
+#[derive(Default)]
+pub struct WifiStreams;
+
+impl WifiStreams {
+    pub async fn next_event(&mut self) -> WifiEvent {
+        std::future::pending().await
+    }
+}
+
+pub struct WifiState {
+    evt_tx: Sender<BackendEvent>,
+}
+
+impl WifiState {
+    pub async fn init(
+        _conn: &zbus::Connection,
+        evt_tx: Sender<BackendEvent>,
+    ) -> zbus::Result<(Self, WifiStreams)> {
+        tracing::info!("WiFi unavailable: built without the wifi feature");
+        let _ = evt_tx.send(BackendEvent::WifiAvailable(false)).await;
+        Ok((Self { evt_tx }, WifiStreams))
+    }
+
+    pub async fn handle_event(&mut self, event: WifiEvent, _streams: &mut WifiStreams) {
+        match event {}
+    }
+
+    pub async fn handle_command(&mut self, cmd: BackendCommand, _streams: &mut WifiStreams) {
+        match cmd {
+            BackendCommand::WifiConnectHidden { request, .. }
+            | BackendCommand::WifiConnect { request, .. }
+            | BackendCommand::WifiDisconnect { request }
+            | BackendCommand::WifiForget { request, .. }
+            | BackendCommand::WifiForgetKnown { request, .. } => {
+                finish_unhandled_request(&self.evt_tx, request).await;
+            }
+            _ => {}
+        }
+    }
+
+    pub async fn shutdown(&mut self) {}
+}
@@ -1,181 +1,104 @@
 use async_channel::Receiver;
-use futures::stream::SelectAll;
-use futures::StreamExt;
 
-use super::super::bluetooth::backend::{
-    BtAdapterEventStream, BtDeviceEventStream, BtDiscoveryStream, BtPairingRequest,
-};
 use super::super::types::BackendCommand;
-use super::super::wifi::PassphraseRequest;
-use super::helpers::{next_iwd_added, next_iwd_removed};
+use super::bluetooth::BtStreams;
+use super::wifi::WifiStreams;
 use super::LoopEvent;
 
 pub struct EventStreams {
     pub cmd_rx: Receiver<BackendCommand>,
-    pub passphrase_rx: Receiver<PassphraseRequest>,
-    pub bt_pairing_rx: Option<Receiver<BtPairingRequest>>,
 
-    pub device_powered_stream: Option<zbus::PropertyStream<'static, bool>>,
-    pub station_scanning_stream: Option<zbus::PropertyStream<'static, bool>>,
-    pub station_state_stream: Option<zbus::PropertyStream<'static, String>>,
+    /// WiFi sources (passphrase agent, iwd devices and stations, polls)
+    pub wifi: WifiStreams,
 
-    pub bt_discovery_stream: Option<BtDiscoveryStream>,
-    pub bt_adapter_events: Option<BtAdapterEventStream>,
-    pub bt_device_events: SelectAll<BtDeviceEventStream>,
-
-    pub bt_scan_deadline: Option<tokio::time::Instant>,
-
-    pub iwd_interfaces_added: Option<zbus::fdo::InterfacesAddedStream<'static>>,
-    pub iwd_interfaces_removed: Option<zbus::fdo::InterfacesRemovedStream<'static>>,
+    /// Bluetooth sources (pairing agent, discovery, adapters, devices)
+    pub bt: BtStreams,
 }
 
 impl EventStreams {
     pub async fn next_event(&mut self) -> LoopEvent {
-        loop {
-            tokio::select! {
-                // BT scan timeout
-                _ = async {
-                    match self.bt_scan_deadline {
-                        Some(deadline) => tokio::time::sleep_until(deadline).await,
-                        None => std::future::pending().await,
-                    }
-                } => {
-                    return LoopEvent::BtScanTimeout;
-                }
-
-                // Device.Powered property change
-                Some(change) = async {
-                    match self.device_powered_stream.as_mut() {
-                        Some(s) => s.next().await,
-                        None => std::future::pending().await,
-                    }
-                } => {
-                    match change.get().await {
-                        Ok(powered) => return LoopEvent::WifiPoweredChanged(powered),
-                        Err(e) => {
-                            tracing::warn!("Failed to get Device.Powered: {}", e);
-                            continue;
-                        }
-                    }
-                }
-
-                // Station.Scanning property change
-                Some(change) = async {
-                    match self.station_scanning_stream.as_mut() {
-                        Some(s) => s.next().await,
-                        None => std::future::pending().await,
-                    }
-                } => {
-                    match change.get().await {
-                        Ok(scanning) => return LoopEvent::WifiScanningChanged(scanning),
-                        Err(e) => {
-                            tracing::warn!("Failed to get Station.Scanning: {}", e);
-                            continue;
-                        }
-                    }
-                }
-
-                // Station.State property change
-                Some(change) = async {
-                    match self.station_state_stream.as_mut() {
-                        Some(s) => s.next().await,
-                        None => std::future::pending().await,
-                    }
-                } => {
-                    match change.get().await {
-                        Ok(state) => return LoopEvent::WifiStationStateChanged(state),
-                        Err(e) => {
-                            tracing::warn!("Failed to get Station.State: {}", e);
-                            continue;
-                        }
-                    }
-                }
-
-                // Passphrase requests from iwd agent
-                Ok(request) = self.passphrase_rx.recv() => {
-                    return LoopEvent::PassphraseRequest(request);
-                }
-
-                // BT discovery stream events
-                Some(event) = async {
-                    match self.bt_discovery_stream.as_mut() {
-                        Some(s) => s.next().await,
-                        None => std::future::pending().await,
-                    }
-                } => {
-                    return LoopEvent::BtDiscoveryEvent(event);
-                }
+        tokio::select! {
+            // WiFi events
+            event = self.wifi.next_event() => LoopEvent::Wifi(event),
+
+            // Bluetooth events
+            event = self.bt.next_event() => LoopEvent::Bt(event),
+
+            // UI commands
+            result = self.cmd_rx.recv() => match result {
+                Ok(cmd) => LoopEvent::Command(cmd),
+                Err(_) => LoopEvent::CommandChannelClosed,
+            },
+        }
+    }
+}
 
-                // BT always-on adapter events
-                Some(event) = async {
-                    match self.bt_adapter_events.as_mut() {
-                        Some(s) => s.next().await,
-                        None => std::future::pending().await,
-                    }
-                } => {
-                    return LoopEvent::BtAdapterEvent(event);
-                }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_channel::Sender;
+    use std::time::Duration;
+
+    const TIMEOUT: Duration = Duration::from_secs(1);
+
+    /// Streams with no D-Bus or BlueZ sources, as when neither iwd nor an
+    /// adapter is present
+    fn idle_streams() -> (EventStreams, Sender<BackendCommand>) {
+        let (cmd_tx, cmd_rx) = async_channel::unbounded();
+        let streams = EventStreams {
+            cmd_rx,
+            wifi: WifiStreams::default(),
+            bt: BtStreams::default(),
+        };
+        (streams, cmd_tx)
+    }
 
-                // BT per-device property changes
-                Some((addr, event)) = self.bt_device_events.next() => {
-                    let bluer::DeviceEvent::PropertyChanged(property) = event;
-                    return LoopEvent::BtDevicePropertyChanged { address: addr, property };
-                }
+    async fn next_event(streams: &mut EventStreams) -> LoopEvent {
+        tokio::time::timeout(TIMEOUT, streams.next_event())
+            .await
+            .expect("event loop hung instead of returning an event")
+    }
 
-                // BT pairing agent requests
-                Ok(request) = async {
-                    match self.bt_pairing_rx.as_ref() {
-                        Some(rx) => rx.recv().await,
-                        None => std::future::pending().await,
-                    }
-                } => {
-                    return LoopEvent::BtPairingRequest(request);
-                }
+    #[cfg(feature = "wifi")]
+    #[tokio::test]
+    async fn closed_command_channel_ends_idle_loop() {
+        let (mut streams, cmd_tx) = idle_streams();
+        streams.wifi.networks_deadline = Some(tokio::time::Instant::now() + TIMEOUT * 10);
+        drop(cmd_tx);
+        assert!(matches!(next_event(&mut streams).await, LoopEvent::CommandChannelClosed));
+    }
 
-                // iwd InterfacesAdded (hot-plug)
-                Some(signal) = next_iwd_added(&mut self.iwd_interfaces_added) => {
-                    match signal.args() {
-                        Ok(args) => {
-                            if args.interfaces_and_properties().contains_key("net.connman.iwd.Device") {
-                                return LoopEvent::IwdDeviceAdded {
-                                    object_path: args.object_path().to_string(),
-                                };
-                            }
-                            continue;
-                        }
-                        Err(e) => {
-                            tracing::warn!("Failed to parse InterfacesAdded: {}", e);
-                            continue;
-                        }
-                    }
-                }
+    #[cfg(feature = "wifi")]
+    #[tokio::test]
+    async fn closed_passphrase_channel_doesnt_hide_shutdown() {
+        let (mut streams, cmd_tx) = idle_streams();
+        let (passphrase_tx, passphrase_rx) = async_channel::unbounded();
+        streams.wifi.passphrase_rx = Some(passphrase_rx);
+        drop(passphrase_tx);
+        drop(cmd_tx);
+        assert!(matches!(next_event(&mut streams).await, LoopEvent::CommandChannelClosed));
+    }
 
-                // iwd InterfacesRemoved (hot-plug)
-                Some(signal) = next_iwd_removed(&mut self.iwd_interfaces_removed) => {
-                    match signal.args() {
-                        Ok(args) => {
-                            if args.interfaces().contains(&"net.connman.iwd.Device") {
-                                return LoopEvent::IwdDeviceRemoved {
-                                    object_path: args.object_path().to_string(),
-                                };
-                            }
-                            continue;
-                        }
-                        Err(e) => {
-                            tracing::warn!("Failed to parse InterfacesRemoved: {}", e);
-                            continue;
-                        }
-                    }
-                }
+    #[cfg(feature = "bluetooth")]
+    #[tokio::test]
+    async fn closed_pairing_channel_doesnt_hide_shutdown() {
+        let (mut streams, cmd_tx) = idle_streams();
+        let (pairing_tx, pairing_rx) = async_channel::unbounded();
+        streams.bt.pairing_rx = Some(pairing_rx);
+        drop(pairing_tx);
+        drop(cmd_tx);
+        assert!(matches!(next_event(&mut streams).await, LoopEvent::CommandChannelClosed));
+    }
 
-                // UI commands
-                result = self.cmd_rx.recv() => {
-                    match result {
-                        Ok(cmd) => return LoopEvent::Command(cmd),
-                        Err(_) => return LoopEvent::CommandChannelClosed,
-                    }
-                }
-            }
-        }
+    #[tokio::test]
+    async fn queued_commands_come_before_shutdown() {
+        let (mut streams, cmd_tx) = idle_streams();
+        cmd_tx.send(BackendCommand::BtRefresh).await.unwrap();
+        drop(cmd_tx);
+        assert!(matches!(
+            next_event(&mut streams).await,
+            LoopEvent::Command(BackendCommand::BtRefresh)
+        ));
+        assert!(matches!(next_event(&mut streams).await, LoopEvent::CommandChannelClosed));
     }
 }
@@ -0,0 +1,982 @@
+//! The WiFi half of the event loop: the iwd backend and agent, their
+//! streams and the Wifi* commands. Only built with the `wifi` feature;
+//! without it `no_wifi.rs` stands in with the same interface.
+
+use async_channel::{Receiver, Sender};
+use futures::StreamExt;
+use tokio::sync::{oneshot, watch};
+use zbus::zvariant::OwnedObjectPath;
+
+use super::super::types::{BackendCommand, BackendEvent};
+use super::super::wifi::iwd_proxy::{AgentManagerProxy, StationProxy};
+use super::super::wifi::{
+    find_all_iwd_devices, IwdAgent, IwdDeviceInfo, PassphraseRequest, WifiBackend,
+};
+use super::finish_unhandled_request;
+use super::helpers::{
+    create_device_proxy, device_powered, next_iwd_added, next_iwd_removed,
+    send_wifi_initial_state, setup_station_streams, setup_station_streams_with_retry,
+};
+
+/// How often to poll StationDiagnostic while connected (roaming detection)
+const WIFI_DIAGNOSTICS_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// How often to re-read iwd's network list between scans. iwd keeps
+/// GetOrderedNetworks current from its own background scans but signals
+/// nothing, so without this the list only updates when a scan ends.
+const WIFI_NETWORKS_REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Least time between two network lists. Scan ends, station state changes
+/// and known-network changes tend to arrive in bursts; they are collapsed
+/// into one list so the UI doesn't rebuild it for each.
+const WIFI_NETWORKS_MIN_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// How long a passphrase given with a connect waits for the agent to ask.
+/// Open and saved networks never ask, so it must not linger.
+const PRESET_PASSPHRASE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Where the iwd agent is exported on the bus
+const AGENT_PATH: &str = "/dev/neoden/wlcontrol/Agent";
+
+/// Passphrase given with a connect, for iwd's agent prompt on that network
+struct PresetPassphrase {
+    network_path: String,
+    passphrase: String,
+    expires: tokio::time::Instant,
+}
+
+pub enum WifiEvent {
+    PoweredChanged(bool),
+    ScanningChanged(bool),
+    StationStateChanged(String),
+    DiagnosticsPoll,
+    /// Re-read the network list between scans
+    NetworksPoll,
+    PassphraseRequest(PassphraseRequest),
+    DeviceAdded { object_path: String },
+    DeviceRemoved { object_path: String },
+    /// A KnownNetwork appeared or disappeared (possibly via an external tool)
+    KnownNetworksChanged,
+}
+
+#[derive(Default)]
+pub struct WifiStreams {
+    /// Passphrase requests from the iwd agent
+    pub passphrase_rx: Option<Receiver<PassphraseRequest>>,
+
+    pub device_powered: Option<zbus::PropertyStream<'static, bool>>,
+    pub station_scanning: Option<zbus::PropertyStream<'static, bool>>,
+    pub station_state: Option<zbus::PropertyStream<'static, String>>,
+
+    pub diagnostics_deadline: Option<tokio::time::Instant>,
+    pub networks_deadline: Option<tokio::time::Instant>,
+
+    /// iwd objects added or removed (adapter hot-plug, saved networks)
+    pub interfaces_added: Option<zbus::fdo::InterfacesAddedStream<'static>>,
+    pub interfaces_removed: Option<zbus::fdo::InterfacesRemovedStream<'static>>,
+}
+
+impl WifiStreams {
+    pub async fn next_event(&mut self) -> WifiEvent {
+        loop {
+            tokio::select! {
+                // WiFi diagnostics poll (BSSID tracking)
+                _ = async {
+                    match self.diagnostics_deadline {
+                        Some(deadline) => tokio::time::sleep_until(deadline).await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    return WifiEvent::DiagnosticsPoll;
+                }
+
+                // Network list refresh between scans
+                _ = async {
+                    match self.networks_deadline {
+                        Some(deadline) => tokio::time::sleep_until(deadline).await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    return WifiEvent::NetworksPoll;
+                }
+
+                // Device.Powered property change
+                Some(change) = async {
+                    match self.device_powered.as_mut() {
+                        Some(s) => s.next().await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    match change.get().await {
+                        Ok(powered) => return WifiEvent::PoweredChanged(powered),
+                        Err(e) => {
+                            tracing::warn!("Failed to get Device.Powered: {}", e);
+                            continue;
+                        }
+                    }
+                }
+
+                // Station.Scanning property change
+                Some(change) = async {
+                    match self.station_scanning.as_mut() {
+                        Some(s) => s.next().await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    match change.get().await {
+                        Ok(scanning) => return WifiEvent::ScanningChanged(scanning),
+                        Err(e) => {
+                            tracing::warn!("Failed to get Station.Scanning: {}", e);
+                            continue;
+                        }
+                    }
+                }
+
+                // Station.State property change
+                Some(change) = async {
+                    match self.station_state.as_mut() {
+                        Some(s) => s.next().await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    match change.get().await {
+                        Ok(state) => return WifiEvent::StationStateChanged(state),
+                        Err(e) => {
+                            tracing::warn!("Failed to get Station.State: {}", e);
+                            continue;
+                        }
+                    }
+                }
+
+                // Passphrase requests from iwd agent
+                Ok(request) = async {
+                    match self.passphrase_rx.as_ref() {
+                        Some(rx) => rx.recv().await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    return WifiEvent::PassphraseRequest(request);
+                }
+
+                // iwd InterfacesAdded (hot-plug)
+                Some(signal) = next_iwd_added(&mut self.interfaces_added) => {
+                    match signal.args() {
+                        Ok(args) => {
+                            let interfaces = args.interfaces_and_properties();
+                            if interfaces.contains_key("net.connman.iwd.Device") {
+                                return WifiEvent::DeviceAdded {
+                                    object_path: args.object_path().to_string(),
+                                };
+                            }
+                            // Saved via iwctl or another tool while we're running
+                            if interfaces.contains_key("net.connman.iwd.KnownNetwork") {
+                                return WifiEvent::KnownNetworksChanged;
+                            }
+                            continue;
+                        }
+                        Err(e) => {
+                            tracing::warn!("Failed to parse InterfacesAdded: {}", e);
+                            continue;
+                        }
+                    }
+                }
+
+                // iwd InterfacesRemoved (hot-plug)
+                Some(signal) = next_iwd_removed(&mut self.interfaces_removed) => {
+                    match signal.args() {
+                        Ok(args) => {
+                            if args.interfaces().contains(&"net.connman.iwd.Device") {
+                                return WifiEvent::DeviceRemoved {
+                                    object_path: args.object_path().to_string(),
+                                };
+                            }
+                            if args.interfaces().contains(&"net.connman.iwd.KnownNetwork") {
+                                return WifiEvent::KnownNetworksChanged;
+                            }
+                            continue;
+                        }
+                        Err(e) => {
+                            tracing::warn!("Failed to parse InterfacesRemoved: {}", e);
+                            continue;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub struct WifiState {
+    conn: zbus::Connection,
+    evt_tx: Sender<BackendEvent>,
+    backend: Option<WifiBackend>,
+    device_infos: Vec<IwdDeviceInfo>,
+    /// BSSID of the current connection, from the last diagnostics poll
+    bssid: Option<String>,
+    /// Access point the user pinned the connection to; iwd is asked to
+    /// roam back whenever it moves elsewhere
+    pinned_bssid: Option<String>,
+    /// A network list refresh is due at the next networks poll
+    networks_requested: bool,
+    /// Forget networks whose first connect failed (user preference)
+    forget_failed_new: bool,
+    /// Agent passphrase-prompt state, handed to each WifiBackend
+    passphrase_prompt: watch::Receiver<bool>,
+    /// The agent is registered with iwd's AgentManager. Not until there is
+    /// an adapter, which may only be hot-plugged after startup.
+    agent_registered: bool,
+    pending_passphrase_response: Option<oneshot::Sender<Option<String>>>,
+    preset_passphrase: Option<PresetPassphrase>,
+}
+
+impl WifiState {
+    /// Register the iwd agent, find the adapters, report whether WiFi is
+    /// available and send the initial state of the one picked
+    pub async fn init(
+        conn: &zbus::Connection,
+        evt_tx: Sender<BackendEvent>,
+    ) -> zbus::Result<(Self, WifiStreams)> {
+        let mut streams = WifiStreams::default();
+
+        // Create channel for passphrase requests from agent
+        let (passphrase_tx, passphrase_rx) = async_channel::unbounded::<PassphraseRequest>();
+        streams.passphrase_rx = Some(passphrase_rx);
+
+        // Tracks whether the agent is waiting on the user (pauses connect timeouts)
+        let (prompt_tx, passphrase_prompt) = watch::channel(false);
+
+        // Create and register iwd agent
+        let agent = IwdAgent::new(passphrase_tx, prompt_tx);
+        conn.object_server().at(AGENT_PATH, agent).await?;
+        tracing::info!("Registered iwd agent at {}", AGENT_PATH);
+
+        // Discover all WiFi devices via iwd
+        let device_infos = match find_all_iwd_devices(conn).await {
+            Ok(infos) => infos,
+            Err(e) => {
+                tracing::warn!("iwd service not available: {}. WiFi features disabled.", e);
+                Vec::new()
+            }
+        };
+
+        // WiFi is available if iwd is running and we found at least one device
+        let wifi_available = !device_infos.is_empty();
+        if wifi_available {
+            tracing::info!("WiFi available: found {} adapter(s) via iwd", device_infos.len());
+        } else {
+            tracing::info!("WiFi unavailable: iwd not running or no adapters found");
+        }
+        let _ = evt_tx.send(BackendEvent::WifiAvailable(wifi_available)).await;
+
+        // Pick initial adapter: prefer one that's already connected, fall back to first
+        let initial_device = {
+            let mut connected_device = None;
+            for info in &device_infos {
+                let path: OwnedObjectPath = info.device_path.as_str().try_into().unwrap();
+                if let Ok(station) = StationProxy::builder(conn).path(path).unwrap().build().await {
+                    if station.connected_network().await.is_ok() {
+                        connected_device = Some(info);
+                        break;
+                    }
+                }
+            }
+            connected_device.or(device_infos.first())
+        };
+
+        let _ = evt_tx
+            .send(BackendEvent::WifiDevices {
+                devices: device_infos.clone(),
+                active_path: initial_device.map(|d| d.device_path.clone()),
+            })
+            .await;
+
+        let backend: Option<WifiBackend> = initial_device.map(|info| {
+            tracing::info!(
+                "Selected initial WiFi adapter: {} ({})",
+                info.device_name,
+                info.device_path
+            );
+            let path: OwnedObjectPath = info.device_path.as_str().try_into().unwrap();
+            WifiBackend::new(conn.clone(), evt_tx.clone(), path, passphrase_prompt.clone())
+        });
+
+        // Register agent with iwd (agent is global, handles all devices)
+        let agent_registered = !device_infos.is_empty() && register_agent(conn, &evt_tx).await;
+
+        // Send initial state for active WiFi device
+        if let Some(ref w) = backend {
+            if let Some(path) = w.device_path() {
+                send_wifi_initial_state(conn, path, &evt_tx).await;
+            }
+            // iwd's cached results may be from a different place; no-op if powered off
+            w.scan_if_idle().await;
+        }
+
+        // Set up property change streams for Device, and for Station if powered
+        if let Some(path) = backend.as_ref().and_then(|w| w.device_path()) {
+            if let Some(device) = create_device_proxy(conn, path).await {
+                streams.device_powered = Some(device.receive_powered_changed().await);
+            }
+            let (scanning, state) = setup_station_streams(conn, path).await;
+            streams.station_scanning = scanning;
+            streams.station_state = state;
+        }
+
+        // Subscribe to iwd ObjectManager for hot-plug (adapter add/remove)
+        let iwd_obj_manager = zbus::fdo::ObjectManagerProxy::builder(conn)
+            .destination("net.connman.iwd")
+            .ok()
+            .and_then(|b| b.path("/").ok());
+        if let Some(builder) = iwd_obj_manager {
+            if let Ok(proxy) = builder.build().await {
+                streams.interfaces_added = proxy.receive_interfaces_added().await.ok();
+                streams.interfaces_removed = proxy.receive_interfaces_removed().await.ok();
+            }
+        }
+
+        // First diagnostics poll records the BSSID of an already-established connection
+        streams.diagnostics_deadline = backend.is_some().then(tokio::time::Instant::now);
+        // Keeps running across adapter changes; polls are no-ops without WiFi
+        streams.networks_deadline =
+            Some(tokio::time::Instant::now() + WIFI_NETWORKS_REFRESH_INTERVAL);
+
+        let state = Self {
+            conn: conn.clone(),
+            evt_tx,
+            backend,
+            device_infos,
+            bssid: None,
+            pinned_bssid: None,
+            networks_requested: false,
+            forget_failed_new: false,
+            passphrase_prompt,
+            agent_registered,
+            pending_passphrase_response: None,
+            preset_passphrase: None,
+        };
+        Ok((state, streams))
+    }
+
+    pub async fn handle_event(&mut self, event: WifiEvent, streams: &mut WifiStreams) {
+        match event {
+            WifiEvent::PoweredChanged(powered) => {
+                tracing::info!("Device powered changed: {}", powered);
+                let _ = self.evt_tx.send(BackendEvent::WifiPowered(powered)).await;
+
+                // Keep the adapter list's power state current, so the
+                // selector still shows this adapter as off after switching away
+                let active = self.backend.as_ref().and_then(|w| w.device_path()).cloned();
+                if let Some(active) = active {
+                    if let Some(info) = self
+                        .device_infos
+                        .iter_mut()
+                        .find(|i| i.device_path == active.as_str())
+                    {
+                        info.powered = powered;
+                    }
+                    self.send_devices().await;
+                }
+
+                if let Some(ref w) = self.backend {
+                    // Device power often follows the radio (rfkill, Adapter.Powered)
+                    w.send_radio_powered().await;
+                    if let Some(path) = w.device_path() {
+                        if powered {
+                            let (scanning, state) =
+                                setup_station_streams_with_retry(&self.conn, path).await;
+                            // Still powered but no Station: the driver didn't come
+                            // up, and the page would stay empty with WiFi "on"
+                            if scanning.is_none() && device_powered(&self.conn, path).await {
+                                tracing::warn!(
+                                    "Station interface never appeared on {}",
+                                    path.as_str()
+                                );
+                                let _ = self.evt_tx.send(BackendEvent::WifiStationMissing).await;
+                            }
+                            streams.station_scanning = scanning;
+                            streams.station_state = state;
+                            w.send_known_networks().await;
+                            // List is likely empty or stale right after power-on
+                            w.scan_if_idle().await;
+                        } else {
+                            streams.station_scanning = None;
+                            streams.station_state = None;
+                            w.send_no_networks().await;
+                        }
+                    }
+                }
+                if powered {
+                    self.request_networks(streams);
+                } else {
+                    self.networks_requested = false;
+                }
+            }
+
+            WifiEvent::ScanningChanged(scanning) => {
+                tracing::debug!("Station scanning changed: {}", scanning);
+                let _ = self
+                    .evt_tx
+                    .send(BackendEvent::WifiScanning(scanning))
+                    .await;
+                if !scanning {
+                    if let Some(ref w) = self.backend {
+                        w.send_known_networks().await;
+                    }
+                    self.request_networks(streams);
+                }
+            }
+
+            WifiEvent::StationStateChanged(state) => {
+                tracing::info!("Station state changed: {}", state);
+                if let Some(ref w) = self.backend {
+                    w.send_station_state(&state).await;
+                    w.send_connected_status().await;
+                }
+                match state.as_str() {
+                    "connected" | "roaming" => {
+                        if streams.diagnostics_deadline.is_none() {
+                            streams.diagnostics_deadline = Some(tokio::time::Instant::now());
+                        }
+                    }
+                    _ => {
+                        streams.diagnostics_deadline = None;
+                        self.clear_bssid().await;
+                    }
+                }
+                // Also on the way to another network, which skips "disconnected"
+                if !matches!(state.as_str(), "connected" | "roaming") {
+                    if let Some(ref w) = self.backend {
+                        w.forget_left_one_time_networks().await;
+                    }
+                }
+                // Connecting or dropping off changes which networks iwd
+                // lists and how it orders them
+                if matches!(state.as_str(), "connected" | "disconnected") {
+                    self.request_networks(streams);
+                }
+            }
+
+            WifiEvent::DiagnosticsPoll => {
+                self.handle_diagnostics_poll(streams).await;
+            }
+
+            WifiEvent::NetworksPoll => {
+                let requested = std::mem::take(&mut self.networks_requested);
+                if let Some(ref w) = self.backend {
+                    // A scan that just finished already sent a fresh list
+                    let fresh = w
+                        .networks_age()
+                        .is_some_and(|age| age < WIFI_NETWORKS_REFRESH_INTERVAL);
+                    if requested || !fresh {
+                        w.send_networks().await;
+                    }
+                }
+                streams.networks_deadline =
+                    Some(tokio::time::Instant::now() + WIFI_NETWORKS_REFRESH_INTERVAL);
+            }
+
+            WifiEvent::PassphraseRequest(request) => {
+                tracing::info!(
+                    "Passphrase request: {} ({})",
+                    request.network_name,
+                    request.network_path
+                );
+                let preset = self.preset_passphrase.take().filter(|p| {
+                    p.network_path == request.network_path
+                        && tokio::time::Instant::now() < p.expires
+                });
+                if let Some(preset) = preset {
+                    tracing::info!("Answering with the passphrase given for the connect");
+                    let _ = request.response_tx.send(Some(preset.passphrase));
+                    return;
+                }
+                self.pending_passphrase_response = Some(request.response_tx);
+                let _ = self
+                    .evt_tx
+                    .send(BackendEvent::PassphraseRequest {
+                        network_path: request.network_path,
+                        network_name: request.network_name,
+                        network_type: request.network_type,
+                    })
+                    .await;
+            }
+
+            WifiEvent::DeviceAdded { object_path } => {
+                self.handle_device_added(&object_path, streams).await;
+            }
+
+            WifiEvent::DeviceRemoved { object_path } => {
+                self.handle_device_removed(&object_path, streams).await;
+            }
+
+            WifiEvent::KnownNetworksChanged => {
+                tracing::debug!("iwd known networks changed, refreshing");
+                if let Some(ref w) = self.backend {
+                    w.send_known_networks().await;
+                }
+                // Re-reading networks refreshes each one's `known` flag
+                self.request_networks(streams);
+            }
+        }
+    }
+
+    /// Handle a Wifi* command or a passphrase response; the event loop
+    /// passes on every command that isn't for Bluetooth
+    pub async fn handle_command(&mut self, cmd: BackendCommand, streams: &mut WifiStreams) {
+        match cmd {
+            BackendCommand::PassphraseResponse { passphrase, remember } => {
+                if let Some(ref w) = self.backend {
+                    w.set_remember(remember);
+                }
+                if let Some(tx) = self.pending_passphrase_response.take() {
+                    let _ = tx.send(passphrase);
+                }
+            }
+            BackendCommand::WifiScan { thorough } => {
+                if let Some(ref w) = self.backend {
+                    if thorough {
+                        w.thorough_scan();
+                    } else {
+                        w.scan().await;
+                    }
+                }
+            }
+            BackendCommand::WifiScanIfIdle => {
+                if let Some(ref w) = self.backend {
+                    w.scan_if_idle().await;
+                }
+            }
+            BackendCommand::WifiRefreshScanning => {
+                if let Some(ref w) = self.backend {
+                    w.refresh_scanning().await;
+                }
+            }
+            BackendCommand::WifiRefresh => {
+                if let Some(ref w) = self.backend {
+                    w.refresh().await;
+                }
+                self.request_networks(streams);
+            }
+            BackendCommand::WifiSetAutoConnect { path, enabled } => {
+                if let Some(ref w) = self.backend {
+                    w.set_auto_connect(&path, enabled).await;
+                }
+            }
+            BackendCommand::WifiConnectHidden { name, request } => {
+                if let Some(ref w) = self.backend {
+                    w.connect_hidden(&name, request).await;
+                } else {
+                    finish_unhandled_request(&self.evt_tx, request).await;
+                }
+            }
+            BackendCommand::WifiConnect { path, remember, passphrase, request } => {
+                // Replaces one left from an earlier connect
+                self.preset_passphrase = passphrase.map(|p| PresetPassphrase {
+                    network_path: path.clone(),
+                    passphrase: p.0,
+                    expires: tokio::time::Instant::now() + PRESET_PASSPHRASE_TIMEOUT,
+                });
+                if let Some(ref w) = self.backend {
+                    w.connect(&path, remember, self.forget_failed_new, Some(request))
+                        .await;
+                } else {
+                    finish_unhandled_request(&self.evt_tx, request).await;
+                }
+            }
+            BackendCommand::WifiDisconnect { request } => {
+                if let Some(ref w) = self.backend {
+                    w.disconnect(request).await;
+                } else {
+                    finish_unhandled_request(&self.evt_tx, request).await;
+                }
+            }
+            BackendCommand::WifiForget { path, request } => {
+                if let Some(ref w) = self.backend {
+                    w.forget(&path, request).await;
+                } else {
+                    finish_unhandled_request(&self.evt_tx, request).await;
+                }
+            }
+            BackendCommand::WifiPinBssid { bssid } => {
+                self.handle_pin_bssid(bssid).await;
+            }
+            BackendCommand::WifiTestLatency { host } => {
+                if let Some(ref w) = self.backend {
+                    w.test_latency(host);
+                } else {
+                    let _ = self
+                        .evt_tx
+                        .send(BackendEvent::WifiLatencyFailed {
+                            host,
+                            error: "No WiFi adapter".into(),
+                        })
+                        .await;
+                }
+            }
+            BackendCommand::WifiUpdatePassword { path } => {
+                if let Some(ref w) = self.backend {
+                    w.update_password(&path).await;
+                }
+            }
+            BackendCommand::WifiForgetKnown { path, request } => {
+                if let Some(ref w) = self.backend {
+                    w.forget_known(&path, request).await;
+                } else {
+                    finish_unhandled_request(&self.evt_tx, request).await;
+                }
+            }
+            BackendCommand::WifiSetPowered { powered, control } => {
+                if let Some(ref w) = self.backend {
+                    w.set_powered(powered, control).await;
+                }
+            }
+            BackendCommand::WifiSetForgetFailedNew { enabled } => {
+                self.forget_failed_new = enabled;
+            }
+            BackendCommand::WifiRestartDevice => {
+                if let Some(ref w) = self.backend {
+                    w.restart_device().await;
+                }
+            }
+            BackendCommand::WifiSwitchAdapter { device_path } => {
+                self.handle_switch_adapter(&device_path, streams).await;
+            }
+            other => {
+                tracing::warn!("Not a WiFi command: {:?}", other);
+            }
+        }
+    }
+
+    /// Stop the active device's background tasks as the backend shuts down,
+    /// and forget its "connect once" networks while iwd can still be asked
+    pub async fn shutdown(&mut self) {
+        if let Some(ref w) = self.backend {
+            w.shutdown();
+            w.forget_one_time_networks().await;
+        }
+    }
+
+    /// Poll the current BSSID and report a roam when it changes mid-connection.
+    /// Stops polling once there is no connection to track.
+    async fn handle_diagnostics_poll(&mut self, streams: &mut WifiStreams) {
+        let bssid = match self.backend {
+            Some(ref w) => w.connected_bssid().await,
+            None => None,
+        };
+        let Some(bssid) = bssid else {
+            streams.diagnostics_deadline = None;
+            self.clear_bssid().await;
+            return;
+        };
+
+        match self.bssid.as_deref() {
+            Some(previous) if previous != bssid => {
+                tracing::info!("WiFi roamed from {} to {}", previous, bssid);
+                let _ = self
+                    .evt_tx
+                    .send(BackendEvent::WifiRoamed { bssid: bssid.clone() })
+                    .await;
+            }
+            Some(_) => {}
+            None => tracing::info!("WiFi connected to BSSID {}", bssid),
+        }
+        if self.bssid.as_deref() != Some(bssid.as_str()) {
+            let _ = self
+                .evt_tx
+                .send(BackendEvent::WifiBssid(Some(bssid.clone())))
+                .await;
+        }
+
+        // iwd has no way to stop roaming, so undo it after the fact
+        if let (Some(pinned), Some(w)) = (self.pinned_bssid.as_deref(), self.backend.as_ref()) {
+            if pinned != bssid {
+                if let Err(e) = w.roam_to(pinned).await {
+                    tracing::warn!("Failed to roam back to pinned BSSID {}: {}", pinned, e);
+                }
+            }
+        }
+        self.bssid = Some(bssid);
+        streams.diagnostics_deadline =
+            Some(tokio::time::Instant::now() + WIFI_DIAGNOSTICS_INTERVAL);
+    }
+
+    /// Schedule a network list refresh, no sooner than
+    /// WIFI_NETWORKS_MIN_INTERVAL after the last one. Requests made before it
+    /// is due are served by the same refresh.
+    fn request_networks(&mut self, streams: &mut WifiStreams) {
+        let Some(ref w) = self.backend else { return };
+        let wait = w.networks_age().map_or(std::time::Duration::ZERO, |age| {
+            WIFI_NETWORKS_MIN_INTERVAL.saturating_sub(age)
+        });
+        let due = tokio::time::Instant::now() + wait;
+        self.networks_requested = true;
+        streams.networks_deadline = Some(match streams.networks_deadline {
+            Some(deadline) => deadline.min(due),
+            None => due,
+        });
+    }
+
+    /// Answer an open passphrase request with "cancelled". The request
+    /// belongs to the device being replaced, and iwd's agent call would
+    /// otherwise never return.
+    async fn cancel_pending_passphrase(&mut self) {
+        if let Some(tx) = self.pending_passphrase_response.take() {
+            tracing::info!("Cancelling pending passphrase request");
+            let _ = tx.send(None);
+            let _ = self.evt_tx.send(BackendEvent::PassphraseCancelled).await;
+        }
+    }
+
+    /// Forget the current BSSID and any pin on it, telling the UI.
+    async fn clear_bssid(&mut self) {
+        if self.bssid.take().is_some() {
+            let _ = self.evt_tx.send(BackendEvent::WifiBssid(None)).await;
+        }
+        if self.pinned_bssid.take().is_some() {
+            let _ = self.evt_tx.send(BackendEvent::WifiBssidPinned(None)).await;
+        }
+    }
+
+    async fn handle_pin_bssid(&mut self, bssid: Option<String>) {
+        let Some(bssid) = bssid else {
+            tracing::info!("Unpinned WiFi access point");
+            self.pinned_bssid = None;
+            let _ = self.evt_tx.send(BackendEvent::WifiBssidPinned(None)).await;
+            return;
+        };
+        let available = match self.backend {
+            Some(ref w) => w.has_station_debug().await,
+            None => false,
+        };
+        if !available {
+            let _ = self
+                .evt_tx
+                .send(BackendEvent::WifiError(
+                    "Pinning an access point needs iwd in developer mode (iwd -E)".into(),
+                ))
+                .await;
+            let _ = self.evt_tx.send(BackendEvent::WifiBssidPinned(None)).await;
+            return;
+        }
+        tracing::info!("Pinned WiFi access point {}", bssid);
+        self.pinned_bssid = Some(bssid.clone());
+        let _ = self.evt_tx.send(BackendEvent::WifiBssidPinned(Some(bssid))).await;
+    }
+
+    async fn handle_device_added(&mut self, object_path: &str, streams: &mut WifiStreams) {
+        tracing::info!("iwd device added: {}", object_path);
+        let Ok(infos) = find_all_iwd_devices(&self.conn).await else {
+            return;
+        };
+        self.device_infos = infos;
+        self.update_agent().await;
+
+        // First adapter since startup, or since the last one went away
+        if self.backend.is_none() {
+            if let Some(info) = self.device_infos.first() {
+                tracing::info!("Using hot-plugged WiFi adapter {}", info.label());
+                let path: OwnedObjectPath = info.device_path.as_str().try_into().unwrap();
+                self.attach_device(&path, streams).await;
+                let _ = self.evt_tx.send(BackendEvent::WifiAvailable(true)).await;
+                send_wifi_initial_state(&self.conn, &path, &self.evt_tx).await;
+                streams.diagnostics_deadline = Some(tokio::time::Instant::now());
+            }
+        }
+
+        self.send_devices().await;
+    }
+
+    /// Send the adapter list and which one is in use
+    async fn send_devices(&self) {
+        let active = self
+            .backend
+            .as_ref()
+            .and_then(|w| w.device_path())
+            .map(|p| p.to_string());
+        let _ = self
+            .evt_tx
+            .send(BackendEvent::WifiDevices {
+                devices: self.device_infos.clone(),
+                active_path: active,
+            })
+            .await;
+    }
+
+    /// Register the agent or forget it was, after the adapter list changed
+    async fn update_agent(&mut self) {
+        match agent_step(self.agent_registered, self.device_infos.len()) {
+            AgentStep::Register => {
+                self.agent_registered = register_agent(&self.conn, &self.evt_tx).await;
+            }
+            AgentStep::Reset => self.agent_registered = false,
+            AgentStep::Keep => {}
+        }
+    }
+
+    async fn handle_device_removed(&mut self, removed_path: &str, streams: &mut WifiStreams) {
+        tracing::info!("iwd device removed: {}", removed_path);
+
+        let active_removed = self
+            .backend
+            .as_ref()
+            .and_then(|w| w.device_path())
+            .map(|p| p.as_str() == removed_path)
+            .unwrap_or(false);
+
+        match find_all_iwd_devices(&self.conn).await {
+            Ok(infos) => self.device_infos = infos,
+            // iwd itself may be going away
+            Err(_) => self.device_infos.retain(|info| info.device_path != removed_path),
+        }
+        self.update_agent().await;
+
+        if active_removed {
+            self.cancel_pending_passphrase().await;
+            // Stop the old device's connect task before anything else so it
+            // can't report stale results for an adapter that no longer exists
+            if let Some(old) = self.backend.take() {
+                old.abandon_connect("Adapter removed").await;
+                old.shutdown();
+                old.forget_one_time_networks().await;
+            }
+
+            if let Some(info) = self.device_infos.first() {
+                let path: OwnedObjectPath = info.device_path.as_str().try_into().unwrap();
+                self.attach_device(&path, streams).await;
+                send_wifi_initial_state(&self.conn, &path, &self.evt_tx).await;
+            } else {
+                self.backend = None;
+                streams.device_powered = None;
+                streams.station_scanning = None;
+                streams.station_state = None;
+                let _ = self
+                    .evt_tx
+                    .send(BackendEvent::WifiPowered(false))
+                    .await;
+                let _ = self
+                    .evt_tx
+                    .send(BackendEvent::WifiNetworks(vec![]))
+                    .await;
+            }
+            self.clear_bssid().await;
+            streams.diagnostics_deadline = self.backend.is_some().then(tokio::time::Instant::now);
+        }
+
+        self.send_devices().await;
+    }
+
+    async fn handle_switch_adapter(&mut self, device_path: &str, streams: &mut WifiStreams) {
+        tracing::info!("Switching WiFi adapter to {}", device_path);
+        self.cancel_pending_passphrase().await;
+        if let Some(ref w) = self.backend {
+            w.shutdown();
+            w.forget_one_time_networks().await;
+        }
+        let path: OwnedObjectPath = device_path.try_into().unwrap();
+        self.attach_device(&path, streams).await;
+        self.clear_bssid().await;
+        streams.diagnostics_deadline = Some(tokio::time::Instant::now());
+        send_wifi_initial_state(&self.conn, &path, &self.evt_tx).await;
+        // Both adapters can be connected; the UI must show this one's
+        // connection, not keep the previous adapter's
+        if let Some(ref w) = self.backend {
+            w.send_connection_state().await;
+        }
+        // Confirms the new active adapter, with fresh power states for all
+        if let Ok(infos) = find_all_iwd_devices(&self.conn).await {
+            self.device_infos = infos;
+        }
+        self.send_devices().await;
+    }
+
+    /// Make `path` the active device: a new WifiBackend and its Device and
+    /// Station streams
+    async fn attach_device(&mut self, path: &OwnedObjectPath, streams: &mut WifiStreams) {
+        self.backend = Some(WifiBackend::new(
+            self.conn.clone(),
+            self.evt_tx.clone(),
+            path.clone(),
+            self.passphrase_prompt.clone(),
+        ));
+        streams.device_powered = match create_device_proxy(&self.conn, path).await {
+            Some(device) => Some(device.receive_powered_changed().await),
+            None => None,
+        };
+        let (scanning, state) = setup_station_streams(&self.conn, path).await;
+        streams.station_scanning = scanning;
+        streams.station_state = state;
+    }
+}
+
+/// What to do about the iwd agent once the adapter list changed
+#[derive(Debug, PartialEq)]
+enum AgentStep {
+    Register,
+    /// Register again with the next adapter
+    Reset,
+    Keep,
+}
+
+/// Decide whether the agent needs registering. With no adapter at startup
+/// it never was, and connecting to a secured network would fail without a
+/// prompt. The last adapter going is also what an iwd restart looks like,
+/// and a new iwd knows no agent.
+fn agent_step(registered: bool, adapters: usize) -> AgentStep {
+    match (registered, adapters) {
+        (false, 1..) => AgentStep::Register,
+        (true, 0) => AgentStep::Reset,
+        _ => AgentStep::Keep,
+    }
+}
+
+/// Register the agent with iwd so it asks us for passphrases; without it
+/// connecting to secured networks fails. Returns whether it worked.
+async fn register_agent(conn: &zbus::Connection, evt_tx: &Sender<BackendEvent>) -> bool {
+    let error = match AgentManagerProxy::new(conn).await {
+        Ok(agent_manager) => match agent_manager
+            .register_agent(AGENT_PATH.try_into().unwrap())
+            .await
+        {
+            Ok(()) => {
+                tracing::info!("Registered agent with iwd");
+                return true;
+            }
+            // Same iwd as before the adapters went away
+            Err(zbus::Error::MethodError(name, _, _))
+                if name.as_str() == "net.connman.iwd.AlreadyExists" =>
+            {
+                tracing::debug!("Agent still registered with iwd");
+                return true;
+            }
+            Err(e) => {
+                tracing::warn!("Failed to register agent with iwd: {}", e);
+                "Cannot register password agent. Connecting to secured networks may fail."
+            }
+        },
+        Err(e) => {
+            tracing::warn!("Failed to connect to iwd AgentManager: {}", e);
+            "Cannot connect to iwd AgentManager. Connecting to secured networks may fail."
+        }
+    };
+    let _ = evt_tx.send(BackendEvent::WifiError(error.into())).await;
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn agent_follows_adapters_coming_and_going() {
+        // None at startup: nothing to register with yet
+        assert_eq!(agent_step(false, 0), AgentStep::Keep);
+        // Plugged in
+        assert_eq!(agent_step(false, 1), AgentStep::Register);
+        assert_eq!(agent_step(true, 2), AgentStep::Keep);
+        // The last one unplugged
+        assert_eq!(agent_step(true, 1), AgentStep::Keep);
+        assert_eq!(agent_step(true, 0), AgentStep::Reset);
+        // Plugged in again, or a registration that failed is retried
+        assert_eq!(agent_step(false, 1), AgentStep::Register);
+    }
+}
@@ -5,12 +5,49 @@ use gtk::{gio, glib};
 use std::sync::OnceLock;
 use tokio::runtime::Runtime;
 
-use super::bluetooth::BtDevice;
+/// A scan that hasn't finished after this long has likely lost its update
+const SCAN_WATCHDOG_SECS: u32 = 20;
+
+/// A row busy this long without a request in flight has missed the event
+/// that should have ended its operation
+const OPERATION_WATCHDOG: std::time::Duration = std::time::Duration::from_secs(30);
+/// Same for a row whose request is still unanswered; longer than any
+/// timeout the backend puts on the request itself
+const REQUEST_WATCHDOG: std::time::Duration = std::time::Duration::from_secs(90);
+/// How often busy rows are checked against the watchdog
+const OPERATION_WATCHDOG_CHECK_SECS: u32 = 5;
+/// A pairing that hasn't ended by then is called off; the device is likely
+/// not in pairing mode. Time with a PIN or confirmation prompt open doesn't
+/// count, and rows count down to it so a slow pairing isn't mistaken for a
+/// hung one.
+const PAIR_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// How long quitting waits for the backend to finish its shutdown work
+/// (forgetting "connect once" networks)
+const SHUTDOWN_WAIT: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// How long to look for a saved network that's out of range before giving up
+const OFFLINE_SEARCH_SECS: u32 = 20;
+
+/// iwd doesn't expose its autoconnect blacklist; this many failed connects
+/// to a network within the window mean it has most likely been blacklisted
+const WIFI_BLOCK_FAILURES: usize = 3;
+const WIFI_BLOCK_WINDOW: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+
+/// Least time between scans started by bringing the window to the front,
+/// and how fresh the WiFi list must be to skip one
+const FOCUS_SCAN_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+use super::bluetooth::{BtDevice, BtDeviceState};
 use super::store_helpers::{find_index, for_each, with_item};
 use super::types::{
-    BackendCommand, BackendEvent, BtDeviceData, BtPairingKind, KnownNetworkData, WifiNetworkData,
+    BackendCommand, BackendEvent, BtAgentCapability, BtAudioRole, BtDeviceData, BtPairingKind,
+    KnownNetworkData, Passphrase, RequestId, WifiNetworkData, WifiPowerControl, WifiSavedSort,
+    WifiStationState,
 };
-use super::wifi::WifiNetwork;
+use super::wifi::{WifiNetwork, WifiNetworkState};
+#[cfg(feature = "wifi")]
+use super::wifi::{network_config, network_export, SavedNetworkEntry};
 
 fn runtime() -> &'static Runtime {
     static RUNTIME: OnceLock<Runtime> = OnceLock::new();
@@ -22,8 +59,71 @@ fn runtime() -> &'static Runtime {
     })
 }
 
+/// What a request in flight acts on, so finishing it clears only that item
+#[derive(Debug, Clone)]
+enum RequestTarget {
+    /// Connect to a WiFi network path; the backend drops it for a newer attempt
+    WifiConnect(String),
+    /// Connect to a hidden network by SSID; dropped like WifiConnect
+    WifiHidden(String),
+    /// Disconnect or forget, by network (or KnownNetwork) path
+    Wifi(String),
+    /// Any device operation, by address
+    Bt(String),
+}
+
+impl RequestTarget {
+    /// A WiFi connect attempt, which a newer one replaces
+    fn is_wifi_connect(&self) -> bool {
+        matches!(self, Self::WifiConnect(_) | Self::WifiHidden(_))
+    }
+
+    /// Acts on the WiFi network (or KnownNetwork) at `path`
+    fn is_wifi_path(&self, path: &str) -> bool {
+        matches!(self, Self::WifiConnect(p) | Self::Wifi(p) if p == path)
+    }
+}
+
+/// The agent prompt (passphrase, PIN, confirmation) of one network or
+/// device. Its row isn't expired while the prompt is open, and afterwards
+/// the watchdog counts from when it closed: typing a passphrase can take
+/// longer than the limit.
+#[derive(Debug)]
+struct AgentPrompt {
+    /// Network path or device address
+    target: String,
+    closed: Option<std::time::Instant>,
+}
+
+impl AgentPrompt {
+    fn open(target: &str) -> Option<Self> {
+        Some(Self {
+            target: target.to_string(),
+            closed: None,
+        })
+    }
+
+    /// When the watchdog starts counting for a row busy since `since`;
+    /// None while the row's prompt is open
+    fn watchdog_start(
+        prompt: Option<&Self>,
+        target: &str,
+        since: std::time::Instant,
+    ) -> Option<std::time::Instant> {
+        match prompt {
+            Some(prompt) if prompt.target == target => prompt.closed.map(|c| c.max(since)),
+            _ => Some(since),
+        }
+    }
+}
+
 mod imp {
-    use super::{BackendCommand, BtDevice, KnownNetworkData, Sender, WifiNetwork};
+    use super::{
+        AgentPrompt, BackendCommand, BtAgentCapability, BtDevice, KnownNetworkData, RequestId,
+        RequestTarget, Sender, WifiNetwork, WifiPowerControl, WifiSavedSort, WifiStationState,
+    };
+    #[cfg(feature = "wifi")]
+    use super::{network_export, SavedNetworkEntry};
     use super::super::wifi::IwdDeviceInfo;
     use adw::prelude::*;
     use adw::subclass::prelude::*;
@@ -37,16 +137,104 @@ mod imp {
         pub bt_devices: gio::ListStore,
         /// Cached known networks from iwd, for cross-filtering with scan results
         pub cached_known: RefCell<Vec<KnownNetworkData>>,
+        /// Reading the saved networks failed; `cached_known` is empty, not
+        /// known to be empty
+        pub wifi_known_unreadable: RefCell<bool>,
         /// Cached (name, type) pairs from scan results, for filtering known networks
         pub cached_visible: RefCell<std::collections::HashSet<(String, String)>>,
+        /// Imported networks iwd hasn't saved yet (persisted)
+        #[cfg(feature = "wifi")]
+        pub imported_networks: RefCell<Vec<SavedNetworkEntry>>,
+        /// Unix time each saved network was first seen, by KnownNetwork path (persisted)
+        pub wifi_known_added: RefCell<std::collections::HashMap<String, i64>>,
+        /// Order of the saved networks list (persisted)
+        pub wifi_saved_sort: RefCell<WifiSavedSort>,
         pub wifi_available: RefCell<bool>,
         pub wifi_powered: RefCell<bool>,
+        /// iwd Adapter.Powered (radio) of the active device
+        pub wifi_radio_powered: RefCell<bool>,
+        /// Which iwd switch the WiFi toggle drives (persisted)
+        pub wifi_power_control: RefCell<WifiPowerControl>,
         pub wifi_scanning: RefCell<bool>,
+        /// Clears a scanning state whose completion never arrives
+        pub wifi_scan_watchdog: RefCell<Option<glib::SourceId>>,
+        /// Checks busy rows while any are busy
+        pub operation_watchdog: RefCell<Option<glib::SourceId>>,
+        /// Latest passphrase prompt, which holds off the watchdog
+        pub wifi_prompt: RefCell<Option<AgentPrompt>>,
+        /// Latest pairing prompt, which holds off the watchdog
+        pub bt_prompt: RefCell<Option<AgentPrompt>>,
+        /// Network (name, type, passphrase to answer the agent with) to
+        /// connect to once a scan finds it, and the timeout that gives up on it
+        pub wifi_offline_search:
+            RefCell<Option<(String, String, Option<Passphrase>, glib::SourceId)>>,
+        /// Scan button runs several merged scans instead of one (persisted)
+        pub wifi_thorough_scan: RefCell<bool>,
+        /// Ask before connecting to an unencrypted network (persisted)
+        pub wifi_warn_open: RefCell<bool>,
+        /// List networks in range under Connected / Saved / Other headers (persisted)
+        pub wifi_group_networks: RefCell<bool>,
+        /// Forget a new network again when its first connect fails (persisted)
+        pub wifi_forget_failed_new: RefCell<bool>,
+        /// Scan when the window comes to the front (persisted)
+        pub wifi_scan_on_focus: RefCell<bool>,
+        pub bt_scan_on_focus: RefCell<bool>,
+        /// When focusing the window last started a scan
+        pub last_focus_scan: RefCell<Option<std::time::Instant>>,
+        /// Progress of a running thorough scan as (cycle, cycles)
+        pub wifi_scan_cycle: RefCell<Option<(u32, u32)>>,
+        /// When the last scan finished; None means the list may be iwd's cache
+        pub wifi_last_scan: RefCell<Option<std::time::Instant>>,
+        /// Last Station state reported by iwd, applied to the active network
+        pub wifi_station_state: RefCell<WifiStationState>,
+        /// Access point of the current connection, and the one it's pinned to
+        pub wifi_bssid: RefCell<Option<String>>,
+        pub wifi_pinned_bssid: RefCell<Option<String>>,
+        /// Path of the connected network and when the connection was first seen
+        pub wifi_connected_since: RefCell<Option<(String, std::time::Instant)>>,
+        /// Path of the connected network kept listed after it dropped out
+        /// of scan results
+        pub wifi_retained_connected: RefCell<Option<String>>,
+        /// Recent failed connects by network path, to infer iwd's blacklist
+        pub wifi_connect_failures: RefCell<std::collections::HashMap<String, Vec<std::time::Instant>>>,
         pub bt_available: RefCell<bool>,
         pub bt_powered: RefCell<bool>,
         pub bt_discovering: RefCell<bool>,
+        /// Scan requested, waiting for BlueZ to confirm discovery started
+        pub bt_scan_starting: RefCell<bool>,
         pub bt_discoverable: RefCell<bool>,
+        pub bt_pairable: RefCell<bool>,
+        /// Keep unpaired devices listed after discovery stops (persisted)
+        pub bt_keep_discovered: RefCell<bool>,
+        /// Connect right after a successful pairing (persisted)
+        pub bt_connect_on_pair: RefCell<bool>,
+        /// Stop discovery while connecting or pairing (persisted)
+        pub bt_pause_scan_on_connect: RefCell<bool>,
+        /// List nameless discovered devices too (persisted)
+        pub bt_show_unnamed: RefCell<bool>,
+        /// Pairing agent IO capability (persisted)
+        pub bt_agent_capability: RefCell<BtAgentCapability>,
+        /// When each connected device's connection was first seen, by address
+        pub bt_connected_since: RefCell<std::collections::HashMap<String, std::time::Instant>>,
+        /// Connected A2DP sinks by address, oldest first
+        pub bt_audio_devices: RefCell<Vec<String>>,
+        /// Unix time each device last connected, by address (persisted)
+        pub bt_last_connected: RefCell<std::collections::HashMap<String, i64>>,
+        /// All radios off, either requested from the header or inferred
+        pub airplane_mode: RefCell<bool>,
+        /// Keep the app and backend running with no window open (persisted)
+        pub run_in_background: RefCell<bool>,
+        /// Set once Shutdown has been sent, so it goes out only once
+        pub shut_down: std::cell::Cell<bool>,
+        /// The backend quit without being asked to; nothing is live anymore
+        pub backend_stopped: RefCell<bool>,
+        /// Last id handed to a request sent to the backend
+        pub next_request_id: std::cell::Cell<u64>,
+        /// Requests the backend hasn't answered yet, and what they act on
+        pub pending_requests: RefCell<std::collections::HashMap<RequestId, RequestTarget>>,
         pub cmd_tx: OnceLock<Sender<BackendCommand>>,
+        /// Resolves once the backend task has ended
+        pub backend_done: RefCell<Option<tokio::sync::oneshot::Receiver<()>>>,
         /// All discovered WiFi adapters
         pub wifi_adapters: RefCell<Vec<IwdDeviceInfo>>,
         /// Device path of the currently active WiFi adapter
@@ -60,15 +248,95 @@ mod imp {
                 saved_networks: gio::ListStore::new::<WifiNetwork>(),
                 bt_devices: gio::ListStore::new::<BtDevice>(),
                 cached_known: RefCell::new(Vec::new()),
+                wifi_known_unreadable: RefCell::new(false),
                 cached_visible: RefCell::new(std::collections::HashSet::new()),
+                #[cfg(feature = "wifi")]
+                imported_networks: RefCell::new(network_export::load_placeholders()),
+                wifi_known_added: RefCell::new(crate::settings::wifi_known_added()),
+                wifi_saved_sort: RefCell::new(WifiSavedSort::from_key(
+                    &crate::settings::get_string("wifi-saved-sort", "name"),
+                )),
                 wifi_available: RefCell::new(false),
                 wifi_powered: RefCell::new(false),
+                wifi_radio_powered: RefCell::new(false),
+                wifi_power_control: RefCell::new(WifiPowerControl::from_key(
+                    &crate::settings::get_string("wifi-power-control", "device"),
+                )),
                 wifi_scanning: RefCell::new(false),
+                wifi_scan_watchdog: RefCell::new(None),
+                operation_watchdog: RefCell::new(None),
+                wifi_prompt: RefCell::new(None),
+                bt_prompt: RefCell::new(None),
+                wifi_offline_search: RefCell::new(None),
+                wifi_thorough_scan: RefCell::new(crate::settings::get_bool(
+                    "wifi-thorough-scan",
+                    false,
+                )),
+                wifi_warn_open: RefCell::new(crate::settings::get_bool("wifi-warn-open", false)),
+                wifi_group_networks: RefCell::new(crate::settings::get_bool(
+                    "wifi-group-networks",
+                    false,
+                )),
+                wifi_forget_failed_new: RefCell::new(crate::settings::get_bool(
+                    "wifi-forget-failed-new",
+                    false,
+                )),
+                wifi_scan_on_focus: RefCell::new(crate::settings::get_bool(
+                    "wifi-scan-on-focus",
+                    true,
+                )),
+                bt_scan_on_focus: RefCell::new(crate::settings::get_bool(
+                    "bt-scan-on-focus",
+                    false,
+                )),
+                last_focus_scan: RefCell::new(None),
+                wifi_scan_cycle: RefCell::new(None),
+                wifi_last_scan: RefCell::new(None),
+                wifi_station_state: RefCell::new(WifiStationState::default()),
+                wifi_bssid: RefCell::new(None),
+                wifi_pinned_bssid: RefCell::new(None),
+                wifi_connected_since: RefCell::new(None),
+                wifi_retained_connected: RefCell::new(None),
+                wifi_connect_failures: RefCell::new(std::collections::HashMap::new()),
                 bt_available: RefCell::new(false),
                 bt_powered: RefCell::new(false),
                 bt_discovering: RefCell::new(false),
+                bt_scan_starting: RefCell::new(false),
                 bt_discoverable: RefCell::new(false),
+                bt_pairable: RefCell::new(false),
+                bt_keep_discovered: RefCell::new(crate::settings::get_bool(
+                    "bt-keep-discovered",
+                    true,
+                )),
+                bt_connect_on_pair: RefCell::new(crate::settings::get_bool(
+                    "bt-connect-on-pair",
+                    true,
+                )),
+                bt_pause_scan_on_connect: RefCell::new(crate::settings::get_bool(
+                    "bt-pause-scan-on-connect",
+                    false,
+                )),
+                bt_show_unnamed: RefCell::new(crate::settings::get_bool(
+                    "bt-show-unnamed",
+                    false,
+                )),
+                bt_agent_capability: RefCell::new(BtAgentCapability::from_key(
+                    &crate::settings::get_string("bt-agent-capability", "keyboard-display"),
+                )),
+                bt_connected_since: RefCell::new(std::collections::HashMap::new()),
+                bt_audio_devices: RefCell::new(Vec::new()),
+                bt_last_connected: RefCell::new(crate::settings::bt_last_connected()),
+                airplane_mode: RefCell::new(false),
+                run_in_background: RefCell::new(crate::settings::get_bool(
+                    "run-in-background",
+                    false,
+                )),
+                shut_down: std::cell::Cell::new(false),
+                backend_stopped: RefCell::new(false),
+                next_request_id: std::cell::Cell::new(0),
+                pending_requests: RefCell::new(std::collections::HashMap::new()),
                 cmd_tx: OnceLock::new(),
+                backend_done: RefCell::new(None),
                 wifi_adapters: RefCell::new(Vec::new()),
                 active_wifi_device: RefCell::new(None),
             }
@@ -84,24 +352,43 @@ mod imp {
 
     impl ObjectImpl for WlcontrolManager {
         fn dispose(&self) {
-            tracing::debug!("WlcontrolManager disposing, sending shutdown");
-            // Send shutdown command to backend
-            if let Some(tx) = self.cmd_tx.get() {
-                let _ = tx.try_send(BackendCommand::Shutdown);
-            }
+            // The application shuts the backend down on quit; this only
+            // covers a manager dropped without that
+            tracing::debug!("WlcontrolManager disposing");
+            self.obj().shutdown();
         }
 
         fn properties() -> &'static [glib::ParamSpec] {
             static PROPERTIES: OnceLock<Vec<glib::ParamSpec>> = OnceLock::new();
             PROPERTIES.get_or_init(|| {
                 vec![
+                    glib::ParamSpecBoolean::builder("backend-stopped")
+                        .read_only()
+                        .build(),
                     glib::ParamSpecBoolean::builder("wifi-available")
                         .read_only()
                         .build(),
                     glib::ParamSpecBoolean::builder("wifi-powered").build(),
+                    glib::ParamSpecBoolean::builder("wifi-radio-powered")
+                        .read_only()
+                        .build(),
+                    glib::ParamSpecBoolean::builder("wifi-known-unreadable")
+                        .read_only()
+                        .build(),
+                    glib::ParamSpecString::builder("wifi-power-control").build(),
+                    glib::ParamSpecString::builder("wifi-saved-sort").build(),
                     glib::ParamSpecBoolean::builder("wifi-scanning")
                         .read_only()
                         .build(),
+                    glib::ParamSpecBoolean::builder("wifi-thorough-scan").build(),
+                    glib::ParamSpecBoolean::builder("wifi-warn-open").build(),
+                    glib::ParamSpecBoolean::builder("wifi-group-networks").build(),
+                    glib::ParamSpecBoolean::builder("wifi-forget-failed-new").build(),
+                    glib::ParamSpecBoolean::builder("wifi-scan-on-focus").build(),
+                    glib::ParamSpecBoolean::builder("bt-scan-on-focus").build(),
+                    glib::ParamSpecUInt::builder("wifi-scan-cycle")
+                        .read_only()
+                        .build(),
                     glib::ParamSpecUInt::builder("wifi-adapter-count")
                         .read_only()
                         .build(),
@@ -112,7 +399,18 @@ mod imp {
                     glib::ParamSpecBoolean::builder("bt-discovering")
                         .read_only()
                         .build(),
+                    glib::ParamSpecBoolean::builder("bt-scan-starting")
+                        .read_only()
+                        .build(),
                     glib::ParamSpecBoolean::builder("bt-discoverable").build(),
+                    glib::ParamSpecBoolean::builder("bt-pairable").build(),
+                    glib::ParamSpecBoolean::builder("bt-keep-discovered").build(),
+                    glib::ParamSpecBoolean::builder("bt-connect-on-pair").build(),
+                    glib::ParamSpecBoolean::builder("bt-pause-scan-on-connect").build(),
+                    glib::ParamSpecBoolean::builder("bt-show-unnamed").build(),
+                    glib::ParamSpecString::builder("bt-agent-capability").build(),
+                    glib::ParamSpecBoolean::builder("airplane-mode").build(),
+                    glib::ParamSpecBoolean::builder("run-in-background").build(),
                 ]
             })
         }
@@ -122,20 +420,55 @@ mod imp {
             SIGNALS.get_or_init(|| {
                 vec![
                     glib::subclass::Signal::builder("passphrase-requested")
-                        .param_types([String::static_type(), String::static_type()])
+                        .param_types([
+                            String::static_type(),
+                            String::static_type(),
+                            String::static_type(),
+                        ])
                         .build(),
+                    glib::subclass::Signal::builder("passphrase-cancelled").build(),
                     glib::subclass::Signal::builder("captive-portal")
                         .param_types([String::static_type()])
                         .build(),
+                    glib::subclass::Signal::builder("wifi-auth-failed")
+                        .param_types([String::static_type(), String::static_type()])
+                        .build(),
+                    glib::subclass::Signal::builder("wifi-roamed")
+                        .param_types([String::static_type()])
+                        .build(),
                     glib::subclass::Signal::builder("wifi-error")
                         .param_types([String::static_type()])
                         .build(),
+                    glib::subclass::Signal::builder("wifi-station-missing").build(),
+                    glib::subclass::Signal::builder("wifi-hidden-done")
+                        .param_types([
+                            u64::static_type(),    // request id
+                            String::static_type(), // SSID
+                            String::static_type(), // error, empty on success or when replaced
+                            bool::static_type(),   // no network has that name
+                        ])
+                        .build(),
+                    glib::subclass::Signal::builder("wifi-latency")
+                        .param_types([
+                            String::static_type(), // host
+                            u32::static_type(),    // round trip in ms
+                            String::static_type(), // error, empty on success
+                        ])
+                        .build(),
                     glib::subclass::Signal::builder("bt-error")
                         .param_types([String::static_type()])
                         .build(),
                     glib::subclass::Signal::builder("wifi-adapters-changed").build(),
                     glib::subclass::Signal::builder("wifi-network-updated").build(),
                     glib::subclass::Signal::builder("bt-device-updated").build(),
+                    glib::subclass::Signal::builder("bt-audio-conflict")
+                        .param_types([
+                            String::static_type(), // new address
+                            String::static_type(), // new name
+                            String::static_type(), // previous address
+                            String::static_type(), // previous name
+                        ])
+                        .build(),
                     glib::subclass::Signal::builder("bt-pairing")
                         .param_types([
                             String::static_type(), // kind
@@ -149,14 +482,40 @@ mod imp {
 
         fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
             match pspec.name() {
+                "backend-stopped" => self.backend_stopped.borrow().to_value(),
                 "wifi-available" => self.wifi_available.borrow().to_value(),
                 "wifi-powered" => self.wifi_powered.borrow().to_value(),
+                "wifi-radio-powered" => self.wifi_radio_powered.borrow().to_value(),
+                "wifi-known-unreadable" => self.wifi_known_unreadable.borrow().to_value(),
+                "wifi-power-control" => self.wifi_power_control.borrow().as_key().to_value(),
+                "wifi-saved-sort" => self.wifi_saved_sort.borrow().as_key().to_value(),
                 "wifi-scanning" => self.wifi_scanning.borrow().to_value(),
+                "wifi-thorough-scan" => self.wifi_thorough_scan.borrow().to_value(),
+                "wifi-warn-open" => self.wifi_warn_open.borrow().to_value(),
+                "wifi-group-networks" => self.wifi_group_networks.borrow().to_value(),
+                "wifi-forget-failed-new" => self.wifi_forget_failed_new.borrow().to_value(),
+                "wifi-scan-on-focus" => self.wifi_scan_on_focus.borrow().to_value(),
+                "bt-scan-on-focus" => self.bt_scan_on_focus.borrow().to_value(),
+                "wifi-scan-cycle" => self
+                    .wifi_scan_cycle
+                    .borrow()
+                    .map(|(cycle, _)| cycle)
+                    .unwrap_or(0)
+                    .to_value(),
                 "wifi-adapter-count" => (self.wifi_adapters.borrow().len() as u32).to_value(),
                 "bt-available" => self.bt_available.borrow().to_value(),
                 "bt-powered" => self.bt_powered.borrow().to_value(),
                 "bt-discovering" => self.bt_discovering.borrow().to_value(),
+                "bt-scan-starting" => self.bt_scan_starting.borrow().to_value(),
                 "bt-discoverable" => self.bt_discoverable.borrow().to_value(),
+                "bt-pairable" => self.bt_pairable.borrow().to_value(),
+                "bt-keep-discovered" => self.bt_keep_discovered.borrow().to_value(),
+                "bt-connect-on-pair" => self.bt_connect_on_pair.borrow().to_value(),
+                "bt-pause-scan-on-connect" => self.bt_pause_scan_on_connect.borrow().to_value(),
+                "bt-show-unnamed" => self.bt_show_unnamed.borrow().to_value(),
+                "bt-agent-capability" => self.bt_agent_capability.borrow().as_key().to_value(),
+                "airplane-mode" => self.airplane_mode.borrow().to_value(),
+                "run-in-background" => self.run_in_background.borrow().to_value(),
                 _ => unimplemented!(),
             }
         }
@@ -166,13 +525,66 @@ mod imp {
                 "wifi-powered" => {
                     let powered = value.get().unwrap();
                     self.wifi_powered.replace(powered);
+                    let control = *self.wifi_power_control.borrow();
+                    if let Some(tx) = self.cmd_tx.get() {
+                        let tx = tx.clone();
+                        glib::spawn_future_local(async move {
+                            let _ = tx
+                                .send(BackendCommand::WifiSetPowered { powered, control })
+                                .await;
+                        });
+                    }
+                }
+                "wifi-power-control" => {
+                    let key: String = value.get().unwrap();
+                    let control = WifiPowerControl::from_key(&key);
+                    self.wifi_power_control.replace(control);
+                    crate::settings::set_string("wifi-power-control", control.as_key());
+                }
+                "wifi-saved-sort" => {
+                    let key: String = value.get().unwrap();
+                    let sort = WifiSavedSort::from_key(&key);
+                    self.wifi_saved_sort.replace(sort);
+                    crate::settings::set_string("wifi-saved-sort", sort.as_key());
+                }
+                "wifi-thorough-scan" => {
+                    let thorough = value.get().unwrap();
+                    self.wifi_thorough_scan.replace(thorough);
+                    crate::settings::set_bool("wifi-thorough-scan", thorough);
+                }
+                "wifi-warn-open" => {
+                    let warn = value.get().unwrap();
+                    self.wifi_warn_open.replace(warn);
+                    crate::settings::set_bool("wifi-warn-open", warn);
+                }
+                "wifi-group-networks" => {
+                    let group = value.get().unwrap();
+                    self.wifi_group_networks.replace(group);
+                    crate::settings::set_bool("wifi-group-networks", group);
+                }
+                "wifi-forget-failed-new" => {
+                    let enabled = value.get().unwrap();
+                    self.wifi_forget_failed_new.replace(enabled);
+                    crate::settings::set_bool("wifi-forget-failed-new", enabled);
                     if let Some(tx) = self.cmd_tx.get() {
                         let tx = tx.clone();
                         glib::spawn_future_local(async move {
-                            let _ = tx.send(BackendCommand::WifiSetPowered { powered }).await;
+                            let _ = tx
+                                .send(BackendCommand::WifiSetForgetFailedNew { enabled })
+                                .await;
                         });
                     }
                 }
+                "wifi-scan-on-focus" => {
+                    let enabled = value.get().unwrap();
+                    self.wifi_scan_on_focus.replace(enabled);
+                    crate::settings::set_bool("wifi-scan-on-focus", enabled);
+                }
+                "bt-scan-on-focus" => {
+                    let enabled = value.get().unwrap();
+                    self.bt_scan_on_focus.replace(enabled);
+                    crate::settings::set_bool("bt-scan-on-focus", enabled);
+                }
                 "bt-powered" => {
                     let powered = value.get().unwrap();
                     self.bt_powered.replace(powered);
@@ -185,7 +597,11 @@ mod imp {
                 }
                 "bt-discoverable" => {
                     let discoverable = value.get().unwrap();
-                    self.bt_discoverable.replace(discoverable);
+                    // The switch binding writes back every state the backend
+                    // reports; only a real change goes to BlueZ
+                    if self.bt_discoverable.replace(discoverable) == discoverable {
+                        return;
+                    }
                     if let Some(tx) = self.cmd_tx.get() {
                         let tx = tx.clone();
                         glib::spawn_future_local(async move {
@@ -193,6 +609,100 @@ mod imp {
                         });
                     }
                 }
+                "bt-pairable" => {
+                    let pairable = value.get().unwrap();
+                    // Same write-back as discoverable
+                    if self.bt_pairable.replace(pairable) == pairable {
+                        return;
+                    }
+                    if let Some(tx) = self.cmd_tx.get() {
+                        let tx = tx.clone();
+                        glib::spawn_future_local(async move {
+                            let _ = tx.send(BackendCommand::BtSetPairable { pairable }).await;
+                        });
+                    }
+                }
+                "bt-keep-discovered" => {
+                    let keep = value.get().unwrap();
+                    self.bt_keep_discovered.replace(keep);
+                    crate::settings::set_bool("bt-keep-discovered", keep);
+                    if let Some(tx) = self.cmd_tx.get() {
+                        let tx = tx.clone();
+                        glib::spawn_future_local(async move {
+                            let _ = tx.send(BackendCommand::BtSetKeepDiscovered { keep }).await;
+                        });
+                    }
+                }
+                "bt-connect-on-pair" => {
+                    let enabled = value.get().unwrap();
+                    self.bt_connect_on_pair.replace(enabled);
+                    crate::settings::set_bool("bt-connect-on-pair", enabled);
+                    if let Some(tx) = self.cmd_tx.get() {
+                        let tx = tx.clone();
+                        glib::spawn_future_local(async move {
+                            let _ = tx.send(BackendCommand::BtSetConnectOnPair { enabled }).await;
+                        });
+                    }
+                }
+                "bt-pause-scan-on-connect" => {
+                    let enabled = value.get().unwrap();
+                    self.bt_pause_scan_on_connect.replace(enabled);
+                    crate::settings::set_bool("bt-pause-scan-on-connect", enabled);
+                    if let Some(tx) = self.cmd_tx.get() {
+                        let tx = tx.clone();
+                        glib::spawn_future_local(async move {
+                            let _ = tx
+                                .send(BackendCommand::BtSetPauseScanOnConnect { enabled })
+                                .await;
+                        });
+                    }
+                }
+                "bt-show-unnamed" => {
+                    let show = value.get().unwrap();
+                    self.bt_show_unnamed.replace(show);
+                    crate::settings::set_bool("bt-show-unnamed", show);
+                    if let Some(tx) = self.cmd_tx.get() {
+                        let tx = tx.clone();
+                        glib::spawn_future_local(async move {
+                            let _ = tx.send(BackendCommand::BtSetShowUnnamed { show }).await;
+                        });
+                    }
+                }
+                "bt-agent-capability" => {
+                    let key: String = value.get().unwrap();
+                    let capability = BtAgentCapability::from_key(&key);
+                    if self.bt_agent_capability.replace(capability) == capability {
+                        return;
+                    }
+                    crate::settings::set_string("bt-agent-capability", capability.as_key());
+                    if let Some(tx) = self.cmd_tx.get() {
+                        let tx = tx.clone();
+                        glib::spawn_future_local(async move {
+                            let _ = tx
+                                .send(BackendCommand::BtSetAgentCapability { capability })
+                                .await;
+                        });
+                    }
+                }
+                "run-in-background" => {
+                    let enabled = value.get().unwrap();
+                    self.run_in_background.replace(enabled);
+                    crate::settings::set_bool("run-in-background", enabled);
+                }
+                "airplane-mode" => {
+                    let enabled: bool = value.get().unwrap();
+                    // Also reached when the header toggle echoes an inferred change
+                    if self.airplane_mode.replace(enabled) == enabled {
+                        return;
+                    }
+                    let obj = self.obj();
+                    if *self.wifi_available.borrow() {
+                        obj.set_property("wifi-powered", !enabled);
+                    }
+                    if *self.bt_available.borrow() {
+                        obj.set_property("bt-powered", !enabled);
+                    }
+                }
                 _ => unimplemented!(),
             }
         }
@@ -214,17 +724,48 @@ impl WlcontrolManager {
         glib::Object::new()
     }
 
+    /// Start the backend. Only the first call does anything, so reopening
+    /// the app can't start a second backend.
     pub fn start(&self) {
+        if self.imp().cmd_tx.get().is_some() {
+            tracing::debug!("Backend already started");
+            return;
+        }
         let (cmd_tx, cmd_rx) = async_channel::bounded::<BackendCommand>(32);
         let (evt_tx, evt_rx) = async_channel::bounded::<BackendEvent>(32);
 
-        self.imp().cmd_tx.set(cmd_tx).unwrap();
+        // Checked above; the manager lives on the GTK thread, so nothing
+        // can set it in between
+        let _ = self.imp().cmd_tx.set(cmd_tx);
+
+        // Push persisted preferences; queued until the backend finishes init
+        self.send_command(BackendCommand::WifiSetForgetFailedNew {
+            enabled: *self.imp().wifi_forget_failed_new.borrow(),
+        });
+        self.send_command(BackendCommand::BtSetKeepDiscovered {
+            keep: *self.imp().bt_keep_discovered.borrow(),
+        });
+        self.send_command(BackendCommand::BtSetConnectOnPair {
+            enabled: *self.imp().bt_connect_on_pair.borrow(),
+        });
+        self.send_command(BackendCommand::BtSetPauseScanOnConnect {
+            enabled: *self.imp().bt_pause_scan_on_connect.borrow(),
+        });
+        self.send_command(BackendCommand::BtSetShowUnnamed {
+            show: *self.imp().bt_show_unnamed.borrow(),
+        });
+        self.send_command(BackendCommand::BtSetAgentCapability {
+            capability: *self.imp().bt_agent_capability.borrow(),
+        });
 
         // Spawn backend task
+        let (done_tx, done_rx) = tokio::sync::oneshot::channel();
+        self.imp().backend_done.replace(Some(done_rx));
         runtime().spawn(async move {
             if let Err(e) = run_backend(cmd_rx, evt_tx).await {
                 tracing::error!("Backend error: {}", e);
             }
+            let _ = done_tx.send(());
         });
 
         // Handle events on GTK main thread
@@ -233,15 +774,48 @@ impl WlcontrolManager {
             while let Ok(event) = evt_rx.recv().await {
                 manager.handle_event(event);
             }
+            // The channel closes when the backend task ends, which is only
+            // expected after shutdown()
+            if !manager.imp().shut_down.get() {
+                manager.backend_stopped();
+            }
         });
     }
 
+    /// The backend ended on its own: what it last reported is no longer
+    /// kept up to date, so stop showing it as live
+    fn backend_stopped(&self) {
+        tracing::error!(
+            "Backend stopped unexpectedly; WiFi and Bluetooth state is no longer updated. \
+             Restart wlcontrol to reconnect."
+        );
+        self.imp().pending_requests.borrow_mut().clear();
+        self.set_wifi_scanning(false);
+        self.handle_event(BackendEvent::WifiConnected(None));
+        self.handle_event(BackendEvent::WifiNetworks(Vec::new()));
+        self.handle_event(BackendEvent::WifiAvailable(false));
+        self.handle_event(BackendEvent::BtAvailable(false));
+        self.imp().backend_stopped.replace(true);
+        self.notify("backend-stopped");
+    }
+
     fn handle_event(&self, event: BackendEvent) {
         match event {
             BackendEvent::WifiAvailable(available) => {
+                if !available {
+                    self.drop_pending_requests(|t| !matches!(t, RequestTarget::Bt(_)));
+                }
                 self.set_wifi_available(available);
             }
             BackendEvent::BtAvailable(available) => {
+                if !available {
+                    // Adapter gone: nothing listed belongs to anything anymore
+                    self.drop_pending_requests(|t| matches!(t, RequestTarget::Bt(_)));
+                    self.set_bt_scan_starting(false);
+                    self.set_bt_discovering(false);
+                    self.clear_bt_devices();
+                    self.set_bt_powered(false);
+                }
                 self.set_bt_available(available);
             }
             BackendEvent::WifiDevices { devices, active_path } => {
@@ -263,46 +837,126 @@ impl WlcontrolManager {
                 self.emit_by_name::<()>("wifi-adapters-changed", &[]);
             }
             BackendEvent::WifiPowered(powered) => self.set_wifi_powered(powered),
+            BackendEvent::WifiStationMissing => {
+                tracing::error!("WiFi error: WiFi hardware did not initialize");
+                self.clear_wifi_operations();
+                self.emit_by_name::<()>("wifi-network-updated", &[]);
+                self.emit_by_name::<()>("wifi-station-missing", &[]);
+            }
+            BackendEvent::WifiRadioPowered(powered) => self.set_wifi_radio_powered(powered),
             BackendEvent::WifiScanning(scanning) => self.set_wifi_scanning(scanning),
+            BackendEvent::WifiThoroughScan(progress) => {
+                self.imp().wifi_scan_cycle.replace(progress);
+                self.notify("wifi-scan-cycle");
+            }
             BackendEvent::WifiNetworks(networks) => {
                 self.update_wifi_networks(networks);
+                self.apply_wifi_station_state();
                 self.rebuild_saved_networks();
+                self.resolve_offline_search();
             }
             BackendEvent::WifiKnownNetworks(known) => {
+                self.track_wifi_known_added(&known);
                 self.imp().cached_known.replace(known);
+                #[cfg(feature = "wifi")]
+                self.settle_imported_networks();
                 self.rebuild_saved_networks();
+                self.set_wifi_known_unreadable(false);
+            }
+            BackendEvent::WifiKnownNetworksUnreadable => {
+                self.set_wifi_known_unreadable(true);
             }
             BackendEvent::WifiConnected(path) => {
+                if let Some(ref path) = path {
+                    self.clear_wifi_connect_failures(path);
+                }
                 self.clear_wifi_operations();
                 self.update_wifi_connected(path);
+                self.apply_wifi_station_state();
                 self.emit_by_name::<()>("wifi-network-updated", &[]);
             }
             BackendEvent::WifiConnecting(path) => {
                 self.set_wifi_connecting(&path);
+                self.apply_wifi_station_state();
+                self.emit_by_name::<()>("wifi-network-updated", &[]);
+            }
+            BackendEvent::WifiState(state) => {
+                self.imp().wifi_station_state.replace(state);
+                self.apply_wifi_station_state();
+                self.emit_by_name::<()>("wifi-network-updated", &[]);
+            }
+            BackendEvent::WifiRoamed { bssid } => {
+                tracing::info!("Roamed to {}", bssid);
+                self.emit_by_name::<()>("wifi-roamed", &[&bssid]);
+            }
+            BackendEvent::WifiLatency { host, ms } => {
+                self.emit_by_name::<()>("wifi-latency", &[&host, &ms, &String::new()]);
+            }
+            BackendEvent::WifiLatencyFailed { host, error } => {
+                self.emit_by_name::<()>("wifi-latency", &[&host, &0u32, &error]);
+            }
+            BackendEvent::WifiBssid(bssid) => {
+                self.imp().wifi_bssid.replace(bssid);
+                self.apply_wifi_station_state();
+                self.emit_by_name::<()>("wifi-network-updated", &[]);
+            }
+            BackendEvent::WifiBssidPinned(bssid) => {
+                self.imp().wifi_pinned_bssid.replace(bssid);
+                self.apply_wifi_station_state();
                 self.emit_by_name::<()>("wifi-network-updated", &[]);
             }
             BackendEvent::WifiNetworkKnown { path } => {
                 self.set_wifi_network_known(&path);
                 self.emit_by_name::<()>("wifi-network-updated", &[]);
             }
+            BackendEvent::WifiAuthFailed { path } => {
+                self.record_wifi_connect_failure(&path);
+                let mut name = String::new();
+                self.set_wifi_network_flag(&path, |n| name = n.name());
+                tracing::warn!("Saved password rejected for {} ({})", name, path);
+                self.emit_by_name::<()>("wifi-auth-failed", &[&path, &name]);
+            }
             BackendEvent::PassphraseRequest {
                 network_path,
                 network_name,
+                network_type,
             } => {
                 tracing::info!(
                     "Passphrase requested for {} ({})",
                     network_name,
                     network_path
                 );
-                self.emit_by_name::<()>("passphrase-requested", &[&network_path, &network_name]);
+                self.imp().wifi_prompt.replace(AgentPrompt::open(&network_path));
+                self.emit_by_name::<()>(
+                    "passphrase-requested",
+                    &[&network_path, &network_name, &network_type],
+                );
+            }
+            BackendEvent::PassphraseCancelled => {
+                Self::close_prompt(&self.imp().wifi_prompt);
+                self.emit_by_name::<()>("passphrase-cancelled", &[]);
             }
             BackendEvent::CaptivePortal { url } => {
                 tracing::info!("Captive portal detected: {}", url);
+                with_item::<WifiNetwork, _, _>(
+                    &self.imp().wifi_networks,
+                    |n| n.connected(),
+                    |n| n.set_captive_portal(true),
+                );
+                self.emit_by_name::<()>("wifi-network-updated", &[]);
                 self.emit_by_name::<()>("captive-portal", &[&url]);
             }
+            BackendEvent::CaptivePortalCleared => {
+                for_each::<WifiNetwork, _>(&self.imp().wifi_networks, |n| {
+                    n.set_captive_portal(false)
+                });
+                self.emit_by_name::<()>("wifi-network-updated", &[]);
+            }
             BackendEvent::BtPowered(powered) => {
                 if !powered {
+                    self.set_bt_scan_starting(false);
                     self.set_bt_discovering(false);
+                    self.drop_pending_requests(|t| matches!(t, RequestTarget::Bt(_)));
                     self.clear_bt_operations();
                     self.reset_bt_connected_state();
                     self.remove_unpaired_bt_devices();
@@ -310,20 +964,45 @@ impl WlcontrolManager {
                 }
                 self.set_bt_powered(powered);
             }
-            BackendEvent::BtDiscovering(discovering) => self.set_bt_discovering(discovering),
+            BackendEvent::BtDiscovering(discovering) => {
+                // Also the answer to a scan that failed to start
+                self.set_bt_scan_starting(false);
+                self.set_bt_discovering(discovering);
+            }
             BackendEvent::BtDiscoverable(discoverable) => self.set_bt_discoverable(discoverable),
+            BackendEvent::BtPairable(pairable) => self.set_bt_pairable(pairable),
             BackendEvent::BtConnecting(address) => self.set_bt_connecting(&address),
             BackendEvent::BtDeviceAdded(data) => self.add_bt_device(&data),
             BackendEvent::BtDeviceChanged(data) => {
                 self.update_bt_device(&data);
+                // Flags otherwise stay until the operation reports back. A
+                // paired device showing up connected is done connecting, but
+                // Connected also turns true mid-pairing, so that doesn't count.
+                if data.paired && data.connected {
+                    self.set_bt_device_flag(&data.address, |d| d.set_connecting(false));
+                }
                 self.emit_by_name::<()>("bt-device-updated", &[]);
             }
-            BackendEvent::BtOperationDone { data, error } => {
+            BackendEvent::BtOperationDone { data, error, request } => {
+                // A request the watchdog gave up on was already reported
+                // (a timed-out pairing comes back as cancelled); its late
+                // answer only updates the device
+                let given_up = request
+                    .is_some_and(|r| self.imp().pending_requests.borrow_mut().remove(&r).is_none());
+                let error = error.filter(|_| !given_up);
+                // Shown passkeys and PINs get no answer; the pairing ending closes them
+                let prompted = self.imp().bt_prompt.borrow().as_ref().map(|p| p.target.clone());
+                if prompted.as_deref() == Some(data.address.as_str()) {
+                    Self::close_prompt(&self.imp().bt_prompt);
+                }
                 self.update_bt_device(&data);
-                self.set_bt_device_flag(&data.address, |d| {
-                    d.set_connecting(false);
-                    d.set_disconnecting(false);
-                });
+                // Another request on the device clears the flags when it ends
+                if !self.bt_request_pending(&data.address) {
+                    self.set_bt_device_flag(&data.address, |d| {
+                        d.set_connecting(false);
+                        d.set_disconnecting(false);
+                    });
+                }
                 self.emit_by_name::<()>("bt-device-updated", &[]);
                 if let Some(msg) = error {
                     self.emit_by_name::<()>("bt-error", &[&msg]);
@@ -339,8 +1018,13 @@ impl WlcontrolManager {
                     BtPairingKind::DisplayPin(code) => ("display-pin", code.as_str()),
                     BtPairingKind::Authorize => ("authorize", ""),
                 };
+                self.imp().bt_prompt.replace(AgentPrompt::open(&address));
                 self.emit_by_name::<()>("bt-pairing", &[&kind_str, &address, &code]);
             }
+            BackendEvent::RequestDone { request, error } => self.finish_request(request, error),
+            BackendEvent::WifiHiddenNotFound { request, error } => {
+                self.finish_hidden_not_found(request, error)
+            }
             BackendEvent::BtError(msg) => {
                 tracing::error!("BT error: {}", msg);
                 self.clear_bt_operations();
@@ -358,11 +1042,10 @@ impl WlcontrolManager {
 
     fn update_wifi_networks(&self, networks: Vec<WifiNetworkData>) {
         // Cache visible (name, type) pairs for filtering known networks
-        let visible: std::collections::HashSet<(String, String)> = networks
+        let mut visible: std::collections::HashSet<(String, String)> = networks
             .iter()
             .map(|n| (n.name.clone(), n.network_type.clone()))
             .collect();
-        self.imp().cached_visible.replace(visible);
 
         let store = &self.imp().wifi_networks;
 
@@ -375,7 +1058,7 @@ impl WlcontrolManager {
         }
 
         // Build new list, reusing existing GObjects (preserves operation flags)
-        let new_items: Vec<WifiNetwork> = networks
+        let mut new_items: Vec<WifiNetwork> = networks
             .iter()
             .map(|data| {
                 if let Some(network) = existing.remove(&data.path) {
@@ -396,7 +1079,80 @@ impl WlcontrolManager {
             })
             .collect();
 
-        store.splice(0, store.n_items(), &new_items);
+        // A network that stops broadcasting briefly can drop out of iwd's
+        // list while the connection holds; keep its row so the active
+        // connection doesn't seem to vanish
+        let connected_path = self
+            .imp()
+            .wifi_connected_since
+            .borrow()
+            .as_ref()
+            .map(|(path, _)| path.clone());
+        let retained = connected_path
+            .and_then(|path| existing.remove(&path))
+            .filter(|network| network.connected());
+        match retained {
+            Some(network) => {
+                tracing::debug!(
+                    "Keeping connected network {} missing from scan results",
+                    network.name()
+                );
+                visible.insert((network.name(), network.network_type()));
+                self.imp().wifi_retained_connected.replace(Some(network.path()));
+                new_items.insert(0, network);
+            }
+            None => {
+                self.imp().wifi_retained_connected.replace(None);
+            }
+        }
+        self.imp().cached_visible.replace(visible);
+
+        // Also lets old failures age out
+        for network in &new_items {
+            network.set_blocked(self.wifi_network_blocked(&network.path()));
+        }
+        // Same networks in the same order: the rows update themselves, so
+        // leave the list alone instead of rebuilding every row
+        let unchanged = store.n_items() as usize == new_items.len()
+            && new_items.iter().enumerate().all(|(i, network)| {
+                store.item(i as u32).as_ref() == Some(network.upcast_ref::<glib::Object>())
+            });
+        if !unchanged {
+            store.splice(0, store.n_items(), &new_items);
+        }
+    }
+
+    /// Note a failed connect; enough of them in a row flag the network as
+    /// likely blacklisted by iwd
+    fn record_wifi_connect_failure(&self, path: &str) {
+        self.imp()
+            .wifi_connect_failures
+            .borrow_mut()
+            .entry(path.to_string())
+            .or_default()
+            .push(std::time::Instant::now());
+        let blocked = self.wifi_network_blocked(path);
+        self.set_wifi_network_flag(path, |n| n.set_blocked(blocked));
+    }
+
+    fn clear_wifi_connect_failures(&self, path: &str) {
+        self.imp().wifi_connect_failures.borrow_mut().remove(path);
+        self.set_wifi_network_flag(path, |n| n.set_blocked(false));
+    }
+
+    /// Whether a network failed to connect often enough recently that iwd
+    /// has probably stopped autoconnecting to it. Drops expired failures.
+    fn wifi_network_blocked(&self, path: &str) -> bool {
+        let mut failures = self.imp().wifi_connect_failures.borrow_mut();
+        let Some(times) = failures.get_mut(path) else {
+            return false;
+        };
+        times.retain(|t| t.elapsed() < WIFI_BLOCK_WINDOW);
+        if times.is_empty() {
+            failures.remove(path);
+            return false;
+        }
+        times.len() >= WIFI_BLOCK_FAILURES
     }
 
     /// Rebuild saved_networks store from cached known networks,
@@ -413,13 +1169,83 @@ impl WlcontrolManager {
                     &data.path,
                     &data.name,
                     &data.network_type,
+                    data.hidden,
                 );
                 store.append(&network);
             }
         }
+        #[cfg(feature = "wifi")]
+        for entry in imp.imported_networks.borrow().iter() {
+            if !visible.contains(&(entry.name.clone(), entry.network_type.clone())) {
+                let network =
+                    WifiNetwork::new_imported(&entry.name, &entry.network_type, entry.hidden);
+                store.append(&network);
+            }
+        }
+    }
+
+    /// Remember when each saved network first showed up, for sorting by
+    /// recently added; iwd doesn't keep that
+    fn track_wifi_known_added(&self, known: &[KnownNetworkData]) {
+        let now = glib::real_time() / 1_000_000;
+        let mut added = self.imp().wifi_known_added.borrow_mut();
+        for data in known {
+            if !added.contains_key(&data.path) {
+                added.insert(data.path.clone(), now);
+                crate::settings::set_wifi_known_added(&data.path, now);
+            }
+        }
+        added.retain(|path, _| {
+            let still_saved = known.iter().any(|k| &k.path == path);
+            if !still_saved {
+                crate::settings::forget_wifi_known_added(path);
+            }
+            still_saved
+        });
+    }
+
+    /// Drop import placeholders that iwd has saved by now (after the first
+    /// connect), applying their auto-connect choice on the way.
+    #[cfg(feature = "wifi")]
+    fn settle_imported_networks(&self) {
+        let imp = self.imp();
+        let known = imp.cached_known.borrow();
+        let mut settled = Vec::new();
+        let before = imp.imported_networks.borrow().len();
+        imp.imported_networks.borrow_mut().retain(|entry| {
+            let Some(data) = known
+                .iter()
+                .find(|k| k.name == entry.name && k.network_type == entry.network_type)
+            else {
+                return true;
+            };
+            if data.auto_connect != entry.auto_connect {
+                settled.push(BackendCommand::WifiSetAutoConnect {
+                    path: data.path.clone(),
+                    enabled: entry.auto_connect,
+                });
+            }
+            false
+        });
+        let changed = imp.imported_networks.borrow().len() != before;
+        drop(known);
+        for cmd in settled {
+            self.send_command(cmd);
+        }
+        if changed {
+            network_export::save_placeholders(&imp.imported_networks.borrow());
+        }
     }
 
     fn update_wifi_connected(&self, connected_path: Option<String>) {
+        {
+            let mut since = self.imp().wifi_connected_since.borrow_mut();
+            match connected_path {
+                Some(ref path) if since.as_ref().is_some_and(|(p, _)| p == path) => {}
+                Some(ref path) => *since = Some((path.clone(), std::time::Instant::now())),
+                None => *since = None,
+            }
+        }
         for_each::<WifiNetwork, _>(&self.imp().wifi_networks, |network| {
             let is_connected = connected_path
                 .as_ref()
@@ -427,6 +1253,49 @@ impl WlcontrolManager {
                 .unwrap_or(false);
             network.set_connected(is_connected);
         });
+
+        // A row kept only because it was connected goes once it isn't
+        let retained = self.imp().wifi_retained_connected.borrow().clone();
+        if let Some(retained) = retained {
+            if connected_path.as_deref() != Some(retained.as_str()) {
+                let imp = self.imp();
+                imp.wifi_retained_connected.replace(None);
+                let store = &imp.wifi_networks;
+                if let Some(idx) = find_index::<WifiNetwork, _>(store, |n| n.path() == retained) {
+                    let network = store.item(idx).and_downcast::<WifiNetwork>().unwrap();
+                    store.remove(idx);
+                    // Not in range after all: list it with the saved networks
+                    imp.cached_visible
+                        .borrow_mut()
+                        .remove(&(network.name(), network.network_type()));
+                    self.rebuild_saved_networks();
+                }
+            }
+        }
+    }
+
+    /// Attach the current Station state to the network being connected/connected,
+    /// and reset it on every other network.
+    fn apply_wifi_station_state(&self) {
+        let state = *self.imp().wifi_station_state.borrow();
+        let bssid = self.imp().wifi_bssid.borrow().clone().unwrap_or_default();
+        let pinned = !bssid.is_empty()
+            && self.imp().wifi_pinned_bssid.borrow().as_deref() == Some(bssid.as_str());
+        for_each::<WifiNetwork, _>(&self.imp().wifi_networks, |network| {
+            if network.connected() || network.connecting() {
+                network.set_station_state(state);
+            } else {
+                network.set_station_state(WifiStationState::Disconnected);
+                network.set_captive_portal(false);
+            }
+            if network.connected() {
+                network.set_bssid(&bssid);
+                network.set_bssid_pinned(pinned);
+            } else {
+                network.set_bssid("");
+                network.set_bssid_pinned(false);
+            }
+        });
     }
 
     fn set_wifi_network_known(&self, path: &str) {
@@ -441,81 +1310,398 @@ impl WlcontrolManager {
         for_each::<WifiNetwork, _>(&self.imp().wifi_networks, |network| {
             network.set_connecting(network.path() == path);
         });
+        self.start_operation_watchdog();
     }
 
-    /// Clear all local operation flags on all WiFi networks.
+    /// Clear local operation flags on all WiFi networks that have no request
+    /// in flight; those are cleared when their request finishes.
     /// Called on connection events and errors as a conservative reset.
     fn clear_wifi_operations(&self) {
         for_each::<WifiNetwork, _>(&self.imp().wifi_networks, |network| {
-            network.set_connecting(false);
-            network.set_disconnecting(false);
-            network.set_forgetting(false);
+            if !self.wifi_request_pending(&network.path()) {
+                Self::clear_wifi_network_operations(network);
+            }
         });
     }
 
-    fn set_wifi_network_flag(&self, path: &str, f: impl FnOnce(&WifiNetwork)) {
-        with_item::<WifiNetwork, _, _>(&self.imp().wifi_networks, |n| n.path() == path, f);
-    }
-
-    fn send_command(&self, cmd: BackendCommand) {
-        if let Some(tx) = self.imp().cmd_tx.get() {
-            let tx = tx.clone();
-            glib::spawn_future_local(async move {
-                if let Err(e) = tx.send(cmd).await {
-                    tracing::error!("Failed to send command: {}", e);
-                }
-            });
-        }
-    }
-
-    pub fn wifi_networks(&self) -> gio::ListStore {
-        self.imp().wifi_networks.clone()
+    fn clear_wifi_network_operations(network: &WifiNetwork) {
+        network.set_connecting(false);
+        network.set_disconnecting(false);
+        network.set_forgetting(false);
     }
 
-    pub fn saved_networks(&self) -> gio::ListStore {
-        self.imp().saved_networks.clone()
+    fn wifi_request_pending(&self, path: &str) -> bool {
+        self.has_pending_request(|t| t.is_wifi_path(path))
     }
 
-    pub fn bt_devices(&self) -> gio::ListStore {
-        self.imp().bt_devices.clone()
+    /// Note a request about to be sent, to match the backend's answer to it
+    fn begin_request(&self, target: RequestTarget) -> RequestId {
+        let imp = self.imp();
+        let request = RequestId(imp.next_request_id.get() + 1);
+        imp.next_request_id.set(request.0);
+        imp.pending_requests.borrow_mut().insert(request, target);
+        self.start_operation_watchdog();
+        request
     }
 
-    pub fn wifi_available(&self) -> bool {
-        *self.imp().wifi_available.borrow()
+    fn has_pending_request(&self, f: impl Fn(&RequestTarget) -> bool) -> bool {
+        self.imp().pending_requests.borrow().values().any(f)
     }
 
-    fn set_wifi_available(&self, available: bool) {
-        if *self.imp().wifi_available.borrow() != available {
-            self.imp().wifi_available.replace(available);
-            self.notify("wifi-available");
+    /// Stop waiting on requests the backend won't answer (aborted or gone)
+    fn drop_pending_requests(&self, f: impl Fn(&RequestTarget) -> bool) {
+        let mut hidden = Vec::new();
+        self.imp().pending_requests.borrow_mut().retain(|request, t| {
+            if !f(t) {
+                return true;
+            }
+            if let RequestTarget::WifiHidden(name) = t {
+                hidden.push((*request, name.clone()));
+            }
+            false
+        });
+        // Whoever waits on a hidden connect learns it won't be answered
+        for (request, name) in hidden {
+            self.emit_by_name::<()>(
+                "wifi-hidden-done",
+                &[&request.0, &name, &String::new(), &false],
+            );
         }
     }
 
-    pub fn wifi_powered(&self) -> bool {
-        *self.imp().wifi_powered.borrow()
+    /// A hidden connect found no network by its name. Reported apart from
+    /// other failures so the hidden network dialog can ask for the name again.
+    fn finish_hidden_not_found(&self, request: RequestId, error: String) {
+        let name = match self.imp().pending_requests.borrow().get(&request) {
+            Some(RequestTarget::WifiHidden(name)) => Some(name.clone()),
+            _ => None,
+        };
+        let Some(name) = name else {
+            self.finish_request(request, Some(error));
+            return;
+        };
+        self.imp().pending_requests.borrow_mut().remove(&request);
+        tracing::error!("WiFi error: {}", error);
+        self.emit_by_name::<()>("wifi-hidden-done", &[&request.0, &name, &error, &true]);
     }
 
-    pub fn set_wifi_powered(&self, powered: bool) {
-        if *self.imp().wifi_powered.borrow() != powered {
-            self.imp().wifi_powered.replace(powered);
-            self.notify("wifi-powered");
+    /// The backend answered a request: clear the flags of the item it acted
+    /// on, unless another request on it is still in flight, and show its error
+    fn finish_request(&self, request: RequestId, error: Option<String>) {
+        let Some(target) = self.imp().pending_requests.borrow_mut().remove(&request) else {
+            tracing::debug!("Answer to unknown request {:?} (error: {:?})", request, error);
+            return;
+        };
+        let is_connect = matches!(target, RequestTarget::WifiConnect(_));
+        match target {
+            RequestTarget::WifiConnect(path) | RequestTarget::Wifi(path) => {
+                if is_connect && error.is_some() {
+                    self.record_wifi_connect_failure(&path);
+                }
+                if !self.wifi_request_pending(&path) {
+                    self.set_wifi_network_flag(&path, Self::clear_wifi_network_operations);
+                    with_item::<WifiNetwork, _, _>(
+                        &self.imp().saved_networks,
+                        |n| n.path() == path,
+                        Self::clear_wifi_network_operations,
+                    );
+                }
+                self.emit_by_name::<()>("wifi-network-updated", &[]);
+                if let Some(msg) = error {
+                    tracing::error!("WiFi error: {}", msg);
+                    self.emit_by_name::<()>("wifi-error", &[&msg]);
+                }
+            }
+            // The connection itself shows up through the station state; the
+            // caller decides how to show a failure
+            RequestTarget::WifiHidden(name) => {
+                if let Some(ref msg) = error {
+                    tracing::error!("WiFi error: {}", msg);
+                }
+                let error = error.unwrap_or_default();
+                self.emit_by_name::<()>("wifi-hidden-done", &[&request.0, &name, &error, &false]);
+            }
+            RequestTarget::Bt(address) => {
+                if !self.bt_request_pending(&address) {
+                    self.set_bt_device_flag(&address, Self::clear_bt_device_operations);
+                }
+                self.emit_by_name::<()>("bt-device-updated", &[]);
+                if let Some(msg) = error {
+                    tracing::error!("BT error: {}", msg);
+                    self.emit_by_name::<()>("bt-error", &[&msg]);
+                }
+            }
         }
     }
 
-    pub fn wifi_scanning(&self) -> bool {
-        *self.imp().wifi_scanning.borrow()
+    /// Check busy rows periodically until none are left. Operation flags
+    /// are cleared by the events that confirm the operation; this is the
+    /// last resort when one of those gets lost. Only runs once the backend
+    /// is started, as nothing could answer before that.
+    fn start_operation_watchdog(&self) {
+        let imp = self.imp();
+        if imp.cmd_tx.get().is_none() || imp.operation_watchdog.borrow().is_some() {
+            return;
+        }
+        let source = glib::timeout_add_seconds_local(
+            OPERATION_WATCHDOG_CHECK_SECS,
+            glib::clone!(
+                #[weak(rename_to = manager)]
+                self,
+                #[upgrade_or]
+                glib::ControlFlow::Break,
+                move || {
+                    if manager.expire_stale_operations(std::time::Instant::now()) {
+                        glib::ControlFlow::Continue
+                    } else {
+                        // Returning Break removes the source
+                        manager.imp().operation_watchdog.take();
+                        glib::ControlFlow::Break
+                    }
+                }
+            ),
+        );
+        imp.operation_watchdog.replace(Some(source));
     }
 
-    pub fn set_wifi_scanning(&self, scanning: bool) {
-        if *self.imp().wifi_scanning.borrow() != scanning {
-            self.imp().wifi_scanning.replace(scanning);
-            self.notify("wifi-scanning");
+    /// Mark the prompt in `cell` as answered or gone, from now
+    fn close_prompt(cell: &std::cell::RefCell<Option<AgentPrompt>>) {
+        if let Some(prompt) = cell.borrow_mut().as_mut() {
+            prompt.closed.get_or_insert_with(std::time::Instant::now);
         }
     }
 
-    pub fn bt_available(&self) -> bool {
-        *self.imp().bt_available.borrow()
-    }
+    /// Clear the flags of rows that have been busy past the watchdog limit,
+    /// along with any request on them that never got an answer. Returns
+    /// whether any row is still busy.
+    fn expire_stale_operations(&self, now: std::time::Instant) -> bool {
+        let imp = self.imp();
+        let limit = |pending: bool| {
+            if pending {
+                REQUEST_WATCHDOG
+            } else {
+                OPERATION_WATCHDOG
+            }
+        };
+        let mut busy = false;
+        let mut wifi_cleared = false;
+        for store in [&imp.wifi_networks, &imp.saved_networks] {
+            for_each::<WifiNetwork, _>(store, |network| {
+                let Some(since) = network.operation_since() else {
+                    return;
+                };
+                let path = network.path();
+                let prompt = imp.wifi_prompt.borrow();
+                let Some(since) = AgentPrompt::watchdog_start(prompt.as_ref(), &path, since)
+                else {
+                    busy = true;
+                    return;
+                };
+                drop(prompt);
+                let pending = self.wifi_request_pending(&path);
+                let elapsed = now.saturating_duration_since(since);
+                if elapsed < limit(pending) {
+                    busy = true;
+                    return;
+                }
+                tracing::warn!(
+                    "WiFi network {} ({}) still {:?} after {}s with no event ending it, \
+                     clearing (request pending: {})",
+                    network.name(),
+                    path,
+                    network.state(),
+                    elapsed.as_secs(),
+                    pending
+                );
+                self.drop_pending_requests(|t| t.is_wifi_path(&path));
+                Self::clear_wifi_network_operations(network);
+                wifi_cleared = true;
+            });
+        }
+        let mut bt_cleared = false;
+        let mut pairing_timed_out = Vec::new();
+        for_each::<BtDevice, _>(&imp.bt_devices, |device| {
+            let Some(since) = device.operation_since() else {
+                return;
+            };
+            let address = device.address();
+            let prompt = imp.bt_prompt.borrow();
+            let Some(since) = AgentPrompt::watchdog_start(prompt.as_ref(), &address, since) else {
+                busy = true;
+                return;
+            };
+            drop(prompt);
+            let pending = self.bt_request_pending(&address);
+            let pairing = device.state() == BtDeviceState::Pairing;
+            let elapsed = now.saturating_duration_since(since);
+            if elapsed < if pairing { PAIR_TIMEOUT } else { limit(pending) } {
+                busy = true;
+                return;
+            }
+            if pairing {
+                tracing::warn!("BT pairing with {} timed out after {}s", address, elapsed.as_secs());
+                pairing_timed_out.push(address.clone());
+            } else {
+                tracing::warn!(
+                    "BT device {} still {:?} after {}s with no event ending it, \
+                     clearing (request pending: {})",
+                    address,
+                    device.state(),
+                    elapsed.as_secs(),
+                    pending
+                );
+            }
+            self.drop_pending_requests(|t| matches!(t, RequestTarget::Bt(a) if *a == address));
+            Self::clear_bt_device_operations(device);
+            bt_cleared = true;
+        });
+        if wifi_cleared {
+            self.emit_by_name::<()>("wifi-network-updated", &[]);
+        }
+        if bt_cleared {
+            self.emit_by_name::<()>("bt-device-updated", &[]);
+        }
+        for address in pairing_timed_out {
+            self.send_command(BackendCommand::BtCancelPair { address });
+            let msg = "Pairing timed out. Make sure the device is in pairing mode.".to_string();
+            self.emit_by_name::<()>("bt-error", &[&msg]);
+        }
+        busy
+    }
+
+    fn set_wifi_network_flag(&self, path: &str, f: impl FnOnce(&WifiNetwork)) {
+        with_item::<WifiNetwork, _, _>(&self.imp().wifi_networks, |n| n.path() == path, f);
+    }
+
+    fn send_command(&self, cmd: BackendCommand) {
+        if let Some(tx) = self.imp().cmd_tx.get() {
+            let tx = tx.clone();
+            glib::spawn_future_local(async move {
+                if let Err(e) = tx.send(cmd).await {
+                    tracing::error!("Failed to send command: {}", e);
+                }
+            });
+        }
+    }
+
+    pub fn wifi_networks(&self) -> gio::ListStore {
+        self.imp().wifi_networks.clone()
+    }
+
+    pub fn bt_devices(&self) -> gio::ListStore {
+        self.imp().bt_devices.clone()
+    }
+
+    pub fn wifi_available(&self) -> bool {
+        *self.imp().wifi_available.borrow()
+    }
+
+    fn set_wifi_available(&self, available: bool) {
+        if *self.imp().wifi_available.borrow() != available {
+            self.imp().wifi_available.replace(available);
+            self.notify("wifi-available");
+        }
+    }
+
+    pub fn wifi_powered(&self) -> bool {
+        *self.imp().wifi_powered.borrow()
+    }
+
+    pub fn set_wifi_powered(&self, powered: bool) {
+        if *self.imp().wifi_powered.borrow() != powered {
+            self.imp().wifi_powered.replace(powered);
+            if !powered {
+                self.imp().wifi_last_scan.replace(None);
+            }
+            self.notify("wifi-powered");
+        }
+        self.sync_airplane_mode();
+    }
+
+    fn set_wifi_known_unreadable(&self, unreadable: bool) {
+        if self.imp().wifi_known_unreadable.replace(unreadable) != unreadable {
+            self.notify("wifi-known-unreadable");
+        }
+    }
+
+    fn set_wifi_radio_powered(&self, powered: bool) {
+        if *self.imp().wifi_radio_powered.borrow() != powered {
+            self.imp().wifi_radio_powered.replace(powered);
+            self.notify("wifi-radio-powered");
+        }
+        self.sync_airplane_mode();
+    }
+
+    pub fn bt_agent_capability(&self) -> BtAgentCapability {
+        *self.imp().bt_agent_capability.borrow()
+    }
+
+    pub fn set_bt_agent_capability(&self, capability: BtAgentCapability) {
+        self.set_property("bt-agent-capability", capability.as_key());
+    }
+
+    pub fn wifi_scanning(&self) -> bool {
+        *self.imp().wifi_scanning.borrow()
+    }
+
+    pub fn set_wifi_scanning(&self, scanning: bool) {
+        if *self.imp().wifi_scanning.borrow() != scanning {
+            self.imp().wifi_scanning.replace(scanning);
+            if scanning {
+                self.start_scan_watchdog();
+            } else {
+                self.stop_scan_watchdog();
+                // Covers iwd's periodic scans too, not just ones we requested
+                self.imp().wifi_last_scan.replace(Some(std::time::Instant::now()));
+            }
+            self.notify("wifi-scanning");
+        }
+    }
+
+    /// If the scanning property stream is lost (e.g. the Station interface
+    /// was recreated), the scan button would spin forever. Clear it after a
+    /// while and ask the backend for the real value.
+    fn start_scan_watchdog(&self) {
+        self.stop_scan_watchdog();
+        let source = glib::timeout_add_seconds_local_once(
+            SCAN_WATCHDOG_SECS,
+            glib::clone!(
+                #[weak(rename_to = manager)]
+                self,
+                move || {
+                    let imp = manager.imp();
+                    // Fired, so the source is already gone
+                    imp.wifi_scan_watchdog.take();
+                    if !*imp.wifi_scanning.borrow() {
+                        return;
+                    }
+                    tracing::warn!(
+                        "Scan still running after {}s, clearing and re-checking",
+                        SCAN_WATCHDOG_SECS
+                    );
+                    imp.wifi_scanning.replace(false);
+                    manager.notify("wifi-scanning");
+                    manager.send_command(BackendCommand::WifiRefreshScanning);
+                }
+            ),
+        );
+        self.imp().wifi_scan_watchdog.replace(Some(source));
+    }
+
+    fn stop_scan_watchdog(&self) {
+        if let Some(source) = self.imp().wifi_scan_watchdog.take() {
+            source.remove();
+        }
+    }
+
+    /// Time since the last completed scan, None if none finished yet
+    pub fn wifi_scan_age(&self) -> Option<std::time::Duration> {
+        self.imp().wifi_last_scan.borrow().map(|t| t.elapsed())
+    }
+
+    pub fn bt_available(&self) -> bool {
+        *self.imp().bt_available.borrow()
+    }
 
     fn set_bt_available(&self, available: bool) {
         if *self.imp().bt_available.borrow() != available {
@@ -533,6 +1719,41 @@ impl WlcontrolManager {
             self.imp().bt_powered.replace(powered);
             self.notify("bt-powered");
         }
+        self.sync_airplane_mode();
+    }
+
+    pub fn airplane_mode(&self) -> bool {
+        *self.imp().airplane_mode.borrow()
+    }
+
+    /// Follow power changes reported by the backend: any radio coming up
+    /// ends airplane mode, and a blocked WiFi radio (rfkill shows up as
+    /// Adapter.Powered off) with Bluetooth also off starts it. Only the
+    /// state is updated here, no power commands are sent.
+    fn sync_airplane_mode(&self) {
+        let imp = self.imp();
+        let wifi_available = *imp.wifi_available.borrow();
+        let bt_available = *imp.bt_available.borrow();
+        if !wifi_available && !bt_available {
+            return;
+        }
+        let wifi_on = wifi_available
+            && *imp.wifi_powered.borrow()
+            && *imp.wifi_radio_powered.borrow();
+        let bt_on = bt_available && *imp.bt_powered.borrow();
+        let wifi_blocked = wifi_available && !*imp.wifi_radio_powered.borrow();
+
+        let airplane = if wifi_on || bt_on {
+            false
+        } else if wifi_blocked {
+            true
+        } else {
+            return;
+        };
+        if *imp.airplane_mode.borrow() != airplane {
+            imp.airplane_mode.replace(airplane);
+            self.notify("airplane-mode");
+        }
     }
 
     fn remove_unpaired_bt_devices(&self) {
@@ -561,6 +1782,13 @@ impl WlcontrolManager {
         }
     }
 
+    fn set_bt_scan_starting(&self, starting: bool) {
+        if *self.imp().bt_scan_starting.borrow() != starting {
+            self.imp().bt_scan_starting.replace(starting);
+            self.notify("bt-scan-starting");
+        }
+    }
+
     pub fn bt_discoverable(&self) -> bool {
         *self.imp().bt_discoverable.borrow()
     }
@@ -572,132 +1800,214 @@ impl WlcontrolManager {
         }
     }
 
-    pub fn wifi_adapters(&self) -> Vec<super::wifi::IwdDeviceInfo> {
-        self.imp().wifi_adapters.borrow().clone()
-    }
-
-    pub fn active_wifi_device_path(&self) -> Option<String> {
-        self.imp().active_wifi_device.borrow().clone()
+    fn set_bt_pairable(&self, pairable: bool) {
+        if *self.imp().bt_pairable.borrow() != pairable {
+            self.imp().bt_pairable.replace(pairable);
+            self.notify("bt-pairable");
+        }
     }
 
-    pub fn set_active_wifi_adapter(&self, device_path: &str) {
-        self.imp().active_wifi_device.replace(Some(device_path.to_string()));
-        // Clear current models while backend loads new state
-        self.imp().wifi_networks.remove_all();
-        self.imp().saved_networks.remove_all();
-        self.send_command(BackendCommand::WifiSwitchAdapter {
-            device_path: device_path.to_string(),
+    pub fn request_wifi_scan(&self) {
+        self.send_command(BackendCommand::WifiScan {
+            thorough: *self.imp().wifi_thorough_scan.borrow(),
         });
     }
 
-    pub fn request_wifi_scan(&self) {
-        self.send_command(BackendCommand::WifiScan);
+    /// The window came to the front: scan if enabled, unless the data is
+    /// fresh. Rate-limited, so switching windows back and forth doesn't keep
+    /// the radios scanning.
+    pub fn scan_on_focus(&self) {
+        let imp = self.imp();
+        let recent =
+            |age: Option<std::time::Duration>| age.is_some_and(|a| a < FOCUS_SCAN_INTERVAL);
+        if recent(imp.last_focus_scan.borrow().map(|t| t.elapsed())) {
+            return;
+        }
+        let mut scanned = false;
+        if *imp.wifi_scan_on_focus.borrow()
+            && self.wifi_powered()
+            && !self.wifi_scanning()
+            && !recent(self.wifi_scan_age())
+        {
+            self.send_command(BackendCommand::WifiScanIfIdle);
+            scanned = true;
+        }
+        if *imp.bt_scan_on_focus.borrow() && self.bt_powered() && !self.bt_discovering() {
+            self.send_command(BackendCommand::BtScan { brief: true });
+            scanned = true;
+        }
+        if scanned {
+            imp.last_focus_scan.replace(Some(std::time::Instant::now()));
+        }
     }
 
-    pub fn request_wifi_connect(&self, path: &str) {
-        self.set_wifi_network_flag(path, |n| n.set_connecting(true));
-        self.emit_by_name::<()>("wifi-network-updated", &[]);
-        self.send_command(BackendCommand::WifiConnect {
-            path: path.to_string(),
-        });
+    /// Re-read saved networks, the connection and the network list from
+    /// iwd without scanning
+    pub fn request_wifi_refresh(&self) {
+        self.send_command(BackendCommand::WifiRefresh);
+    }
+
+    fn search_and_connect(&self, name: &str, network_type: &str, passphrase: Option<Passphrase>) {
+        self.cancel_offline_search();
+        let timeout = glib::timeout_add_seconds_local_once(
+            OFFLINE_SEARCH_SECS,
+            glib::clone!(
+                #[weak(rename_to = manager)]
+                self,
+                move || {
+                    // Fired, so the source is already gone
+                    let Some((name, ..)) = manager.imp().wifi_offline_search.take() else {
+                        return;
+                    };
+                    let msg = format!("\"{}\" was not found nearby", name);
+                    manager.emit_by_name::<()>("wifi-error", &[&msg]);
+                }
+            ),
+        );
+        self.imp().wifi_offline_search.replace(Some((
+            name.to_string(),
+            network_type.to_string(),
+            passphrase,
+            timeout,
+        )));
+        // The scan list may be ahead of the saved list already
+        self.resolve_offline_search();
+        if self.imp().wifi_offline_search.borrow().is_some() {
+            self.request_wifi_scan();
+        }
     }
 
-    pub fn request_wifi_disconnect(&self) {
-        // Set disconnecting flag on the currently connected network for instant UI feedback
-        with_item::<WifiNetwork, _, _>(
-            &self.imp().wifi_networks,
-            |n| n.connected(),
-            |n| n.set_disconnecting(true),
-        );
-        self.emit_by_name::<()>("wifi-network-updated", &[]);
-        self.send_command(BackendCommand::WifiDisconnect);
+    fn cancel_offline_search(&self) {
+        if let Some((.., timeout)) = self.imp().wifi_offline_search.take() {
+            timeout.remove();
+        }
     }
 
-    pub fn request_wifi_forget(&self, path: &str) {
-        self.set_wifi_network_flag(path, |n| n.set_forgetting(true));
-        self.emit_by_name::<()>("wifi-network-updated", &[]);
-        self.send_command(BackendCommand::WifiForget {
-            path: path.to_string(),
-        });
+    /// Connect to the network being searched for if it's now visible
+    fn resolve_offline_search(&self) {
+        let imp = self.imp();
+        let index = {
+            let search = imp.wifi_offline_search.borrow();
+            let Some((name, network_type, ..)) = search.as_ref() else {
+                return;
+            };
+            find_index::<WifiNetwork, _>(&imp.wifi_networks, |n| {
+                &n.name() == name && &n.network_type() == network_type
+            })
+        };
+        let Some(network) = index
+            .and_then(|i| imp.wifi_networks.item(i))
+            .and_downcast::<WifiNetwork>()
+        else {
+            return;
+        };
+        let Some((.., passphrase, timeout)) = imp.wifi_offline_search.take() else {
+            return;
+        };
+        timeout.remove();
+        // Connected or already busy with it by other means: nothing to do
+        if matches!(network.state(), WifiNetworkState::Saved | WifiNetworkState::Available) {
+            self.send_wifi_connect(&network.path(), true, passphrase);
+        }
     }
 
-    /// Forget a saved-offline network using its KnownNetwork D-Bus path directly
-    pub fn request_wifi_forget_known(&self, path: &str) {
-        // Set forgetting flag on the saved network for UI feedback
-        with_item::<WifiNetwork, _, _>(
-            &self.imp().saved_networks,
-            |n| n.path() == path,
-            |n| n.set_forgetting(true),
-        );
-        self.send_command(BackendCommand::WifiForgetKnown {
+    /// Connect to a WPA Personal network by SSID with a known passphrase, so
+    /// the agent is answered without a prompt (automation). The network is
+    /// saved. Like a search for a saved network, this waits for a scan to
+    /// find it, so it also works before the first network list arrives.
+    pub fn request_wifi_connect_with_passphrase(&self, name: &str, passphrase: &str) {
+        let passphrase = Passphrase(passphrase.to_string());
+        self.search_and_connect(name, "psk", Some(passphrase));
+    }
+
+    fn send_wifi_connect(&self, path: &str, remember: bool, passphrase: Option<Passphrase>) {
+        // The backend aborts an attempt still running, which then answers
+        // before the new one starts; that answer no longer matters, so the
+        // rows it was for stop spinning now
+        let superseded: Vec<String> = self
+            .imp()
+            .pending_requests
+            .borrow()
+            .values()
+            .filter_map(|t| match t {
+                RequestTarget::WifiConnect(p) => Some(p.clone()),
+                _ => None,
+            })
+            .collect();
+        self.drop_pending_requests(RequestTarget::is_wifi_connect);
+        for superseded in superseded {
+            if !self.wifi_request_pending(&superseded) {
+                self.set_wifi_network_flag(&superseded, Self::clear_wifi_network_operations);
+            }
+        }
+        self.set_wifi_network_flag(path, |n| n.set_connecting(true));
+        self.emit_by_name::<()>("wifi-network-updated", &[]);
+        let request = self.begin_request(RequestTarget::WifiConnect(path.to_string()));
+        self.send_command(BackendCommand::WifiConnect {
             path: path.to_string(),
+            remember,
+            passphrase,
+            request,
         });
     }
 
-    pub fn send_passphrase_response(&self, passphrase: Option<String>) {
-        self.send_command(BackendCommand::PassphraseResponse { passphrase });
-    }
-
-    pub fn request_bt_scan(&self) {
-        self.send_command(BackendCommand::BtScan);
+    /// Re-read adapter state and devices from BlueZ
+    pub fn request_bt_refresh(&self) {
+        self.send_command(BackendCommand::BtRefresh);
     }
 
-    pub fn request_bt_stop_scan(&self) {
-        self.send_command(BackendCommand::BtStopScan);
+    pub fn request_bt_connect(&self, path: &str) {
+        self.send_bt_connect(path, None);
     }
 
-    pub fn request_bt_connect(&self, path: &str) {
+    fn send_bt_connect(&self, path: &str, role: Option<BtAudioRole>) {
         self.set_bt_device_flag(path, |d| d.set_connecting(true));
+        let request = self.begin_request(RequestTarget::Bt(path.to_string()));
         self.send_command(BackendCommand::BtConnect {
             path: path.to_string(),
+            role,
+            request,
         });
     }
 
     pub fn request_bt_disconnect(&self, path: &str) {
         self.set_bt_device_flag(path, |d| d.set_disconnecting(true));
+        let request = self.begin_request(RequestTarget::Bt(path.to_string()));
         self.send_command(BackendCommand::BtDisconnect {
             path: path.to_string(),
+            request,
         });
     }
 
-    pub fn request_bt_pair(&self, path: &str) {
-        self.set_bt_device_flag(path, |d| d.set_connecting(true));
-        self.send_command(BackendCommand::BtPair {
-            path: path.to_string(),
-        });
-    }
-
-    pub fn request_bt_set_alias(&self, path: &str, alias: &str) {
-        self.send_command(BackendCommand::BtSetAlias {
-            path: path.to_string(),
-            alias: alias.to_string(),
-        });
-    }
-
-    pub fn request_bt_set_trusted(&self, path: &str, trusted: bool) {
-        self.send_command(BackendCommand::BtSetTrusted {
-            path: path.to_string(),
-            trusted,
-        });
-    }
-
-    pub fn request_bt_remove(&self, path: &str) {
-        self.set_bt_device_flag(path, |d| d.set_removing(true));
-        self.send_command(BackendCommand::BtRemove {
-            path: path.to_string(),
+    /// The listed paired device that connected most recently
+    pub fn last_bt_device(&self) -> Option<BtDevice> {
+        let last_connected = self.imp().bt_last_connected.borrow();
+        let mut latest: Option<(i64, BtDevice)> = None;
+        for_each::<BtDevice, _>(&self.imp().bt_devices, |device| {
+            if !device.paired() {
+                return;
+            }
+            let Some(&time) = last_connected.get(&device.address()) else {
+                return;
+            };
+            if latest.as_ref().map_or(true, |(t, _)| time > *t) {
+                latest = Some((time, device.clone()));
+            }
         });
+        latest.map(|(_, device)| device)
     }
 
-    pub fn send_bt_pairing_response(&self, accept: bool) {
-        self.send_command(BackendCommand::BtPairingResponse { accept });
-    }
-
-    pub fn send_bt_pairing_pin(&self, pin: Option<String>) {
-        self.send_command(BackendCommand::BtPairingPinResponse { pin });
-    }
-
-    pub fn send_bt_pairing_passkey(&self, passkey: Option<u32>) {
-        self.send_command(BackendCommand::BtPairingPasskeyResponse { passkey });
+    /// Connect the most recently used device, or disconnect it if connected
+    pub fn toggle_last_bt_device(&self) {
+        let Some(device) = self.last_bt_device() else {
+            return;
+        };
+        match device.state() {
+            BtDeviceState::Connected => self.request_bt_disconnect(&device.address()),
+            BtDeviceState::Paired => self.request_bt_connect(&device.address()),
+            // Already connecting, disconnecting or being removed
+            _ => {}
+        }
     }
 
     fn set_bt_device_flag(&self, address: &str, f: impl FnOnce(&BtDevice)) {
@@ -706,23 +2016,37 @@ impl WlcontrolManager {
 
     fn set_bt_connecting(&self, address: &str) {
         self.set_bt_device_flag(address, |d| d.set_connecting(true));
+        self.start_operation_watchdog();
     }
 
     /// Reset connected state on all devices (adapter powered off).
     fn reset_bt_connected_state(&self) {
+        self.imp().bt_connected_since.borrow_mut().clear();
+        self.imp().bt_audio_devices.borrow_mut().clear();
         for_each::<BtDevice, _>(&self.imp().bt_devices, |d| d.set_connected(false));
     }
 
-    /// Clear all local operation flags on all devices.
+    /// Clear local operation flags on all devices that have no request in
+    /// flight; those are cleared when their request finishes.
     /// Called on errors and state changes as a conservative reset.
     fn clear_bt_operations(&self) {
         for_each::<BtDevice, _>(&self.imp().bt_devices, |d| {
-            d.set_connecting(false);
-            d.set_disconnecting(false);
-            d.set_removing(false);
+            if !self.bt_request_pending(&d.address()) {
+                Self::clear_bt_device_operations(d);
+            }
         });
     }
 
+    fn bt_request_pending(&self, address: &str) -> bool {
+        self.has_pending_request(|t| matches!(t, RequestTarget::Bt(a) if a == address))
+    }
+
+    fn clear_bt_device_operations(d: &BtDevice) {
+        d.set_connecting(false);
+        d.set_disconnecting(false);
+        d.set_removing(false);
+    }
+
     fn find_bt_device_index(&self, address: &str) -> Option<u32> {
         find_index::<BtDevice, _>(&self.imp().bt_devices, |d| d.address() == address)
     }
@@ -734,6 +2058,11 @@ impl WlcontrolManager {
             self.emit_by_name::<()>("bt-device-updated", &[]);
             return;
         }
+        self.track_bt_connected_since(&data.address, data.connected);
+        // Already connected when first seen (e.g. at startup): nothing to ask
+        if data.connected && data.audio_sink {
+            self.imp().bt_audio_devices.borrow_mut().push(data.address.clone());
+        }
         let device = BtDevice::new(
             &data.address, // path = address (bluer uses addresses, not D-Bus paths)
             &data.address,
@@ -749,11 +2078,75 @@ impl WlcontrolManager {
         device.set_alias(&data.alias);
         device.set_trusted(data.trusted);
         device.set_battery_percentage(data.battery_percentage);
+        device.set_dual_audio(data.audio_sink && data.hands_free);
         device.set_rssi(data.rssi);
         self.imp().bt_devices.append(&device);
     }
 
+    /// Record when a device connection is first seen; runs before the device
+    /// is updated so its row already sees the timestamp.
+    fn track_bt_connected_since(&self, address: &str, connected: bool) {
+        let mut since = self.imp().bt_connected_since.borrow_mut();
+        if connected {
+            if let std::collections::hash_map::Entry::Vacant(entry) =
+                since.entry(address.to_string())
+            {
+                entry.insert(std::time::Instant::now());
+                self.record_bt_last_connected(address);
+            }
+        } else {
+            since.remove(address);
+        }
+    }
+
+    /// Remember when a device connected, for the "Last connected" subtitle
+    /// once it's gone. BlueZ doesn't keep this, so it's stored locally.
+    fn record_bt_last_connected(&self, address: &str) {
+        let now = glib::real_time() / 1_000_000;
+        self.imp()
+            .bt_last_connected
+            .borrow_mut()
+            .insert(address.to_string(), now);
+        crate::settings::set_bt_last_connected(address, now);
+    }
+
+    /// Track connected audio sinks; a second one connecting means audio may
+    /// be routed to either, so ask whether to drop the one already in use.
+    fn track_bt_audio_device(&self, data: &BtDeviceData) {
+        let previous = {
+            let mut audio = self.imp().bt_audio_devices.borrow_mut();
+            let known = audio.contains(&data.address);
+            if !(data.connected && data.audio_sink) {
+                audio.retain(|a| a != &data.address);
+                return;
+            }
+            if known {
+                return;
+            }
+            let previous = audio.last().cloned();
+            audio.push(data.address.clone());
+            previous
+        };
+        let Some(previous) = previous else {
+            return;
+        };
+
+        let name = if data.alias.is_empty() { &data.name } else { &data.alias };
+        let store = &self.imp().bt_devices;
+        let previous_name = find_index::<BtDevice, _>(store, |d| d.address() == previous)
+            .and_then(|idx| store.item(idx))
+            .and_then(|obj| obj.downcast::<BtDevice>().ok())
+            .map(|d| d.display_name())
+            .unwrap_or_else(|| previous.clone());
+        self.emit_by_name::<()>(
+            "bt-audio-conflict",
+            &[&data.address, name, &previous, &previous_name],
+        );
+    }
+
     fn update_bt_device(&self, data: &BtDeviceData) {
+        self.track_bt_connected_since(&data.address, data.connected);
+        self.track_bt_audio_device(data);
         with_item::<BtDevice, _, _>(
             &self.imp().bt_devices,
             |d| d.address() == data.address,
@@ -765,25 +2158,492 @@ impl WlcontrolManager {
                 device.set_trusted(data.trusted);
                 device.set_connected(data.connected);
                 device.set_battery_percentage(data.battery_percentage);
+                device.set_dual_audio(data.audio_sink && data.hands_free);
                 device.set_rssi(data.rssi);
             },
         );
     }
 
+    /// Drop every listed device, when the adapter they were seen on is gone
+    fn clear_bt_devices(&self) {
+        let imp = self.imp();
+        imp.bt_connected_since.borrow_mut().clear();
+        imp.bt_audio_devices.borrow_mut().clear();
+        imp.bt_devices.remove_all();
+        self.emit_by_name::<()>("bt-device-updated", &[]);
+    }
+
     fn remove_bt_device(&self, address: &str) {
+        self.imp().bt_connected_since.borrow_mut().remove(address);
+        self.imp().bt_audio_devices.borrow_mut().retain(|a| a != address);
         if let Some(idx) = self.find_bt_device_index(address) {
             self.imp().bt_devices.remove(idx);
         }
     }
 
-    /// Shutdown the backend gracefully
+    pub fn run_in_background(&self) -> bool {
+        *self.imp().run_in_background.borrow()
+    }
+
+    /// Shutdown the backend gracefully. Only the first call sends anything.
     pub fn shutdown(&self) {
+        if self.imp().shut_down.replace(true) {
+            return;
+        }
         tracing::info!("Requesting backend shutdown");
         if let Some(tx) = self.imp().cmd_tx.get() {
             // Use try_send to avoid blocking - if channel is full, backend will
             // shutdown anyway when the channel is dropped
             let _ = tx.try_send(BackendCommand::Shutdown);
         }
+        // The process exits right after quitting; give the backend a moment
+        // to finish, or iwd keeps networks that were meant to be forgotten
+        if let Some(done) = self.imp().backend_done.take() {
+            if runtime().block_on(tokio::time::timeout(SHUTDOWN_WAIT, done)).is_err() {
+                tracing::warn!("Backend did not finish shutting down in time");
+            }
+        }
+    }
+}
+
+/// Manager API only the WiFi UI uses; not built without the "wifi" feature
+#[cfg(feature = "wifi")]
+impl WlcontrolManager {
+    /// Order two saved-list entries by the chosen sort. Entries without the
+    /// data to sort by (never connected, import placeholders) go last, and
+    /// ties fall back to the name.
+    pub fn compare_saved_networks(&self, a: &WifiNetwork, b: &WifiNetwork) -> std::cmp::Ordering {
+        let imp = self.imp();
+        let by_name = || a.name().to_lowercase().cmp(&b.name().to_lowercase());
+        match *imp.wifi_saved_sort.borrow() {
+            WifiSavedSort::Name => by_name(),
+            WifiSavedSort::LastConnected => {
+                let known = imp.cached_known.borrow();
+                let last_connected = |n: &WifiNetwork| {
+                    known
+                        .iter()
+                        .find(|k| k.path == n.path())
+                        .and_then(|k| k.last_connected.clone())
+                };
+                // Newest first; None sorts below any time
+                last_connected(b)
+                    .cmp(&last_connected(a))
+                    .then_with(by_name)
+            }
+            WifiSavedSort::RecentlyAdded => {
+                let added = imp.wifi_known_added.borrow();
+                let time = |n: &WifiNetwork| added.get(&n.path()).copied();
+                time(b).cmp(&time(a)).then_with(by_name)
+            }
+        }
+    }
+
+    /// Saved networks and pending import placeholders, as an export file
+    pub fn export_saved_networks(&self) -> String {
+        let imp = self.imp();
+        let mut networks: Vec<SavedNetworkEntry> = imp
+            .cached_known
+            .borrow()
+            .iter()
+            .map(|k| SavedNetworkEntry {
+                name: k.name.clone(),
+                network_type: k.network_type.clone(),
+                auto_connect: k.auto_connect,
+                hidden: k.hidden,
+            })
+            .collect();
+        networks.extend(imp.imported_networks.borrow().iter().cloned());
+        network_export::to_json(&networks)
+    }
+
+    /// Add placeholders for networks from an export file that aren't saved
+    /// yet. Returns how many were added.
+    pub fn import_saved_networks(&self, json: &str) -> Result<usize, String> {
+        let entries = network_export::from_json(json)?;
+        let imp = self.imp();
+        let added = {
+            let known = imp.cached_known.borrow();
+            let mut imported = imp.imported_networks.borrow_mut();
+            let mut added = 0;
+            for entry in entries {
+                let is_known = known
+                    .iter()
+                    .any(|k| k.name == entry.name && k.network_type == entry.network_type);
+                let is_imported = imported
+                    .iter()
+                    .any(|e| e.name == entry.name && e.network_type == entry.network_type);
+                if is_known || is_imported {
+                    continue;
+                }
+                imported.push(entry);
+                added += 1;
+            }
+            added
+        };
+        if added > 0 {
+            network_export::save_placeholders(&imp.imported_networks.borrow());
+            self.rebuild_saved_networks();
+        }
+        Ok(added)
+    }
+
+    /// Whether iwd gives a saved network a random MAC address on each
+    /// connect. None when iwd's network file can't be read.
+    pub fn wifi_address_randomization(&self, network: &WifiNetwork) -> Option<bool> {
+        network_config::address_randomization(&network.name(), &network.network_type()).ok()
+    }
+
+    /// Change MAC randomization for a saved network in iwd's network file.
+    /// Reports failures (usually permissions) through "wifi-error".
+    pub fn set_wifi_address_randomization(&self, network: &WifiNetwork, enabled: bool) -> bool {
+        match network_config::set_address_randomization(
+            &network.name(),
+            &network.network_type(),
+            enabled,
+        ) {
+            Ok(()) => true,
+            Err(msg) => {
+                tracing::warn!("Failed to set MAC randomization: {}", msg);
+                self.emit_by_name::<()>("wifi-error", &[&msg]);
+                false
+            }
+        }
+    }
+
+    /// Static IPv4 settings of a saved network. None when it uses DHCP or
+    /// iwd's network file can't be read.
+    pub fn wifi_static_ipv4(&self, network: &WifiNetwork) -> Option<network_config::StaticIpv4> {
+        network_config::static_ipv4(&network.name(), &network.network_type())
+            .ok()
+            .flatten()
+    }
+
+    /// Give a saved network a static IPv4 configuration (None: DHCP) in
+    /// iwd's network file; iwd uses it from the next connect. Reports
+    /// failures (usually permissions) through "wifi-error".
+    pub fn set_wifi_static_ipv4(
+        &self,
+        network: &WifiNetwork,
+        config: Option<&network_config::StaticIpv4>,
+    ) -> bool {
+        match network_config::set_static_ipv4(&network.name(), &network.network_type(), config) {
+            Ok(()) => true,
+            Err(msg) => {
+                tracing::warn!("Failed to set static IP: {}", msg);
+                self.emit_by_name::<()>("wifi-error", &[&msg]);
+                false
+            }
+        }
+    }
+
+    /// Drop an import placeholder (its "Forget")
+    pub fn remove_imported_network(&self, name: &str, network_type: &str) {
+        let imp = self.imp();
+        imp.imported_networks
+            .borrow_mut()
+            .retain(|e| !(e.name == name && e.network_type == network_type));
+        network_export::save_placeholders(&imp.imported_networks.borrow());
+        self.rebuild_saved_networks();
+    }
+
+    pub fn saved_networks(&self) -> gio::ListStore {
+        self.imp().saved_networks.clone()
+    }
+
+    pub fn wifi_radio_powered(&self) -> bool {
+        *self.imp().wifi_radio_powered.borrow()
+    }
+
+    /// Whether the saved networks couldn't be read (as opposed to there being none)
+    pub fn wifi_known_unreadable(&self) -> bool {
+        *self.imp().wifi_known_unreadable.borrow()
+    }
+
+    pub fn wifi_power_control(&self) -> WifiPowerControl {
+        *self.imp().wifi_power_control.borrow()
+    }
+
+    pub fn set_wifi_power_control(&self, control: WifiPowerControl) {
+        self.set_property("wifi-power-control", control.as_key());
+    }
+
+    pub fn wifi_saved_sort(&self) -> WifiSavedSort {
+        *self.imp().wifi_saved_sort.borrow()
+    }
+
+    pub fn set_wifi_saved_sort(&self, sort: WifiSavedSort) {
+        self.set_property("wifi-saved-sort", sort.as_key());
+    }
+
+    /// Progress of a running thorough scan as (cycle, cycles)
+    pub fn wifi_scan_cycle(&self) -> Option<(u32, u32)> {
+        *self.imp().wifi_scan_cycle.borrow()
+    }
+
+    /// How long the network at `path` has been connected, if it is
+    pub fn wifi_connected_duration(&self, path: &str) -> Option<std::time::Duration> {
+        self.imp()
+            .wifi_connected_since
+            .borrow()
+            .as_ref()
+            .filter(|(p, _)| p == path)
+            .map(|(_, since)| since.elapsed())
+    }
+
+    pub fn wifi_adapters(&self) -> Vec<super::wifi::IwdDeviceInfo> {
+        self.imp().wifi_adapters.borrow().clone()
+    }
+
+    pub fn active_wifi_device_path(&self) -> Option<String> {
+        self.imp().active_wifi_device.borrow().clone()
+    }
+
+    pub fn set_active_wifi_adapter(&self, device_path: &str) {
+        let imp = self.imp();
+        imp.active_wifi_device.replace(Some(device_path.to_string()));
+        // Clear current models while backend loads new state
+        imp.wifi_networks.remove_all();
+        imp.saved_networks.remove_all();
+        // The connection belonged to the previous adapter; the new one
+        // reports its own, which may be another network or none
+        imp.wifi_connected_since.replace(None);
+        imp.wifi_retained_connected.replace(None);
+        imp.wifi_station_state.replace(WifiStationState::default());
+        self.drop_pending_requests(|t| !matches!(t, RequestTarget::Bt(_)));
+        self.send_command(BackendCommand::WifiSwitchAdapter {
+            device_path: device_path.to_string(),
+        });
+    }
+
+    /// Time a connection to `host` ("host" or "host:port", empty for the
+    /// default). The outcome is reported with "wifi-latency".
+    pub fn request_wifi_latency_test(&self, host: &str) {
+        self.send_command(BackendCommand::WifiTestLatency {
+            host: host.trim().to_string(),
+        });
+    }
+
+    /// Power-cycle the WiFi device after it came up without a Station
+    pub fn request_wifi_restart(&self) {
+        self.send_command(BackendCommand::WifiRestartDevice);
+    }
+
+    /// Scan for a saved network that isn't in range, and connect once it
+    /// shows up. Gives up with a "wifi-error" after OFFLINE_SEARCH_SECS.
+    pub fn request_wifi_search_and_connect(&self, name: &str, network_type: &str) {
+        self.search_and_connect(name, network_type, None);
+    }
+
+    /// Join a hidden network, which has no Network path to connect to.
+    /// The outcome is reported with "wifi-hidden-done" under the returned id.
+    pub fn request_wifi_connect_hidden(&self, name: &str) -> RequestId {
+        self.drop_pending_requests(RequestTarget::is_wifi_connect);
+        let request = self.begin_request(RequestTarget::WifiHidden(name.to_string()));
+        self.send_command(BackendCommand::WifiConnectHidden {
+            name: name.to_string(),
+            request,
+        });
+        request
+    }
+
+    /// Connect to a network; `remember: false` joins it without saving
+    pub fn request_wifi_connect(&self, path: &str, remember: bool) {
+        self.send_wifi_connect(path, remember, None);
+    }
+
+    /// Forget the failures behind a "temporarily blocked" network and
+    /// connect; an explicit connect isn't subject to iwd's blacklist
+    pub fn request_wifi_clear_and_retry(&self, path: &str) {
+        self.clear_wifi_connect_failures(path);
+        self.request_wifi_connect(path, true);
+    }
+
+    pub fn request_wifi_disconnect(&self) {
+        self.drop_pending_requests(RequestTarget::is_wifi_connect);
+        // Set disconnecting flag on the currently connected network for instant UI feedback
+        let mut path = String::new();
+        with_item::<WifiNetwork, _, _>(
+            &self.imp().wifi_networks,
+            |n| n.connected(),
+            |n| {
+                n.set_disconnecting(true);
+                path = n.path();
+            },
+        );
+        self.emit_by_name::<()>("wifi-network-updated", &[]);
+        let request = self.begin_request(RequestTarget::Wifi(path));
+        self.send_command(BackendCommand::WifiDisconnect { request });
+    }
+
+    pub fn request_wifi_forget(&self, path: &str) {
+        self.set_wifi_network_flag(path, |n| n.set_forgetting(true));
+        self.emit_by_name::<()>("wifi-network-updated", &[]);
+        let request = self.begin_request(RequestTarget::Wifi(path.to_string()));
+        self.send_command(BackendCommand::WifiForget {
+            path: path.to_string(),
+            request,
+        });
+    }
+
+    /// Pin the connection to its current access point, or unpin it
+    pub fn request_wifi_pin_bssid(&self, pin: bool) {
+        let bssid = if pin {
+            let Some(bssid) = self.imp().wifi_bssid.borrow().clone() else { return };
+            Some(bssid)
+        } else {
+            None
+        };
+        self.send_command(BackendCommand::WifiPinBssid { bssid });
+    }
+
+    /// Replace a saved network's password: backend forgets it and reconnects,
+    /// which brings up the passphrase prompt
+    pub fn request_wifi_update_password(&self, path: &str) {
+        self.drop_pending_requests(RequestTarget::is_wifi_connect);
+        self.set_wifi_network_flag(path, |n| n.set_connecting(true));
+        self.emit_by_name::<()>("wifi-network-updated", &[]);
+        self.send_command(BackendCommand::WifiUpdatePassword {
+            path: path.to_string(),
+        });
+    }
+
+    /// Forget a saved-offline network using its KnownNetwork D-Bus path directly
+    pub fn request_wifi_forget_known(&self, path: &str) {
+        // Set forgetting flag on the saved network for UI feedback
+        with_item::<WifiNetwork, _, _>(
+            &self.imp().saved_networks,
+            |n| n.path() == path,
+            |n| n.set_forgetting(true),
+        );
+        let request = self.begin_request(RequestTarget::Wifi(path.to_string()));
+        self.send_command(BackendCommand::WifiForgetKnown {
+            path: path.to_string(),
+            request,
+        });
+    }
+
+    pub fn send_passphrase_response(&self, passphrase: Option<String>, remember: bool) {
+        Self::close_prompt(&self.imp().wifi_prompt);
+        self.send_command(BackendCommand::PassphraseResponse { passphrase, remember });
+    }
+
+    /// Whether to confirm before connecting to an unencrypted network
+    pub fn wifi_warn_open(&self) -> bool {
+        *self.imp().wifi_warn_open.borrow()
+    }
+
+    /// Whether the networks in range are listed in sections
+    pub fn wifi_group_networks(&self) -> bool {
+        *self.imp().wifi_group_networks.borrow()
+    }
+}
+
+/// Manager API only the Bluetooth UI uses; not built without the "bluetooth"
+/// feature
+#[cfg(feature = "bluetooth")]
+impl WlcontrolManager {
+    /// Time left before pairing `device` is called off; None while its
+    /// agent prompt is open, as the timeout waits for the answer
+    pub fn bt_pair_time_left(&self, device: &BtDevice) -> Option<std::time::Duration> {
+        let since = device.operation_since()?;
+        let prompt = self.imp().bt_prompt.borrow();
+        let start = AgentPrompt::watchdog_start(prompt.as_ref(), &device.address(), since)?;
+        Some(PAIR_TIMEOUT.saturating_sub(start.elapsed()))
+    }
+
+    /// How long the device at `address` has been connected, if it is
+    pub fn bt_connected_duration(&self, address: &str) -> Option<std::time::Duration> {
+        self.imp()
+            .bt_connected_since
+            .borrow()
+            .get(address)
+            .map(|since| since.elapsed())
+    }
+
+    /// Unix time the device last connected, if it ever did
+    pub fn bt_last_connected(&self, address: &str) -> Option<i64> {
+        self.imp().bt_last_connected.borrow().get(address).copied()
+    }
+
+    pub fn bt_scan_starting(&self) -> bool {
+        *self.imp().bt_scan_starting.borrow()
+    }
+
+    pub fn request_bt_scan(&self) {
+        self.set_bt_scan_starting(true);
+        self.send_command(BackendCommand::BtScan { brief: false });
+    }
+
+    pub fn request_bt_stop_scan(&self) {
+        self.send_command(BackendCommand::BtStopScan);
+    }
+
+    /// Re-read powered, discoverable and pairable state from BlueZ
+    pub fn request_bt_adapter_state(&self) {
+        self.send_command(BackendCommand::BtSyncAdapterState);
+    }
+
+    /// Connect a headset for listening or for calls only, when the
+    /// automatic choice brings up the wrong profile
+    pub fn request_bt_connect_role(&self, path: &str, role: BtAudioRole) {
+        self.send_bt_connect(path, Some(role));
+    }
+
+    pub fn request_bt_pair(&self, path: &str) {
+        self.set_bt_device_flag(path, |d| d.set_connecting(true));
+        let request = self.begin_request(RequestTarget::Bt(path.to_string()));
+        self.send_command(BackendCommand::BtPair {
+            path: path.to_string(),
+            request,
+        });
+    }
+
+    pub fn request_bt_cancel_pair(&self, address: &str) {
+        Self::close_prompt(&self.imp().bt_prompt);
+        self.send_command(BackendCommand::BtCancelPair {
+            address: address.to_string(),
+        });
+    }
+
+    pub fn request_bt_set_alias(&self, path: &str, alias: &str) {
+        self.send_command(BackendCommand::BtSetAlias {
+            path: path.to_string(),
+            alias: alias.to_string(),
+        });
+    }
+
+    pub fn request_bt_set_trusted(&self, path: &str, trusted: bool) {
+        self.send_command(BackendCommand::BtSetTrusted {
+            path: path.to_string(),
+            trusted,
+        });
+    }
+
+    pub fn request_bt_remove(&self, path: &str) {
+        self.set_bt_device_flag(path, |d| d.set_removing(true));
+        if self.imp().bt_last_connected.borrow_mut().remove(path).is_some() {
+            crate::settings::forget_bt_last_connected(path);
+        }
+        let request = self.begin_request(RequestTarget::Bt(path.to_string()));
+        self.send_command(BackendCommand::BtRemove {
+            path: path.to_string(),
+            request,
+        });
+    }
+
+    pub fn send_bt_pairing_response(&self, accept: bool) {
+        Self::close_prompt(&self.imp().bt_prompt);
+        self.send_command(BackendCommand::BtPairingResponse { accept });
+    }
+
+    pub fn send_bt_pairing_pin(&self, pin: Option<String>) {
+        Self::close_prompt(&self.imp().bt_prompt);
+        self.send_command(BackendCommand::BtPairingPinResponse { pin });
+    }
+
+    pub fn send_bt_pairing_passkey(&self, passkey: Option<u32>) {
+        Self::close_prompt(&self.imp().bt_prompt);
+        self.send_command(BackendCommand::BtPairingPasskeyResponse { passkey });
     }
 }
 
@@ -804,3 +2664,432 @@ async fn run_backend(
     tracing::info!("Backend loop terminated");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A device as the backend reports it, with only what the tests vary
+    fn bt_device_data(address: &str, paired: bool, connected: bool) -> BtDeviceData {
+        BtDeviceData {
+            address: address.into(),
+            name: "Device".into(),
+            alias: "Device".into(),
+            icon: "audio-headphones".into(),
+            paired,
+            trusted: paired,
+            connected,
+            audio_sink: false,
+            hands_free: false,
+            battery_percentage: -1,
+            rssi: i16::MIN,
+        }
+    }
+
+    /// A saved network in range, as the backend reports it
+    #[cfg(feature = "wifi")]
+    fn wifi_network_data(path: &str, name: &str, connected: bool) -> WifiNetworkData {
+        WifiNetworkData {
+            path: path.into(),
+            name: name.into(),
+            network_type: "psk".into(),
+            signal_strength: -5000,
+            connected,
+            known: true,
+        }
+    }
+
+    #[test]
+    fn start_twice_does_not_panic() {
+        // start() spawns local futures, which need a main context owned by
+        // this thread
+        let context = glib::MainContext::new();
+        context
+            .with_thread_default(|| {
+                let manager = WlcontrolManager::new();
+                manager.start();
+                manager.start();
+                assert!(manager.imp().cmd_tx.get().is_some());
+            })
+            .unwrap();
+    }
+
+    #[cfg(feature = "wifi")]
+    #[test]
+    fn stopped_backend_leaves_nothing_live() {
+        let manager = WlcontrolManager::new();
+        manager.handle_event(BackendEvent::WifiAvailable(true));
+        manager.handle_event(BackendEvent::BtAvailable(true));
+        let network = WifiNetwork::new("/net/1", "Home", "psk", -50, true, true);
+        manager.wifi_networks().append(&network);
+        manager.request_wifi_disconnect();
+
+        manager.backend_stopped();
+        assert!(*manager.imp().backend_stopped.borrow());
+        assert!(!manager.wifi_available());
+        assert!(!manager.bt_available());
+        assert_eq!(manager.wifi_networks().n_items(), 0);
+        assert!(!network.connected());
+        assert!(!network.disconnecting());
+    }
+
+    #[cfg(feature = "wifi")]
+    #[test]
+    fn finished_request_clears_only_its_network() {
+        let manager = WlcontrolManager::new();
+        let store = manager.wifi_networks();
+        let first = WifiNetwork::new("/net/1", "First", "psk", -50, false, true);
+        let second = WifiNetwork::new("/net/2", "Second", "psk", -60, false, true);
+        store.append(&first);
+        store.append(&second);
+
+        manager.request_wifi_forget("/net/1");
+        manager.request_wifi_forget("/net/2");
+        manager.handle_event(BackendEvent::RequestDone {
+            request: RequestId(1),
+            error: None,
+        });
+        assert!(!first.forgetting());
+        assert!(second.forgetting());
+
+        // An unrelated error leaves the request in flight alone
+        manager.handle_event(BackendEvent::WifiError("Scan: busy".into()));
+        assert!(second.forgetting());
+    }
+
+    #[cfg(feature = "wifi")]
+    #[test]
+    fn only_the_last_of_rapid_connects_stays_connecting() {
+        let manager = WlcontrolManager::new();
+        let store = manager.wifi_networks();
+        let rows: Vec<_> = ["/net/1", "/net/2", "/net/3"]
+            .into_iter()
+            .map(|path| {
+                let network = WifiNetwork::new(path, path, "psk", -50, false, true);
+                store.append(&network);
+                network
+            })
+            .collect();
+
+        for row in &rows {
+            manager.request_wifi_connect(&row.path(), true);
+        }
+        // The backend answers each superseded attempt as it aborts it
+        for request in [RequestId(1), RequestId(2)] {
+            manager.handle_event(BackendEvent::RequestDone { request, error: None });
+        }
+        assert!(!rows[0].connecting());
+        assert!(!rows[1].connecting());
+        assert!(rows[2].connecting());
+
+        manager.handle_event(BackendEvent::RequestDone {
+            request: RequestId(3),
+            error: None,
+        });
+        assert!(!rows[2].connecting());
+    }
+
+    #[cfg(feature = "wifi")]
+    #[test]
+    fn switching_adapters_shows_only_the_new_adapters_connection() {
+        let manager = WlcontrolManager::new();
+        // Each adapter is connected, to a different network
+        manager.handle_event(BackendEvent::WifiNetworks(vec![wifi_network_data(
+            "/a/home", "Home", true,
+        )]));
+        manager.handle_event(BackendEvent::WifiConnected(Some("/a/home".into())));
+        assert!(manager.wifi_connected_duration("/a/home").is_some());
+
+        manager.set_active_wifi_adapter("/dev/b");
+        assert!(manager.wifi_connected_duration("/a/home").is_none());
+
+        manager.handle_event(BackendEvent::WifiNetworks(vec![
+            wifi_network_data("/b/home", "Home", false),
+            wifi_network_data("/b/office", "Office", true),
+        ]));
+        manager.handle_event(BackendEvent::WifiConnected(Some("/b/office".into())));
+        let connected: Vec<String> = (0..manager.wifi_networks().n_items())
+            .filter_map(|i| manager.wifi_networks().item(i).and_downcast::<WifiNetwork>())
+            .filter(|n| n.connected())
+            .map(|n| n.path())
+            .collect();
+        assert_eq!(connected, vec!["/b/office".to_string()]);
+        assert!(manager.wifi_connected_duration("/b/office").is_some());
+    }
+
+    /// Record every "wifi-hidden-done" as (request, error, not found)
+    #[cfg(feature = "wifi")]
+    fn hidden_done_log(
+        manager: &WlcontrolManager,
+    ) -> std::rc::Rc<std::cell::RefCell<Vec<(u64, String, bool)>>> {
+        let done = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        manager.connect_closure(
+            "wifi-hidden-done",
+            false,
+            glib::closure_local!(
+                #[strong]
+                done,
+                move |_: WlcontrolManager,
+                      request: u64,
+                      _name: String,
+                      error: String,
+                      not_found: bool| {
+                    done.borrow_mut().push((request, error, not_found));
+                }
+            ),
+        );
+        done
+    }
+
+    #[cfg(feature = "wifi")]
+    #[test]
+    fn forgetting_the_connected_network_leaves_it_disconnected() {
+        let manager = WlcontrolManager::new();
+        let path = "/net/connman/iwd/0/3/686f6d65_psk";
+        manager.handle_event(BackendEvent::WifiNetworks(vec![wifi_network_data(path, "home", true)]));
+        manager.handle_event(BackendEvent::WifiConnected(Some(path.into())));
+        let network = manager.wifi_networks().item(0).and_downcast::<WifiNetwork>().unwrap();
+        manager.request_wifi_forget(path);
+        assert!(network.forgetting());
+
+        // What the backend sends when the forgotten network was the active one
+        let request = *manager.imp().pending_requests.borrow().keys().next().unwrap();
+        manager.handle_event(BackendEvent::WifiConnected(None));
+        manager.handle_event(BackendEvent::RequestDone { request, error: None });
+
+        assert!(!network.connected());
+        assert!(!network.forgetting());
+        assert!(!network.connecting());
+        assert!(manager.wifi_connected_duration(path).is_none());
+        assert!(!manager.has_pending_request(|t| t.is_wifi_path(path)));
+    }
+
+    #[cfg(feature = "wifi")]
+    #[test]
+    fn replaced_hidden_connect_reports_done_under_its_id() {
+        let manager = WlcontrolManager::new();
+        let done = hidden_done_log(&manager);
+
+        let first = manager.request_wifi_connect_hidden("Attic");
+        let second = manager.request_wifi_connect_hidden("Attic");
+        assert_eq!(*done.borrow(), [(first.0, String::new(), false)]);
+
+        manager.handle_event(BackendEvent::RequestDone {
+            request: second,
+            error: Some("Connection timed out".into()),
+        });
+        assert_eq!(done.borrow()[1], (second.0, "Connection timed out".to_string(), false));
+    }
+
+    #[cfg(feature = "wifi")]
+    #[test]
+    fn hidden_connect_says_when_the_name_was_not_found() {
+        let manager = WlcontrolManager::new();
+        let done = hidden_done_log(&manager);
+
+        let request = manager.request_wifi_connect_hidden("attic");
+        let error = "\"attic\" was not found nearby".to_string();
+        manager.handle_event(BackendEvent::WifiHiddenNotFound {
+            request,
+            error: error.clone(),
+        });
+        assert_eq!(*done.borrow(), [(request.0, error, true)]);
+        assert!(!manager.has_pending_request(|t| matches!(t, RequestTarget::WifiHidden(_))));
+    }
+
+    #[cfg(feature = "wifi")]
+    #[test]
+    fn unreadable_saved_list_is_not_an_empty_one() {
+        let manager = WlcontrolManager::new();
+        manager.handle_event(BackendEvent::WifiKnownNetworksUnreadable);
+        assert!(manager.wifi_known_unreadable());
+
+        manager.handle_event(BackendEvent::WifiKnownNetworks(Vec::new()));
+        assert!(!manager.wifi_known_unreadable());
+    }
+
+    #[cfg(feature = "wifi")]
+    #[test]
+    fn watchdog_clears_operations_that_never_ended() {
+        let manager = WlcontrolManager::new();
+        let store = manager.wifi_networks();
+        let idle = WifiNetwork::new("/net/1", "Idle", "psk", -50, false, true);
+        let forgetting = WifiNetwork::new("/net/2", "Forgetting", "psk", -60, false, true);
+        store.append(&idle);
+        store.append(&forgetting);
+        manager.request_wifi_forget("/net/2");
+        // Connecting on its own, e.g. autoconnect, with no request behind it
+        manager.set_wifi_connecting("/net/1");
+
+        let now = std::time::Instant::now();
+        assert!(manager.expire_stale_operations(now));
+        assert!(idle.connecting());
+
+        // The request in flight gets longer than the lone flag
+        assert!(manager.expire_stale_operations(now + OPERATION_WATCHDOG));
+        assert!(!idle.connecting());
+        assert!(forgetting.forgetting());
+
+        assert!(!manager.expire_stale_operations(now + REQUEST_WATCHDOG));
+        assert!(!forgetting.forgetting());
+        assert!(!manager.has_pending_request(|t| t.is_wifi_path("/net/2")));
+    }
+
+    #[cfg(feature = "wifi")]
+    #[test]
+    fn watchdog_waits_for_an_open_prompt() {
+        let manager = WlcontrolManager::new();
+        let network = WifiNetwork::new("/net/1", "Cafe", "psk", -50, false, false);
+        manager.wifi_networks().append(&network);
+        manager.set_wifi_connecting("/net/1");
+        manager.handle_event(BackendEvent::PassphraseRequest {
+            network_path: "/net/1".into(),
+            network_name: "Cafe".into(),
+            network_type: "psk".into(),
+        });
+
+        let now = std::time::Instant::now();
+        assert!(manager.expire_stale_operations(now + REQUEST_WATCHDOG));
+        assert!(network.connecting());
+
+        // Counts from the answer, not from when connecting started
+        manager.send_passphrase_response(None, true);
+        let answered = std::time::Instant::now();
+        assert!(manager.expire_stale_operations(answered + OPERATION_WATCHDOG / 2));
+        assert!(network.connecting());
+        assert!(!manager.expire_stale_operations(answered + OPERATION_WATCHDOG));
+        assert!(!network.connecting());
+    }
+
+    #[cfg(feature = "bluetooth")]
+    #[test]
+    fn pairing_times_out_unless_a_prompt_is_open() {
+        let manager = WlcontrolManager::new();
+        let errors = std::rc::Rc::new(std::cell::RefCell::new(Vec::<String>::new()));
+        manager.connect_closure(
+            "bt-error",
+            false,
+            glib::closure_local!(
+                #[strong]
+                errors,
+                move |_: WlcontrolManager, msg: String| errors.borrow_mut().push(msg)
+            ),
+        );
+        let address = "00:11:22:33:44:55";
+        manager.handle_event(BackendEvent::BtDeviceAdded(bt_device_data(address, false, false)));
+        let device = manager.bt_devices().item(0).and_downcast::<BtDevice>().unwrap();
+        manager.request_bt_pair(address);
+        let request = *manager.imp().pending_requests.borrow().keys().next().unwrap();
+
+        let now = std::time::Instant::now();
+        assert!(manager.expire_stale_operations(now + PAIR_TIMEOUT / 2));
+        manager.handle_event(BackendEvent::BtPairing {
+            kind: BtPairingKind::Authorize,
+            address: address.into(),
+        });
+        assert!(manager.bt_pair_time_left(&device).is_none());
+        assert!(manager.expire_stale_operations(now + REQUEST_WATCHDOG));
+        assert_eq!(device.state(), BtDeviceState::Pairing);
+
+        // Counts again from the answer
+        manager.send_bt_pairing_response(true);
+        let answered = std::time::Instant::now();
+        assert!(manager.bt_pair_time_left(&device).is_some());
+        assert!(!manager.expire_stale_operations(answered + PAIR_TIMEOUT));
+        assert_eq!(device.state(), BtDeviceState::Discovered);
+        assert_eq!(errors.borrow().len(), 1);
+
+        // The cancelled pairing answering late isn't a second error, and
+        // whether it paired after all comes from its answer
+        manager.handle_event(BackendEvent::BtOperationDone {
+            data: bt_device_data(address, true, false),
+            error: Some("Pairing cancelled.".into()),
+            request: Some(request),
+        });
+        assert_eq!(errors.borrow().len(), 1);
+        assert_eq!(device.state(), BtDeviceState::Paired);
+    }
+
+    #[cfg(feature = "wifi")]
+    #[test]
+    fn saved_networks_sort_by_last_connected() {
+        let manager = WlcontrolManager::new();
+        let known = |path: &str, name: &str, last_connected: Option<&str>| KnownNetworkData {
+            path: path.into(),
+            name: name.into(),
+            network_type: "psk".into(),
+            hidden: false,
+            auto_connect: true,
+            last_connected: last_connected.map(Into::into),
+        };
+        manager.imp().cached_known.replace(vec![
+            known("/known/a", "Alpha", Some("2024-01-01T10:00:00Z")),
+            known("/known/b", "Beta", Some("2024-03-01T10:00:00Z")),
+            known("/known/c", "Gamma", None),
+        ]);
+        manager.imp().wifi_saved_sort.replace(WifiSavedSort::LastConnected);
+
+        let alpha = WifiNetwork::new_saved_offline("/known/a", "Alpha", "psk", false);
+        let beta = WifiNetwork::new_saved_offline("/known/b", "Beta", "psk", false);
+        let gamma = WifiNetwork::new_saved_offline("/known/c", "Gamma", "psk", false);
+        assert_eq!(manager.compare_saved_networks(&beta, &alpha), std::cmp::Ordering::Less);
+        assert_eq!(manager.compare_saved_networks(&gamma, &alpha), std::cmp::Ordering::Greater);
+
+        manager.imp().wifi_saved_sort.replace(WifiSavedSort::Name);
+        assert_eq!(manager.compare_saved_networks(&alpha, &beta), std::cmp::Ordering::Less);
+    }
+
+    #[cfg(feature = "bluetooth")]
+    #[test]
+    fn paired_and_connected_ends_a_pair_and_connect() {
+        let manager = WlcontrolManager::new();
+        let data = |paired, connected| bt_device_data("00:11:22:33:44:55", paired, connected);
+        manager.handle_event(BackendEvent::BtDeviceAdded(data(false, false)));
+        let device = manager.bt_devices().item(0).and_downcast::<BtDevice>().unwrap();
+
+        manager.request_bt_pair("00:11:22:33:44:55");
+        // BlueZ brings the link up while pairing; that isn't the connect
+        manager.handle_event(BackendEvent::BtDeviceChanged(data(false, true)));
+        assert!(device.connecting());
+        assert_eq!(device.state(), BtDeviceState::Pairing);
+
+        // Paired and connected ends it before the request is answered
+        manager.handle_event(BackendEvent::BtDeviceChanged(data(true, true)));
+        assert!(!device.connecting());
+        assert_eq!(device.state(), BtDeviceState::Connected);
+        // Recorded in the test's own settings store, not the user's file
+        assert!(crate::settings::bt_last_connected().contains_key("00:11:22:33:44:55"));
+    }
+
+    #[test]
+    fn unplugged_adapter_clears_devices() {
+        let manager = WlcontrolManager::new();
+        manager.handle_event(BackendEvent::BtAvailable(true));
+        manager.handle_event(BackendEvent::BtPowered(true));
+        manager.handle_event(BackendEvent::BtDeviceAdded(bt_device_data(
+            "00:11:22:33:44:55",
+            true,
+            true,
+        )));
+        manager.request_bt_disconnect("00:11:22:33:44:55");
+        // The connected device was recorded in the test's own settings store
+        assert!(crate::settings::bt_last_connected().contains_key("00:11:22:33:44:55"));
+
+        manager.handle_event(BackendEvent::BtAvailable(false));
+        assert_eq!(manager.bt_devices().n_items(), 0);
+        assert!(!manager.bt_powered());
+        assert!(!manager.has_pending_request(|t| matches!(t, RequestTarget::Bt(_))));
+    }
+
+    #[cfg(feature = "bluetooth")]
+    #[test]
+    fn failed_bt_scan_start_clears_starting() {
+        let manager = WlcontrolManager::new();
+        manager.request_bt_scan();
+        assert!(manager.bt_scan_starting());
+
+        manager.handle_event(BackendEvent::BtDiscovering(false));
+        assert!(!manager.bt_scan_starting());
+        assert!(!manager.bt_discovering());
+    }
+}
@@ -1,8 +1,14 @@
 mod event_loop;
 mod manager;
+mod signal_trend;
 mod store_helpers;
+#[cfg(test)]
+mod test_helpers;
 mod types;
+#[cfg(feature = "wifi")]
+mod util;
 pub mod wifi;
 pub mod bluetooth;
 
 pub use manager::WlcontrolManager;
+pub use types::{BtAgentCapability, BtAudioRole, RequestId, WifiPowerControl, WifiSavedSort};
@@ -0,0 +1,98 @@
+//! Whether a network's or device's signal is getting better or worse.
+//!
+//! Networks and devices keep the last few samples (in dBm) and compare the newest one to
+//! the average of the ones before it. Small wobbles are ignored so the
+//! arrow only shows up for a real change, like walking toward or away from
+//! the access point or device.
+
+use std::collections::VecDeque;
+
+/// Samples kept per network or device
+const TREND_SAMPLES: usize = 6;
+/// Fewer samples than this say nothing about a trend
+const TREND_MIN_SAMPLES: usize = 3;
+/// Change from the recent average that counts as a trend, in dB
+const TREND_THRESHOLD_DB: i32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trend {
+    Rising,
+    Falling,
+    Steady,
+}
+
+#[derive(Debug, Default)]
+pub struct SignalTrend {
+    samples: VecDeque<i32>,
+}
+
+impl SignalTrend {
+    /// Record a new sample, dropping the oldest once the history is full
+    pub fn push(&mut self, dbm: i32) {
+        if self.samples.len() == TREND_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(dbm);
+    }
+
+    /// Forget the history, e.g. when the signal is no longer reported
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    pub fn trend(&self) -> Trend {
+        let count = self.samples.len();
+        if count < TREND_MIN_SAMPLES {
+            return Trend::Steady;
+        }
+        let latest = self.samples[count - 1];
+        let average = self.samples.iter().take(count - 1).sum::<i32>() / (count - 1) as i32;
+        if latest - average >= TREND_THRESHOLD_DB {
+            Trend::Rising
+        } else if average - latest >= TREND_THRESHOLD_DB {
+            Trend::Falling
+        } else {
+            Trend::Steady
+        }
+    }
+
+    /// Arrow icon and tooltip for the current trend; None when steady
+    pub fn indicator(&self) -> Option<(&'static str, &'static str)> {
+        match self.trend() {
+            Trend::Rising => Some(("go-up-symbolic", "Signal getting stronger")),
+            Trend::Falling => Some(("go-down-symbolic", "Signal getting weaker")),
+            Trend::Steady => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trend_of(samples: &[i32]) -> Trend {
+        let mut trend = SignalTrend::default();
+        for &sample in samples {
+            trend.push(sample);
+        }
+        trend.trend()
+    }
+
+    #[test]
+    fn too_few_samples_are_steady() {
+        assert_eq!(trend_of(&[-80, -60]), Trend::Steady);
+    }
+
+    #[test]
+    fn compares_latest_to_earlier_average() {
+        assert_eq!(trend_of(&[-70, -70, -70, -64]), Trend::Rising);
+        assert_eq!(trend_of(&[-60, -60, -60, -66]), Trend::Falling);
+        assert_eq!(trend_of(&[-60, -61, -59, -61]), Trend::Steady);
+    }
+
+    #[test]
+    fn old_samples_fall_out() {
+        // The early weak samples no longer count once the history is full
+        assert_eq!(trend_of(&[-90, -90, -60, -60, -60, -60, -60, -60]), Trend::Steady);
+    }
+}
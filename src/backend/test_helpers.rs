@@ -0,0 +1,21 @@
+//! Assertions shared by the backend's GObject tests.
+
+use gtk::glib;
+use gtk::prelude::{IsA, ObjectExt};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Run `set` twice: the first call must notify `property` once and
+/// nothing else, the repeat with the same value must not notify
+pub fn assert_notifies_once<T: IsA<glib::Object>>(object: &T, property: &str, set: impl Fn(&T)) {
+    let notified = Rc::new(RefCell::new(Vec::<String>::new()));
+    let handler = object.connect_notify_local(None, {
+        let notified = notified.clone();
+        move |_, pspec| notified.borrow_mut().push(pspec.name().to_string())
+    });
+    set(object);
+    assert_eq!(*notified.borrow(), [property], "changing {}", property);
+    set(object);
+    assert_eq!(*notified.borrow(), [property], "repeating {}", property);
+    object.disconnect(handler);
+}
@@ -2,31 +2,128 @@
 
 use super::wifi::IwdDeviceInfo;
 
+/// Tags a user-initiated operation; the backend echoes it in
+/// `RequestDone`/`BtOperationDone` so the UI can tell which operation ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RequestId(pub u64);
+
+/// Report how a request ended. Operations a backend started on its own have
+/// no request id, so only their failure goes out, as `error_event`.
+pub async fn finish_request(
+    evt_tx: &async_channel::Sender<BackendEvent>,
+    request: Option<RequestId>,
+    error: Option<String>,
+    error_event: fn(String) -> BackendEvent,
+) {
+    let event = match (request, error) {
+        (Some(request), error) => BackendEvent::RequestDone { request, error },
+        (None, Some(message)) => error_event(message),
+        (None, None) => return,
+    };
+    if let Err(e) = evt_tx.send(event).await {
+        tracing::error!("Failed to report the end of a request: {}", e);
+    }
+}
+
+/// A passphrase handed to the backend ahead of the agent asking for it.
+/// Commands are logged with `{:?}`, so Debug leaves the value out.
+#[derive(Clone)]
+pub struct Passphrase(pub String);
+
+impl std::fmt::Debug for Passphrase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Passphrase(..)")
+    }
+}
+
 /// Commands sent from UI to backend
 #[derive(Debug, Clone)]
+// A build without one of the radios never sends or receives its variants
+#[cfg_attr(not(all(feature = "bluetooth", feature = "wifi")), allow(dead_code))]
 pub enum BackendCommand {
     /// Shutdown the backend gracefully
     Shutdown,
-    WifiScan,
-    WifiConnect { path: String },
-    WifiDisconnect,
-    WifiForget { path: String },           // network path, backend will get known_network from it
-    WifiForgetKnown { path: String },       // KnownNetwork D-Bus path, for saved-offline networks
-    WifiSetPowered { powered: bool },
+    /// `thorough` runs several scans and merges their results
+    WifiScan { thorough: bool },
+    /// Scan unless iwd already is; failures are only logged
+    WifiScanIfIdle,
+    /// Re-read Station.Scanning and report it, when an update seems lost
+    WifiRefreshScanning,
+    /// Re-read saved networks, the connection and the network list without
+    /// scanning, after changes made outside wlcontrol
+    WifiRefresh,
+    /// Join a hidden network by SSID (it has no Network object to connect)
+    WifiConnectHidden { name: String, request: RequestId },
+    /// `remember: false` joins the network without keeping it saved.
+    /// A `passphrase` answers iwd's agent prompt for this network without
+    /// asking the user.
+    WifiConnect {
+        path: String,
+        remember: bool,
+        passphrase: Option<Passphrase>,
+        request: RequestId,
+    },
+    WifiDisconnect { request: RequestId },
+    WifiForget { path: String, request: RequestId },      // network path, backend will get known_network from it
+    WifiForgetKnown { path: String, request: RequestId }, // KnownNetwork D-Bus path, for saved-offline networks
+    /// Set AutoConnect on a KnownNetwork (D-Bus path)
+    WifiSetAutoConnect { path: String, enabled: bool },
+    /// Keep the connection on this access point (None = let iwd roam freely)
+    WifiPinBssid { bssid: Option<String> },
+    /// Time a connection to `host` ("host" or "host:port", empty for the
+    /// default) and answer with WifiLatency or WifiLatencyFailed
+    WifiTestLatency { host: String },
+    /// Drop a saved network's stored password and reconnect so iwd asks for a new one
+    WifiUpdatePassword { path: String },
+    WifiSetPowered { powered: bool, control: WifiPowerControl },
+    /// Whether a network that wasn't saved before is forgotten again when
+    /// connecting to it fails
+    WifiSetForgetFailedNew { enabled: bool },
+    /// Power the device off and on again, when its Station never appeared
+    WifiRestartDevice,
     /// Switch to a different WiFi adapter (recreate backend + streams)
     WifiSwitchAdapter { device_path: String },
-    /// Response to a passphrase request (None = cancelled)
-    PassphraseResponse { passphrase: Option<String> },
-    BtScan,
+    /// Response to a passphrase request (None = cancelled), with the
+    /// "Remember this network" choice
+    PassphraseResponse { passphrase: Option<String>, remember: bool },
+    /// `brief` stops discovery sooner, for scans the user didn't ask for
+    BtScan { brief: bool },
     BtStopScan,
-    BtConnect { path: String },
-    BtDisconnect { path: String },
-    BtPair { path: String },
-    BtRemove { path: String },
+    /// Re-read adapter state and the device list, after changes made
+    /// outside wlcontrol (bluetoothctl)
+    BtRefresh,
+    /// `role` connects only that audio profile, for devices that offer
+    /// both; None lets BlueZ pick
+    BtConnect {
+        path: String,
+        role: Option<BtAudioRole>,
+        request: RequestId,
+    },
+    BtDisconnect { path: String, request: RequestId },
+    BtPair { path: String, request: RequestId },
+    /// Abort an in-progress pairing or connection attempt
+    BtCancelPair { address: String },
+    BtRemove { path: String, request: RequestId },
     BtSetAlias { path: String, alias: String },
     BtSetTrusted { path: String, trusted: bool },
     BtSetPowered { powered: bool },
     BtSetDiscoverable { discoverable: bool },
+    /// Whether new devices may pair; paired ones can connect either way
+    BtSetPairable { pairable: bool },
+    /// Re-send powered, discoverable and pairable state, e.g. when the page
+    /// is shown after discoverable mode may have timed out
+    BtSyncAdapterState,
+    /// Whether unpaired devices stay listed after discovery stops
+    BtSetKeepDiscovered { keep: bool },
+    /// Whether to connect a device right after pairing succeeds
+    BtSetConnectOnPair { enabled: bool },
+    /// Whether discovery is stopped while connecting or pairing, and
+    /// resumed once that finishes
+    BtSetPauseScanOnConnect { enabled: bool },
+    /// Whether discovered devices without a name are listed (BLE debugging)
+    BtSetShowUnnamed { show: bool },
+    /// Re-register the pairing agent with a different IO capability
+    BtSetAgentCapability { capability: BtAgentCapability },
     /// Response to a pairing confirmation/authorization (accept or reject)
     BtPairingResponse { accept: bool },
     /// Response with PIN code
@@ -35,6 +132,56 @@ pub enum BackendCommand {
     BtPairingPasskeyResponse { passkey: Option<u32> },
 }
 
+impl BackendCommand {
+    /// Whether the Bluetooth half of the backend handles this command.
+    /// No wildcard arm: a new command must be sorted here to compile.
+    pub fn is_bluetooth(&self) -> bool {
+        match self {
+            Self::BtScan { .. }
+            | Self::BtStopScan
+            | Self::BtRefresh
+            | Self::BtConnect { .. }
+            | Self::BtDisconnect { .. }
+            | Self::BtPair { .. }
+            | Self::BtCancelPair { .. }
+            | Self::BtRemove { .. }
+            | Self::BtSetAlias { .. }
+            | Self::BtSetTrusted { .. }
+            | Self::BtSetPowered { .. }
+            | Self::BtSetDiscoverable { .. }
+            | Self::BtSetPairable { .. }
+            | Self::BtSyncAdapterState
+            | Self::BtSetKeepDiscovered { .. }
+            | Self::BtSetConnectOnPair { .. }
+            | Self::BtSetPauseScanOnConnect { .. }
+            | Self::BtSetShowUnnamed { .. }
+            | Self::BtSetAgentCapability { .. }
+            | Self::BtPairingResponse { .. }
+            | Self::BtPairingPinResponse { .. }
+            | Self::BtPairingPasskeyResponse { .. } => true,
+            Self::Shutdown
+            | Self::WifiScan { .. }
+            | Self::WifiScanIfIdle
+            | Self::WifiRefreshScanning
+            | Self::WifiRefresh
+            | Self::WifiConnectHidden { .. }
+            | Self::WifiConnect { .. }
+            | Self::WifiDisconnect { .. }
+            | Self::WifiForget { .. }
+            | Self::WifiForgetKnown { .. }
+            | Self::WifiSetAutoConnect { .. }
+            | Self::WifiPinBssid { .. }
+            | Self::WifiTestLatency { .. }
+            | Self::WifiUpdatePassword { .. }
+            | Self::WifiSetPowered { .. }
+            | Self::WifiSetForgetFailedNew { .. }
+            | Self::WifiRestartDevice
+            | Self::WifiSwitchAdapter { .. }
+            | Self::PassphraseResponse { .. } => false,
+        }
+    }
+}
+
 /// Data for a WiFi network, used to transfer between backend and UI threads
 #[derive(Debug, Clone)]
 pub struct WifiNetworkData {
@@ -46,16 +193,181 @@ pub struct WifiNetworkData {
     pub known: bool,
 }
 
+/// Which iwd power switch the WiFi toggle drives
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WifiPowerControl {
+    /// `Device.Powered`: the network interface
+    #[default]
+    Device,
+    /// `Adapter.Powered`: the whole radio (phy)
+    Adapter,
+}
+
+impl WifiPowerControl {
+    /// Key used to persist this choice in settings
+    pub fn as_key(&self) -> &'static str {
+        match self {
+            Self::Device => "device",
+            Self::Adapter => "adapter",
+        }
+    }
+
+    pub fn from_key(s: &str) -> Self {
+        match s {
+            "adapter" => Self::Adapter,
+            _ => Self::Device,
+        }
+    }
+}
+
+/// Order of the saved (out of range) networks list
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WifiSavedSort {
+    #[default]
+    Name,
+    /// Most recently connected first, per iwd's LastConnectedTime
+    LastConnected,
+    /// Most recently saved first, as first noticed by wlcontrol
+    RecentlyAdded,
+}
+
+impl WifiSavedSort {
+    /// Key used to persist this choice in settings
+    pub fn as_key(&self) -> &'static str {
+        match self {
+            Self::Name => "name",
+            Self::LastConnected => "last-connected",
+            Self::RecentlyAdded => "recently-added",
+        }
+    }
+
+    pub fn from_key(s: &str) -> Self {
+        match s {
+            "last-connected" => Self::LastConnected,
+            "recently-added" => Self::RecentlyAdded,
+            _ => Self::Name,
+        }
+    }
+}
+
+/// IO capability the Bluetooth pairing agent reports to BlueZ, which decides
+/// the pairing method: the less the agent can do, the fewer prompts, and the
+/// less protection against a device impersonating the one being paired.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BtAgentCapability {
+    /// "Just works" pairing without confirmation; no MITM protection
+    NoInputNoOutput,
+    /// Passkey comparison with yes/no confirmation
+    DisplayYesNo,
+    /// Confirmation, or typing/showing a PIN or passkey, whichever the
+    /// device supports
+    #[default]
+    KeyboardDisplay,
+}
+
+impl BtAgentCapability {
+    /// Key used to persist this choice in settings
+    pub fn as_key(&self) -> &'static str {
+        match self {
+            Self::NoInputNoOutput => "no-input-no-output",
+            Self::DisplayYesNo => "display-yes-no",
+            Self::KeyboardDisplay => "keyboard-display",
+        }
+    }
+
+    pub fn from_key(s: &str) -> Self {
+        match s {
+            "no-input-no-output" => Self::NoInputNoOutput,
+            "display-yes-no" => Self::DisplayYesNo,
+            _ => Self::KeyboardDisplay,
+        }
+    }
+}
+
+/// Audio role to connect a headset in: high-quality playback (A2DP) or
+/// calls with the microphone (HFP)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BtAudioRole {
+    Listen,
+    Call,
+}
+
+impl BtAudioRole {
+    /// Role named by a menu action target
+    pub fn from_key(s: &str) -> Option<Self> {
+        match s {
+            "listen" => Some(Self::Listen),
+            "call" => Some(Self::Call),
+            _ => None,
+        }
+    }
+}
+
+/// Connection state of the active iwd Station, mapped from `Station.State`.
+/// Richer than connected/disconnected so the UI can show what iwd is doing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WifiStationState {
+    #[default]
+    Disconnected,
+    Connecting,
+    Authenticating,
+    ObtainingAddress,
+    Connected,
+    Roaming,
+    Disconnecting,
+}
+
+impl WifiStationState {
+    /// Map an iwd `Station.State` string. `network_type` is the type of the network
+    /// being connected to: 802.1x spends most of its "connecting" phase in EAP.
+    pub fn from_iwd(state: &str, network_type: Option<&str>) -> Self {
+        match state {
+            "connecting" | "connecting (auto)" if network_type == Some("8021x") => {
+                Self::Authenticating
+            }
+            "connecting" | "connecting (auto)" => Self::Connecting,
+            "netconfig" => Self::ObtainingAddress,
+            "connected" => Self::Connected,
+            "roaming" | "ft-roaming" | "fw-roaming" => Self::Roaming,
+            "disconnecting" => Self::Disconnecting,
+            _ => Self::Disconnected,
+        }
+    }
+
+    /// Whether the link is up but not yet usable (auth or DHCP still running)
+    pub fn is_establishing(&self) -> bool {
+        matches!(
+            self,
+            Self::Connecting | Self::Authenticating | Self::ObtainingAddress
+        )
+    }
+
+    /// Row subtitle for this state, or None when the plain state label is enough
+    pub fn label(&self) -> Option<&'static str> {
+        match self {
+            Self::Connecting => Some("Connecting…"),
+            Self::Authenticating => Some("Authenticating…"),
+            Self::ObtainingAddress => Some("Obtaining address…"),
+            Self::Roaming => Some("Roaming"),
+            Self::Disconnecting => Some("Disconnecting…"),
+            Self::Connected | Self::Disconnected => None,
+        }
+    }
+}
+
 /// Data for a saved (known) WiFi network from iwd KnownNetwork interface
 #[derive(Debug, Clone)]
 pub struct KnownNetworkData {
     pub path: String, // KnownNetwork D-Bus path
     pub name: String,
     pub network_type: String,
+    pub hidden: bool,
+    pub auto_connect: bool,
+    pub last_connected: Option<String>, // iwd LastConnectedTime (ISO 8601), None if never
 }
 
 /// Data for a Bluetooth device, used to transfer between backend and UI threads
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct BtDeviceData {
     pub address: String,
     pub name: String,
@@ -64,6 +376,8 @@ pub struct BtDeviceData {
     pub paired: bool,
     pub trusted: bool,
     pub connected: bool,
+    pub audio_sink: bool,        // advertises A2DP Audio Sink
+    pub hands_free: bool,        // advertises HFP Hands-Free
     pub battery_percentage: i32, // -1 if not available
     pub rssi: i16,               // i16::MIN = no data
 }
@@ -81,6 +395,7 @@ pub enum BtPairingKind {
 
 /// Events sent from backend to UI
 #[derive(Debug, Clone)]
+#[cfg_attr(not(all(feature = "bluetooth", feature = "wifi")), allow(dead_code))]
 pub enum BackendEvent {
     /// Whether WiFi backend (iwd) is available
     WifiAvailable(bool),
@@ -92,33 +407,75 @@ pub enum BackendEvent {
         active_path: Option<String>,
     },
     WifiPowered(bool),
+    /// Device powered on but iwd never added Station (driver didn't initialize)
+    WifiStationMissing,
+    /// iwd `Adapter.Powered` of the active device's radio
+    WifiRadioPowered(bool),
     WifiScanning(bool),
+    /// Thorough scan progress as (cycle, cycles); None when it finished
+    WifiThoroughScan(Option<(u32, u32)>),
     WifiNetworks(Vec<WifiNetworkData>),
     WifiConnected(Option<String>),    // path of connected network, or None
     WifiConnecting(String),           // path of network we're connecting to
+    /// Detailed Station state (authenticating, obtaining address, roaming, ...)
+    WifiState(WifiStationState),
+    /// Connection moved to a different access point of the same network
+    WifiRoamed { bssid: String },
+    /// BSSID of the current connection (None when not connected)
+    WifiBssid(Option<String>),
+    /// Access point the connection is pinned to (None = not pinned)
+    WifiBssidPinned(Option<String>),
     WifiNetworkKnown { path: String },            // network became known (saved)
+    /// A saved PSK network rejected its stored password; iwd won't ask the agent
+    WifiAuthFailed { path: String },
     WifiKnownNetworks(Vec<KnownNetworkData>),      // all saved networks from iwd
+    /// The saved networks couldn't be read; the list shown isn't their real state
+    WifiKnownNetworksUnreadable,
     /// iwd is requesting a passphrase for a network
     PassphraseRequest {
         network_path: String,
         network_name: String,
+        network_type: String,
     },
+    /// The pending passphrase request was dropped (adapter switched or removed)
+    PassphraseCancelled,
     /// Captive portal detected after connection, URL to open in browser
     CaptivePortal { url: String },
+    /// Internet became reachable after a captive portal login
+    CaptivePortalCleared,
+    /// Round-trip time of a latency test to `host`, as it was requested
+    WifiLatency { host: String, ms: u32 },
+    /// A latency test to `host` got no answer in time, or the host was unknown
+    WifiLatencyFailed { host: String, error: String },
     BtPowered(bool),
     BtDiscovering(bool),
     BtDiscoverable(bool),
+    BtPairable(bool),
     BtConnecting(String),    // address of device we're connecting/pairing to
     BtDeviceAdded(BtDeviceData),
     BtDeviceChanged(BtDeviceData),
     /// Device operation (connect/disconnect/pair) completed — carries
-    /// re-read device state from BlueZ + optional error message.
+    /// re-read device state from BlueZ + optional error message, and the
+    /// request it answers (None for follow-ups the backend started itself).
     BtOperationDone {
         data: BtDeviceData,
         error: Option<String>,
+        request: Option<RequestId>,
     },
     BtDeviceRemoved(String), // address
     BtPairing { kind: BtPairingKind, address: String },
+    /// A tagged request finished without device data to report (WiFi
+    /// operations, or a BT device that couldn't be read)
+    RequestDone {
+        request: RequestId,
+        error: Option<String>,
+    },
+    /// A hidden connect found no network by the name given, answering
+    /// `request`; the name may just be mistyped
+    WifiHiddenNotFound {
+        request: RequestId,
+        error: String,
+    },
     BtError(String),
     WifiError(String),
 }
@@ -0,0 +1,105 @@
+//! Async helpers shared across backends.
+
+use futures::{Stream, StreamExt};
+use std::time::Duration;
+
+/// Wait until `values` yields a value matching `predicate`.
+/// Returns that value, or None on timeout or if the stream ends first.
+pub async fn wait_for_property<T, S, P>(values: S, predicate: P, timeout: Duration) -> Option<T>
+where
+    S: Stream<Item = T>,
+    P: Fn(&T) -> bool,
+{
+    let matching = values.filter(|value| std::future::ready(predicate(value)));
+    futures::pin_mut!(matching);
+    tokio::time::timeout(timeout, matching.next()).await.ok().flatten()
+}
+
+/// Turn a zbus property change stream into a stream of the new values,
+/// skipping changes whose value can't be read.
+pub fn property_values<T>(stream: zbus::PropertyStream<'static, T>) -> impl Stream<Item = T>
+where
+    T: TryFrom<zbus::zvariant::OwnedValue> + Unpin,
+    T::Error: Into<zbus::Error>,
+{
+    stream.filter_map(|change| async move { change.get().await.ok() })
+}
+
+/// Read each item in order, keeping the ones that succeed. Failures are
+/// logged and skipped, so one bad entry doesn't discard the rest.
+pub async fn read_each<I, T, E, F, Fut>(what: &str, items: I, mut read: F) -> Vec<T>
+where
+    I: IntoIterator,
+    F: FnMut(I::Item) -> Fut,
+    Fut: std::future::Future<Output = Result<T, E>>,
+    E: std::fmt::Display,
+{
+    let mut results = Vec::new();
+    for item in items {
+        match read(item).await {
+            Ok(value) => results.push(value),
+            Err(e) => tracing::warn!("Skipping {} that failed to read: {}", what, e),
+        }
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_millis(50);
+
+    #[tokio::test]
+    async fn resolves_on_first_match() {
+        let values = futures::stream::iter([false, false, true, false]);
+        assert_eq!(wait_for_property(values, |v| *v, TIMEOUT).await, Some(true));
+    }
+
+    #[tokio::test]
+    async fn returns_matching_value() {
+        let values = futures::stream::iter(["disconnected", "connecting", "connected"]);
+        let state = wait_for_property(values, |s| s.starts_with("conn"), TIMEOUT).await;
+        assert_eq!(state, Some("connecting"));
+    }
+
+    #[tokio::test]
+    async fn none_when_stream_ends_without_match() {
+        let values = futures::stream::iter([1, 2, 3]);
+        assert_eq!(wait_for_property(values, |v| *v > 3, TIMEOUT).await, None);
+    }
+
+    #[tokio::test]
+    async fn none_on_timeout() {
+        let values = futures::stream::pending::<bool>();
+        assert_eq!(wait_for_property(values, |v| *v, TIMEOUT).await, None);
+    }
+
+    #[tokio::test]
+    async fn resolves_on_late_value() {
+        let values = futures::stream::once(async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            true
+        });
+        assert_eq!(wait_for_property(values, |v| *v, TIMEOUT).await, Some(true));
+    }
+
+    #[tokio::test]
+    async fn read_each_skips_failures() {
+        let paths = ["/net/1", "/net/bad", "/net/2", "/net/gone"];
+        let read = |path: &'static str| async move {
+            if path.ends_with("bad") || path.ends_with("gone") {
+                Err(format!("{}: no such object", path))
+            } else {
+                Ok(path.to_string())
+            }
+        };
+        assert_eq!(read_each("network", paths, read).await, ["/net/1", "/net/2"]);
+    }
+
+    #[tokio::test]
+    async fn read_each_all_failing_is_empty() {
+        let read = |_: u32| async { Err::<u32, _>("unavailable") };
+        assert!(read_each("network", [1, 2], read).await.is_empty());
+    }
+}
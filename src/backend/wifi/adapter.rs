@@ -0,0 +1,67 @@
+//! What the adapter selector knows about each iwd device.
+
+/// Info about an iwd WiFi device, used to populate the adapter selector in UI
+#[derive(Debug, Clone)]
+pub struct IwdDeviceInfo {
+    pub device_path: String,
+    pub device_name: String,
+    /// Adapter.Model and Adapter.Vendor; empty when iwd doesn't know them
+    pub adapter_model: String,
+    pub adapter_vendor: String,
+    /// Device.Powered when the list was read
+    pub powered: bool,
+}
+
+impl IwdDeviceInfo {
+    /// Name for the adapter selector
+    pub fn label(&self) -> String {
+        adapter_label(&self.adapter_vendor, &self.adapter_model, &self.device_name)
+    }
+}
+
+/// Name an adapter from whichever of vendor, model and interface name are
+/// known, e.g. "Intel Corporation Wi-Fi 6 AX200 (wlan0)". The model often
+/// repeats the vendor already, and either can be missing.
+fn adapter_label(vendor: &str, model: &str, interface: &str) -> String {
+    let clean = |s: &str| s.split_whitespace().collect::<Vec<_>>().join(" ");
+    let (vendor, model, interface) = (clean(vendor), clean(model), clean(interface));
+    let name = if model.is_empty() {
+        vendor
+    } else if vendor.is_empty() || model.starts_with(&vendor) {
+        model
+    } else {
+        format!("{} {}", vendor, model)
+    };
+    match (name.is_empty(), interface.is_empty()) {
+        (true, true) => "WiFi adapter".to_string(),
+        (true, false) => interface,
+        (false, true) => name,
+        (false, false) => format!("{} ({})", name, interface),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adapter_label_combines_known_fields() {
+        assert_eq!(
+            adapter_label("Intel Corporation", "Wi-Fi 6 AX200", "wlan0"),
+            "Intel Corporation Wi-Fi 6 AX200 (wlan0)"
+        );
+        assert_eq!(adapter_label("", "AX200", "wlan0"), "AX200 (wlan0)");
+        assert_eq!(adapter_label("Realtek", "", "wlan1"), "Realtek (wlan1)");
+        assert_eq!(adapter_label("Intel", "Wi-Fi 6 AX200", ""), "Intel Wi-Fi 6 AX200");
+    }
+
+    #[test]
+    fn adapter_label_skips_repeated_vendor_and_extra_whitespace() {
+        assert_eq!(
+            adapter_label("Intel Corporation", "Intel Corporation  AX200 ", "wlan0"),
+            "Intel Corporation AX200 (wlan0)"
+        );
+        assert_eq!(adapter_label("  ", "\t", "wlan0"), "wlan0");
+        assert_eq!(adapter_label("", "", ""), "WiFi adapter");
+    }
+}
@@ -1,57 +1,120 @@
 //! WiFi backend implementation using iwd (Intel Wireless Daemon)
 
 use async_channel::Sender;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::Mutex;
-use tokio::task::AbortHandle;
+use tokio::sync::{watch, Mutex};
+use tokio::task::JoinHandle;
 use zbus::zvariant::OwnedObjectPath;
 
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::time::Duration;
 
-use crate::backend::types::{BackendEvent, KnownNetworkData, WifiNetworkData};
-use super::iwd_proxy::{AdapterProxy, DeviceProxy, KnownNetworkProxy, NetworkProxy, StationProxy};
+use crate::backend::types::{
+    finish_request, BackendEvent, KnownNetworkData, RequestId, WifiNetworkData, WifiPowerControl,
+    WifiStationState,
+};
+use super::super::util::{property_values, read_each, wait_for_property};
+use super::adapter::IwdDeviceInfo;
+use super::iwd_proxy::{
+    AdapterProxy, DeviceProxy, KnownNetworkProxy, NetworkProxy, StationDebugProxy,
+    StationDiagnosticProxy, StationProxy,
+};
 
 const CAPTIVE_PORTAL_CHECK_URL: &str = "http://connectivitycheck.gstatic.com/generate_204";
 
-/// Check if a captive portal is present by probing a known URL.
-/// Returns `Some(portal_url)` if a captive portal is detected, `None` otherwise.
-/// Retries until the network stack is ready (DHCP/DNS may take variable time after WiFi connects).
-async fn check_captive_portal() -> Option<String> {
-    let client = reqwest::Client::builder()
+/// How long to wait for iwd to create the KnownNetwork after a connect
+/// succeeds. iwd saves the profile asynchronously, so it can lag the reply.
+const KNOWN_NETWORK_WAIT: Duration = Duration::from_secs(3);
+
+/// Time allowed for iwd's connect operation, excluding time spent at the
+/// passphrase prompt. Protects against iwd hangs.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How often to re-probe while a captive portal login is pending
+const PORTAL_REPROBE_INTERVAL: Duration = Duration::from_secs(10);
+/// Re-probes before giving up on noticing the login (about a minute)
+const PORTAL_REPROBE_ATTEMPTS: u32 = 6;
+
+/// Host of the latency test when the user names none
+pub const LATENCY_TEST_DEFAULT_HOST: &str = "connectivitycheck.gstatic.com";
+/// Port of the latency test when the host comes without one
+const LATENCY_TEST_PORT: u16 = 443;
+/// Longest a latency test waits for the name lookup, and then the handshake
+const LATENCY_TEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Scans in a thorough scan; networks seen in any of them are listed
+const THOROUGH_SCAN_CYCLES: u32 = 3;
+/// Pause between thorough scan cycles
+const THOROUGH_SCAN_SPACING: Duration = Duration::from_secs(2);
+/// Longest a single scan cycle may take before moving on
+const THOROUGH_SCAN_CYCLE_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Result of a single connectivity probe
+enum ProbeResult {
+    Online,
+    /// Portal detected, with the URL to open for login
+    Portal(String),
+    Unreachable,
+}
+
+fn probe_client() -> Option<reqwest::Client> {
+    reqwest::Client::builder()
         .redirect(reqwest::redirect::Policy::none())
         .local_address(std::net::IpAddr::V4(std::net::Ipv4Addr::UNSPECIFIED))
         .timeout(Duration::from_secs(3))
         .build()
-        .ok()?;
+        .ok()
+}
+
+/// Probe the check URL once
+async fn probe_connectivity(client: &reqwest::Client) -> ProbeResult {
+    let resp = match client.get(CAPTIVE_PORTAL_CHECK_URL).send().await {
+        Ok(r) => r,
+        Err(e) => {
+            tracing::debug!("Connectivity probe failed: {}", e);
+            return ProbeResult::Unreachable;
+        }
+    };
+
+    if resp.status().as_u16() == 204 {
+        ProbeResult::Online
+    } else if resp.status().is_redirection() {
+        let url = resp
+            .headers()
+            .get("location")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or(CAPTIVE_PORTAL_CHECK_URL)
+            .to_string();
+        tracing::info!("Captive portal detected (redirect to {})", url);
+        ProbeResult::Portal(url)
+    } else {
+        tracing::info!("Captive portal detected (status {})", resp.status());
+        ProbeResult::Portal(CAPTIVE_PORTAL_CHECK_URL.to_string())
+    }
+}
+
+/// Check if a captive portal is present by probing a known URL.
+/// Returns `Some(portal_url)` if a captive portal is detected, `None` otherwise.
+/// Retries until the network stack is ready (DHCP/DNS may take variable time after WiFi connects).
+async fn check_captive_portal() -> Option<String> {
+    let client = probe_client()?;
 
     for attempt in 1..=10 {
         if attempt > 1 {
             tokio::time::sleep(Duration::from_secs(2)).await;
         }
 
-        let resp = match client.get(CAPTIVE_PORTAL_CHECK_URL).send().await {
-            Ok(r) => r,
-            Err(e) => {
-                tracing::debug!("Captive portal check attempt {}/10: {}", attempt, e);
-                continue;
+        match probe_connectivity(&client).await {
+            ProbeResult::Online => {
+                tracing::debug!("No captive portal detected");
+                return None;
+            }
+            ProbeResult::Portal(url) => return Some(url),
+            ProbeResult::Unreachable => {
+                tracing::debug!("Captive portal check attempt {}/10 failed", attempt);
             }
-        };
-
-        if resp.status().as_u16() == 204 {
-            tracing::debug!("No captive portal detected");
-            return None;
-        } else if resp.status().is_redirection() {
-            let url = resp
-                .headers()
-                .get("location")
-                .and_then(|v| v.to_str().ok())
-                .unwrap_or(CAPTIVE_PORTAL_CHECK_URL)
-                .to_string();
-            tracing::info!("Captive portal detected (redirect to {})", url);
-            return Some(url);
-        } else {
-            tracing::info!("Captive portal detected (status {})", resp.status());
-            return Some(CAPTIVE_PORTAL_CHECK_URL.to_string());
         }
     }
 
@@ -59,6 +122,54 @@ async fn check_captive_portal() -> Option<String> {
     None
 }
 
+/// Split "host" or "host:port" into what to connect to; an IPv6 address
+/// with a port goes in brackets, "[::1]:22". Empty means the default host.
+fn latency_target(input: &str) -> (String, u16) {
+    let input = match input.trim() {
+        "" => LATENCY_TEST_DEFAULT_HOST,
+        input => input,
+    };
+    if let Some((host, rest)) = input.strip_prefix('[').and_then(|r| r.split_once(']')) {
+        let port = rest.strip_prefix(':').and_then(|p| p.parse().ok());
+        return (host.to_string(), port.unwrap_or(LATENCY_TEST_PORT));
+    }
+    match input.rsplit_once(':') {
+        // A second colon makes it a bare IPv6 address, not a port
+        Some((host, port)) if !host.contains(':') => match port.parse() {
+            Ok(port) => (host.to_string(), port),
+            Err(_) => (input.to_string(), LATENCY_TEST_PORT),
+        },
+        _ => (input.to_string(), LATENCY_TEST_PORT),
+    }
+}
+
+/// Time a TCP handshake with the host, as a ping that needs no raw socket
+/// (ICMP would need CAP_NET_RAW). The name lookup isn't timed. A refused
+/// connection still took one round trip, so it counts as an answer.
+async fn measure_latency(host: &str, port: u16) -> Result<Duration, String> {
+    let lookup = tokio::time::timeout(LATENCY_TEST_TIMEOUT, tokio::net::lookup_host((host, port)));
+    let addr = match lookup.await {
+        Ok(Ok(mut addrs)) => addrs.next().ok_or("Unknown host")?,
+        Ok(Err(e)) => {
+            tracing::debug!("Latency test: lookup of {} failed: {}", host, e);
+            return Err("Unknown host".into());
+        }
+        Err(_) => return Err("Name lookup timed out".into()),
+    };
+
+    let start = tokio::time::Instant::now();
+    let connect = tokio::time::timeout(LATENCY_TEST_TIMEOUT, tokio::net::TcpStream::connect(addr));
+    match connect.await {
+        Ok(Ok(_)) => Ok(start.elapsed()),
+        Ok(Err(e)) if e.kind() == std::io::ErrorKind::ConnectionRefused => Ok(start.elapsed()),
+        Ok(Err(e)) => {
+            tracing::debug!("Latency test: connecting to {} failed: {}", addr, e);
+            Err("Host unreachable".into())
+        }
+        Err(_) => Err("No answer (timed out)".into()),
+    }
+}
+
 /// Convert iwd D-Bus errors to user-friendly messages
 pub fn format_iwd_error(e: &zbus::Error) -> String {
     let s = e.to_string();
@@ -83,12 +194,58 @@ pub fn format_iwd_error(e: &zbus::Error) -> String {
     }
 }
 
-/// Info about an iwd WiFi device, used to populate the adapter selector in UI
-#[derive(Debug, Clone)]
-pub struct IwdDeviceInfo {
-    pub device_path: String,
-    pub device_name: String,
-    pub adapter_model: String,
+/// Whether the KnownNetwork at `known_path` is the one the station is on
+/// (or moving to), given the KnownNetwork of its connected network
+fn is_connected_known(connected_known: Option<&str>, known_path: &str) -> bool {
+    connected_known == Some(known_path)
+}
+
+/// Whether iwd found no network by the name it was asked to connect to
+fn is_not_found(e: &zbus::Error) -> bool {
+    matches!(e, zbus::Error::MethodError(name, _, _) if name.as_str() == "net.connman.iwd.NotFound")
+}
+
+/// Whether a connect error means the network rejected the key
+fn is_auth_failure(e: &zbus::Error) -> bool {
+    let s = e.to_string();
+    s.contains("AuthenticationFailed") || s.contains("InvalidFormat")
+}
+
+/// Explain a failed connect that didn't go through passphrase entry (open or
+/// already-saved networks), where the generic iwd error hides the cause.
+/// `station_state` is `Station.State` read right after the failure; it only
+/// explains errors that don't say what went wrong, since State still reads
+/// "connecting" during the key handshake as well.
+fn diagnose_connect_failure(e: &zbus::Error, network_type: &str, station_state: Option<&str>) -> String {
+    let s = e.to_string();
+    if s.contains("Aborted") || s.contains("Canceled") {
+        format_iwd_error(e)
+    } else if is_auth_failure(e) {
+        if network_type == "8021x" {
+            "Authentication failed, check the network credentials".into()
+        } else {
+            "The saved password was rejected by the network".into()
+        }
+    } else if s.contains("NotSupported") {
+        "This network's security is not supported by the adapter".into()
+    } else if station_state == Some("connecting") {
+        // Link is up but netconfig never finished
+        "Connected to the access point but could not obtain an IP address".into()
+    } else if s.contains("Failed") {
+        "The access point rejected the association".into()
+    } else {
+        format_iwd_error(e)
+    }
+}
+
+/// Parse "aa:bb:cc:dd:ee:ff" into the byte form iwd expects
+fn parse_bssid(bssid: &str) -> Option<[u8; 6]> {
+    let mut bytes = [0u8; 6];
+    let mut parts = bssid.split(':');
+    for byte in &mut bytes {
+        *byte = u8::from_str_radix(parts.next()?, 16).ok()?;
+    }
+    parts.next().is_none().then_some(bytes)
 }
 
 /// Find all iwd Device objects on D-Bus (exist even when WiFi is off)
@@ -115,33 +272,36 @@ pub async fn find_all_iwd_devices(
             .build()
             .await?;
         let device_name = device.name().await.unwrap_or_default();
+        let powered = device.powered().await.unwrap_or(false);
 
-        // Read adapter model via the Device.adapter property
-        let adapter_model = match device.adapter().await {
+        // Model and vendor come from the Adapter (phy) the device belongs
+        // to; both properties are optional in iwd
+        let (adapter_model, adapter_vendor) = match device.adapter().await {
             Ok(adapter_path) => {
                 match AdapterProxy::builder(conn)
                     .path(adapter_path)?
                     .build()
                     .await
                 {
-                    Ok(adapter) => adapter.model().await.unwrap_or_default(),
-                    Err(_) => String::new(),
+                    Ok(adapter) => (
+                        adapter.model().await.unwrap_or_default(),
+                        adapter.vendor().await.unwrap_or_default(),
+                    ),
+                    Err(_) => (String::new(), String::new()),
                 }
             }
-            Err(_) => String::new(),
+            Err(_) => (String::new(), String::new()),
         };
 
-        tracing::info!(
-            "Found iwd device {} at {} ({})",
-            device_name,
-            path,
-            if adapter_model.is_empty() { "unknown" } else { &adapter_model }
-        );
-        devices.push(IwdDeviceInfo {
+        let info = IwdDeviceInfo {
             device_path: path.to_string(),
             device_name,
             adapter_model,
-        });
+            adapter_vendor,
+            powered,
+        };
+        tracing::info!("Found iwd device {} at {}", info.label(), path);
+        devices.push(info);
     }
 
     // Sort by device name for stable ordering
@@ -155,6 +315,30 @@ pub async fn find_all_iwd_devices(
 }
 
 
+/// Create the AdapterProxy for the radio a device belongs to
+async fn create_adapter_proxy_for_device(
+    conn: &zbus::Connection,
+    device_path: &OwnedObjectPath,
+) -> Result<AdapterProxy<'static>, String> {
+    let device = create_device_proxy(conn, device_path).await?;
+    let adapter_path = device
+        .adapter()
+        .await
+        .map_err(|e| format!("Failed to read device adapter: {}", e))?;
+    AdapterProxy::builder(conn)
+        .path(adapter_path)
+        .map_err(|e| format!("Invalid adapter path: {}", e))?
+        .build()
+        .await
+        .map_err(|e| format!("Failed to create adapter proxy: {}", e))
+}
+
+/// Read `Adapter.Powered` for the radio a device belongs to
+pub async fn get_radio_powered(conn: &zbus::Connection, device_path: &OwnedObjectPath) -> Option<bool> {
+    let adapter = create_adapter_proxy_for_device(conn, device_path).await.ok()?;
+    adapter.powered().await.ok()
+}
+
 /// Get list of WiFi networks from iwd Station
 pub async fn get_wifi_networks(
     conn: &zbus::Connection,
@@ -162,46 +346,59 @@ pub async fn get_wifi_networks(
 ) -> Result<Vec<WifiNetworkData>, Box<dyn std::error::Error + Send + Sync>> {
     let ordered = station.get_ordered_networks().await?;
     tracing::debug!("Found {} networks from iwd", ordered.len());
-    let mut networks = Vec::with_capacity(ordered.len());
 
     let connected_path = station.connected_network().await.ok();
+    let connected_path = connected_path.as_ref();
 
-    for (path, signal_strength) in ordered {
-        let network = NetworkProxy::builder(conn)
-            .path(path.clone())?
-            .build()
-            .await?;
-
-        let name = network.name().await.unwrap_or_default();
-        let network_type = network.network_type().await.unwrap_or_else(|_| "open".into());
-        let connected = connected_path
-            .as_ref()
-            .map(|cp| cp.as_str() == path.as_str())
-            .unwrap_or(false);
-        let known = network.known_network().await.is_ok();
-
-        tracing::debug!(
-            "  Network: {} ({} dBm, type={}, connected={}, known={})",
-            name,
-            signal_strength / 100,
-            network_type,
-            connected,
-            known
-        );
-        networks.push(WifiNetworkData {
-            path: path.to_string(),
-            name,
-            network_type,
-            signal_strength,
-            connected,
-            known,
-        });
-    }
+    // A network can vanish between the scan result and reading it; skip it
+    // rather than blanking the whole list
+    let networks = read_each("network", ordered, |(path, signal_strength)| async move {
+        read_wifi_network(conn, path.clone(), signal_strength, connected_path)
+            .await
+            .map_err(|e| format!("{}: {}", path.as_str(), e))
+    })
+    .await;
 
     tracing::info!("Loaded {} WiFi networks", networks.len());
     Ok(networks)
 }
 
+async fn read_wifi_network(
+    conn: &zbus::Connection,
+    path: OwnedObjectPath,
+    signal_strength: i16,
+    connected_path: Option<&OwnedObjectPath>,
+) -> zbus::Result<WifiNetworkData> {
+    let network = NetworkProxy::builder(conn)
+        .path(path.clone())?
+        .build()
+        .await?;
+
+    let name = network.name().await.unwrap_or_default();
+    let network_type = network.network_type().await.unwrap_or_else(|_| "open".into());
+    let connected = connected_path
+        .map(|cp| cp.as_str() == path.as_str())
+        .unwrap_or(false);
+    let known = network.known_network().await.is_ok();
+
+    tracing::debug!(
+        "  Network: {} ({} dBm, type={}, connected={}, known={})",
+        name,
+        signal_strength / 100,
+        network_type,
+        connected,
+        known
+    );
+    Ok(WifiNetworkData {
+        path: path.to_string(),
+        name,
+        network_type,
+        signal_strength,
+        connected,
+        known,
+    })
+}
+
 /// Get all saved (known) networks from iwd via ObjectManager
 pub async fn get_known_networks(
     conn: &zbus::Connection,
@@ -228,11 +425,17 @@ pub async fn get_known_networks(
 
         let name = kn.name().await.unwrap_or_default();
         let network_type = kn.network_type().await.unwrap_or_else(|_| "open".into());
+        let hidden = kn.hidden().await.unwrap_or(false);
+        let auto_connect = kn.auto_connect().await.unwrap_or(true);
+        let last_connected = kn.last_connected_time().await.ok().filter(|t| !t.is_empty());
 
         known_networks.push(KnownNetworkData {
             path: path.to_string(),
             name,
             network_type,
+            hidden,
+            auto_connect,
+            last_connected,
         });
     }
 
@@ -282,23 +485,267 @@ async fn create_device_proxy(
         .map_err(|e| format!("Failed to create device proxy: {}", e))
 }
 
+/// Run a connect call (`Network.Connect()` or `Station.ConnectHiddenNetwork()`)
+/// with a timeout that is suspended while the agent waits for the user to type
+/// a passphrase, and restarts once it's submitted.
+/// Returns None on timeout.
+async fn connect_with_timeout(
+    connect: impl std::future::Future<Output = zbus::Result<()>>,
+    passphrase_prompt: watch::Receiver<bool>,
+) -> Option<zbus::Result<()>> {
+    tokio::pin!(connect);
+
+    let mut prompt = Some(passphrase_prompt);
+    let mut deadline = match prompt.as_mut() {
+        Some(rx) if *rx.borrow_and_update() => None,
+        _ => Some(tokio::time::Instant::now() + CONNECT_TIMEOUT),
+    };
+
+    loop {
+        tokio::select! {
+            result = &mut connect => return Some(result),
+
+            _ = async {
+                match deadline {
+                    Some(d) => tokio::time::sleep_until(d).await,
+                    None => std::future::pending().await,
+                }
+            } => return None,
+
+            changed = async {
+                match prompt.as_mut() {
+                    Some(rx) => rx.changed().await,
+                    None => std::future::pending().await,
+                }
+            } => {
+                if changed.is_err() {
+                    // Agent gone: stop watching, make sure a deadline is running
+                    prompt = None;
+                    if deadline.is_none() {
+                        deadline = Some(tokio::time::Instant::now() + CONNECT_TIMEOUT);
+                    }
+                    continue;
+                }
+                let open = prompt
+                    .as_mut()
+                    .map(|rx| *rx.borrow_and_update())
+                    .unwrap_or(false);
+                deadline = if open {
+                    tracing::debug!("Passphrase prompt open, pausing connect timeout");
+                    None
+                } else {
+                    Some(tokio::time::Instant::now() + CONNECT_TIMEOUT)
+                };
+            }
+        }
+    }
+}
+
+/// Prepare a freshly joined network for "connect once": wait for iwd to
+/// create its KnownNetwork and turn off autoconnect so it isn't rejoined
+/// before it gets forgotten. Returns the KnownNetwork path.
+async fn mark_one_time(
+    conn: &zbus::Connection,
+    network: &NetworkProxy<'static>,
+) -> Option<OwnedObjectPath> {
+    let deadline = tokio::time::Instant::now() + KNOWN_NETWORK_WAIT;
+    let known_path = loop {
+        if let Ok(path) = network.known_network().await {
+            break path;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            tracing::warn!("iwd did not create a KnownNetwork, nothing to forget");
+            return None;
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    };
+
+    match create_known_network_proxy(conn, known_path.clone()).await {
+        Ok(known) => {
+            if let Err(e) = known.set_auto_connect(false).await {
+                tracing::warn!("Failed to disable autoconnect for one-time network: {}", e);
+            }
+        }
+        Err(e) => tracing::warn!("{}", e),
+    }
+    Some(known_path)
+}
+
+/// Forget "connect once" KnownNetworks; false if there were none
+async fn forget_known_networks(conn: &zbus::Connection, paths: Vec<OwnedObjectPath>) -> bool {
+    if paths.is_empty() {
+        return false;
+    }
+    for path in paths {
+        tracing::info!("Forgetting one-time network: {}", path);
+        match create_known_network_proxy(conn, path).await {
+            Ok(known) => {
+                if let Err(e) = known.forget().await {
+                    // Already gone (e.g. forgotten from another tool)
+                    tracing::debug!("Forget one-time network failed: {}", e);
+                }
+            }
+            Err(e) => tracing::debug!("{}", e),
+        }
+    }
+    true
+}
+
+/// Forget the KnownNetwork iwd may have saved for a network whose first
+/// connect failed, and send the updated saved list if there was one
+async fn forget_failed_new_network(
+    conn: &zbus::Connection,
+    network: &NetworkProxy<'static>,
+    evt_tx: &Sender<BackendEvent>,
+) {
+    let Ok(known_path) = network.known_network().await else {
+        return;
+    };
+    tracing::info!("Forgetting network saved by a failed first connect: {}", known_path);
+    match create_known_network_proxy(conn, known_path).await {
+        Ok(known) => {
+            if let Err(e) = known.forget().await {
+                tracing::warn!("Failed to forget network after failed connect: {}", e);
+                return;
+            }
+        }
+        Err(e) => {
+            tracing::warn!("{}", e);
+            return;
+        }
+    }
+    if let Ok(known) = get_known_networks(conn).await {
+        let _ = evt_tx.send(BackendEvent::WifiKnownNetworks(known)).await;
+    }
+}
+
 /// WiFi backend abstraction over iwd
 pub struct WifiBackend {
     conn: zbus::Connection,
     device_path: Option<OwnedObjectPath>,
     evt_tx: Sender<BackendEvent>,
-    /// Handle to abort pending connection task
-    pending_connect: Arc<Mutex<Option<AbortHandle>>>,
+    /// The running connection task, to abort it
+    pending_connect: Arc<Mutex<Option<PendingConnect>>>,
+    /// Whether the agent's passphrase prompt is open (see `connect_with_timeout`)
+    passphrase_prompt: watch::Receiver<bool>,
+    /// Whether the pending connect should keep the network saved.
+    /// The passphrase dialog can still change it while the connect runs.
+    remember: Arc<AtomicBool>,
+    /// KnownNetwork paths of "connect once" networks, forgotten once the
+    /// station leaves them or this backend goes away (iwd disconnects a
+    /// network if it is forgotten while connected)
+    one_time_networks: Arc<Mutex<Vec<OwnedObjectPath>>>,
+    /// Set while a thorough scan runs; plain network updates are held back
+    /// so only its merged list is shown
+    thorough_scanning: Arc<AtomicBool>,
+    /// When the network list was last sent, so periodic refreshes can skip
+    /// right after a scan
+    networks_sent_at: Arc<std::sync::Mutex<Option<std::time::Instant>>>,
+    /// Hash of the last network list sent (see `shown_networks_hash`); an
+    /// identical list isn't sent again
+    networks_hash: Arc<std::sync::Mutex<Option<u64>>>,
+    /// Network lists not sent because nothing visible changed
+    networks_skipped: AtomicU64,
+    /// Orders network list refreshes (see `RefreshGeneration`)
+    networks_generation: Arc<RefreshGeneration>,
+}
+
+/// A connection task: Connect() and then the captive portal checks
+struct PendingConnect {
+    handle: JoinHandle<()>,
+    /// Set until iwd's Connect() has returned; the portal checks that may
+    /// follow don't count as connecting
+    in_flight: Arc<AtomicBool>,
+    /// Request the task still has to answer; taken by whoever answers it
+    request: RequestSlot,
+}
+
+impl PendingConnect {
+    fn new(handle: JoinHandle<()>, in_flight: Arc<AtomicBool>, request: RequestSlot) -> Self {
+        Self { handle, in_flight, request }
+    }
+
+    /// Abort the task and report it abandoned (see `abandon_connect`)
+    async fn abandon(self, evt_tx: &Sender<BackendEvent>, reason: &str) {
+        let finished = self.handle.is_finished();
+        self.handle.abort();
+        let request = self.request.lock().unwrap().take();
+        if finished {
+            return;
+        }
+        if self.in_flight.load(Ordering::SeqCst) {
+            tracing::warn!("Connect in flight aborted: {}", reason);
+            let _ = evt_tx
+                .send(BackendEvent::WifiState(WifiStationState::Disconnected))
+                .await;
+            let _ = evt_tx.send(BackendEvent::WifiConnected(None)).await;
+            finish_request(evt_tx, request, Some(reason.to_string()), BackendEvent::WifiError)
+                .await;
+        } else if request.is_some() {
+            finish_request(evt_tx, request, Some(reason.to_string()), BackendEvent::WifiError)
+                .await;
+        }
+    }
+}
+
+/// A connect task's request, shared so it is answered exactly once
+type RequestSlot = Arc<std::sync::Mutex<Option<RequestId>>>;
+
+/// Counts network list refreshes. Reading the list takes several D-Bus
+/// round trips, so a refresh that finishes after a newer one has started
+/// is dropped rather than overwriting the newer list.
+#[derive(Default)]
+struct RefreshGeneration(AtomicU64);
+
+impl RefreshGeneration {
+    /// Start a refresh; its results are only current until the next one
+    fn begin(&self) -> u64 {
+        self.0.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    fn is_current(&self, generation: u64) -> bool {
+        self.0.load(Ordering::SeqCst) == generation
+    }
+}
+
+/// Hash of what the UI shows for a network list: order, names, state and
+/// signal in whole dBm. The rows show the dBm figure and a trend arrow, so
+/// anything finer than the signal icon has to get through; iwd reports
+/// cBm, and changes under 1 dB don't count.
+fn shown_networks_hash(networks: &[WifiNetworkData]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for network in networks {
+        network.path.hash(&mut hasher);
+        network.name.hash(&mut hasher);
+        network.network_type.hash(&mut hasher);
+        (network.signal_strength / 100).hash(&mut hasher);
+        network.connected.hash(&mut hasher);
+        network.known.hash(&mut hasher);
+    }
+    hasher.finish()
 }
 
 impl WifiBackend {
     /// Create a new WifiBackend for a specific iwd device
-    pub fn new(conn: zbus::Connection, evt_tx: Sender<BackendEvent>, device_path: OwnedObjectPath) -> Self {
+    pub fn new(
+        conn: zbus::Connection,
+        evt_tx: Sender<BackendEvent>,
+        device_path: OwnedObjectPath,
+        passphrase_prompt: watch::Receiver<bool>,
+    ) -> Self {
         Self {
             conn,
             device_path: Some(device_path),
             evt_tx,
             pending_connect: Arc::new(Mutex::new(None)),
+            passphrase_prompt,
+            remember: Arc::new(AtomicBool::new(true)),
+            one_time_networks: Arc::new(Mutex::new(Vec::new())),
+            thorough_scanning: Arc::new(AtomicBool::new(false)),
+            networks_sent_at: Arc::new(std::sync::Mutex::new(None)),
+            networks_hash: Arc::new(std::sync::Mutex::new(None)),
+            networks_skipped: AtomicU64::new(0),
+            networks_generation: Arc::new(RefreshGeneration::default()),
         }
     }
 
@@ -328,23 +775,187 @@ impl WifiBackend {
         }
     }
 
-    /// Connect to a WiFi network (spawns a task for passphrase handling)
-    pub async fn connect(&self, path: &str) {
-        tracing::info!("Connecting to WiFi network: {}", path);
+    /// Report the current Station.Scanning value; no station means not scanning
+    pub async fn refresh_scanning(&self) {
+        let scanning = match self.station().await {
+            Some(station) => station.scanning().await.unwrap_or(false),
+            None => false,
+        };
+        tracing::debug!("Refreshed scanning state: {}", scanning);
+        let _ = self.evt_tx.send(BackendEvent::WifiScanning(scanning)).await;
+    }
 
-        // Abort any previous pending connection using async lock (no race condition)
-        {
-            let mut guard = self.pending_connect.lock().await;
-            if let Some(prev_handle) = guard.take() {
-                tracing::debug!("Aborting previous connection attempt");
-                prev_handle.abort();
+    /// Run several scans spaced apart and present the union of what they
+    /// found, for adapters that miss weak or distant APs in a single pass.
+    /// Best-effort: iwd picks the channels itself, this only gives it more
+    /// chances. Runs in its own task so the event loop stays responsive.
+    pub fn thorough_scan(&self) {
+        if self.thorough_scanning.swap(true, Ordering::SeqCst) {
+            tracing::debug!("Thorough scan already running");
+            return;
+        }
+        let conn = self.conn.clone();
+        let evt_tx = self.evt_tx.clone();
+        let device_path = self.device_path.clone();
+        let thorough_scanning = self.thorough_scanning.clone();
+        let networks_hash = self.networks_hash.clone();
+        let networks_generation = self.networks_generation.clone();
+        tokio::spawn(async move {
+            let networks = match device_path {
+                Some(path) => Self::run_thorough_scan(&conn, &evt_tx, path).await,
+                None => None,
+            };
+            thorough_scanning.store(false, Ordering::SeqCst);
+            if let Some(networks) = networks {
+                // The merged list replaces whatever was last sent, and
+                // supersedes any plain refresh still reading its list
+                networks_generation.begin();
+                *networks_hash.lock().unwrap() = None;
+                let _ = evt_tx.send(BackendEvent::WifiNetworks(networks)).await;
             }
+            let _ = evt_tx.send(BackendEvent::WifiThoroughScan(None)).await;
+        });
+    }
+
+    async fn run_thorough_scan(
+        conn: &zbus::Connection,
+        evt_tx: &Sender<BackendEvent>,
+        device_path: OwnedObjectPath,
+    ) -> Option<Vec<WifiNetworkData>> {
+        let station = StationProxy::builder(conn)
+            .path(device_path)
+            .ok()?
+            .build()
+            .await
+            .ok()?;
+
+        let mut merged: HashMap<String, WifiNetworkData> = HashMap::new();
+        let mut latest: Vec<WifiNetworkData> = Vec::new();
+        for cycle in 1..=THOROUGH_SCAN_CYCLES {
+            let _ = evt_tx
+                .send(BackendEvent::WifiThoroughScan(Some((cycle, THOROUGH_SCAN_CYCLES))))
+                .await;
+            if cycle > 1 {
+                tokio::time::sleep(THOROUGH_SCAN_SPACING).await;
+            }
+
+            // Subscribe before scanning so the completion isn't missed
+            let scanning = property_values(station.receive_scanning_changed().await);
+            tracing::info!("Thorough scan {}/{}", cycle, THOROUGH_SCAN_CYCLES);
+            match station.scan().await {
+                Ok(()) => {}
+                // iwd is already scanning on its own; wait for that one
+                Err(e) if e.to_string().contains("Busy") => {}
+                Err(e) => {
+                    tracing::error!("Thorough scan failed: {}", e);
+                    let _ = evt_tx.send(BackendEvent::WifiError(format!("Scan: {}", e))).await;
+                    break;
+                }
+            }
+            if wait_for_property(scanning, |s| !*s, THOROUGH_SCAN_CYCLE_TIMEOUT)
+                .await
+                .is_none()
+            {
+                tracing::warn!("Thorough scan cycle {} did not finish in time", cycle);
+            }
+
+            match get_wifi_networks(conn, &station).await {
+                Ok(networks) => latest = networks,
+                Err(e) => {
+                    tracing::warn!("Failed to read networks during thorough scan: {}", e);
+                    continue;
+                }
+            }
+            // Keep the strongest reading of each network across cycles
+            for network in &latest {
+                merged
+                    .entry(network.path.clone())
+                    .and_modify(|seen| {
+                        seen.signal_strength = seen.signal_strength.max(network.signal_strength)
+                    })
+                    .or_insert_with(|| network.clone());
+            }
+        }
+
+        // Connected/known come from the latest read. Networks iwd has dropped
+        // since an earlier cycle can't be joined anymore, so leave them out.
+        let mut networks = Vec::with_capacity(merged.len());
+        for (path, mut network) in merged {
+            if let Some(current) = latest.iter().find(|n| n.path == path) {
+                network.connected = current.connected;
+                network.known = current.known;
+            } else if !Self::network_exists(conn, &path).await {
+                continue;
+            }
+            networks.push(network);
         }
+        networks.sort_by(|a, b| b.signal_strength.cmp(&a.signal_strength));
+        tracing::info!("Thorough scan found {} networks", networks.len());
+        Some(networks)
+    }
+
+    /// Whether iwd still has a Network object at `path`
+    async fn network_exists(conn: &zbus::Connection, path: &str) -> bool {
+        let Ok(builder) = NetworkProxy::builder(conn).path(path.to_string()) else {
+            return false;
+        };
+        match builder.build().await {
+            Ok(network) => network.name().await.is_ok(),
+            Err(_) => false,
+        }
+    }
+
+    /// Refresh the network list with a scan the user didn't ask for (power-on,
+    /// startup). Skipped if iwd is already scanning, e.g. its own scan after
+    /// the station comes up; failures are only logged.
+    pub async fn scan_if_idle(&self) {
+        let Some(station) = self.station().await else { return };
+        match station.scanning().await {
+            Ok(false) => {}
+            Ok(true) => {
+                tracing::debug!("Already scanning, skipping automatic scan");
+                return;
+            }
+            Err(e) => {
+                tracing::debug!("Station not ready for automatic scan: {}", e);
+                return;
+            }
+        }
+        tracing::info!("Starting automatic WiFi scan");
+        if let Err(e) = station.scan().await {
+            tracing::debug!("Automatic scan failed: {}", e);
+        }
+    }
+
+    /// Connect to a WiFi network (spawns a task for passphrase handling).
+    /// With `remember == false` the network is not kept in iwd's saved list.
+    /// With `forget_if_failed`, a network that wasn't saved before is
+    /// forgotten again when the connect fails.
+    /// The outcome is reported for `request`, if the UI tagged one.
+    pub async fn connect(
+        &self,
+        path: &str,
+        remember: bool,
+        forget_if_failed: bool,
+        request: Option<RequestId>,
+    ) {
+        tracing::info!("Connecting to WiFi network: {} (remember: {})", path, remember);
+
+        self.abort_superseded_connect().await;
 
         let path = path.to_string();
         let conn = self.conn.clone();
         let evt_tx = self.evt_tx.clone();
         let device_path = self.device_path.clone();
+        let passphrase_prompt = self.passphrase_prompt.clone();
+        self.remember.store(remember, Ordering::Relaxed);
+        let remember = self.remember.clone();
+        let one_time_networks = self.one_time_networks.clone();
+        let in_flight = Arc::new(AtomicBool::new(true));
+        let task_in_flight = in_flight.clone();
+        let request_slot: RequestSlot = Arc::new(std::sync::Mutex::new(request));
+        let task_request = request_slot.clone();
+        let take_request = move || task_request.lock().unwrap().take();
 
         // Spawn connect in separate task to not block passphrase handling
         let handle = tokio::spawn(async move {
@@ -354,40 +965,111 @@ impl WifiBackend {
                 Ok(n) => n,
                 Err(e) => {
                     tracing::error!("{}", e);
+                    task_in_flight.store(false, Ordering::SeqCst);
                     let _ = evt_tx.send(BackendEvent::WifiConnected(None)).await;
-                    let _ = evt_tx.send(BackendEvent::WifiError("Invalid network path".into())).await;
+                    finish_request(
+                        &evt_tx,
+                        take_request(),
+                        Some("Invalid network path".into()),
+                        BackendEvent::WifiError,
+                    )
+                    .await;
                     return;
                 }
             };
 
-            // 60 second timeout - enough for password entry, protects against iwd hangs
-            const CONNECT_TIMEOUT_SECS: u64 = 60;
-            let connect_timeout = std::time::Duration::from_secs(CONNECT_TIMEOUT_SECS);
-            match tokio::time::timeout(connect_timeout, network.connect()).await {
-                Ok(Ok(())) => {
+            // Open and saved networks connect without the agent; remember that so a
+            // failure can be explained beyond iwd's generic error
+            let network_type = network.network_type().await.unwrap_or_default();
+            let was_known = network.known_network().await.is_ok();
+            let needs_passphrase = network_type != "open" && !was_known;
+            // A failed first attempt can still leave a saved network behind
+            let forget_new = forget_if_failed && !was_known;
+
+            let result = connect_with_timeout(network.connect(), passphrase_prompt).await;
+            task_in_flight.store(false, Ordering::SeqCst);
+            match result {
+                Some(Ok(())) => {
                     tracing::info!("Connected to {}", path);
+                    // iwd replies to Connect() before netconfig finishes; State stays
+                    // "connecting" until an address is obtained
+                    let station_state = Self::get_station_state_static(&conn, device_path.as_ref()).await;
+                    if station_state.as_deref() == Some("connecting") {
+                        let _ = evt_tx
+                            .send(BackendEvent::WifiState(WifiStationState::ObtainingAddress))
+                            .await;
+                    }
                     let _ = evt_tx.send(BackendEvent::WifiConnected(Some(path.clone()))).await;
-                    let _ = evt_tx.send(BackendEvent::WifiNetworkKnown { path }).await;
+                    if remember.load(Ordering::Relaxed) {
+                        let _ = evt_tx.send(BackendEvent::WifiNetworkKnown { path }).await;
+                    } else if let Some(known_path) = mark_one_time(&conn, &network).await {
+                        one_time_networks.lock().await.push(known_path);
+                    }
+                    finish_request(&evt_tx, take_request(), None, BackendEvent::WifiError).await;
 
                     // Check for captive portal
                     if let Some(portal_url) = check_captive_portal().await {
                         let _ = evt_tx
                             .send(BackendEvent::CaptivePortal { url: portal_url })
                             .await;
+                        Self::wait_for_portal_login(&conn, device_path.as_ref(), &path, &evt_tx).await;
                     }
                 }
-                Ok(Err(e)) => {
+                Some(Err(e)) if !needs_passphrase && network_type == "psk" && is_auth_failure(&e) => {
+                    // The stored key is stale (password changed on the router).
+                    // iwd keeps using it and never invokes the agent, so let the
+                    // UI offer to replace it instead of showing a plain error
+                    tracing::error!("Saved password rejected for {}: {}", path, e);
+                    let actual_connected = Self::get_connected_network_static(&conn, device_path.as_ref()).await;
+                    let _ = evt_tx.send(BackendEvent::WifiConnected(actual_connected)).await;
+                    let _ = evt_tx.send(BackendEvent::WifiAuthFailed { path }).await;
+                    finish_request(&evt_tx, take_request(), None, BackendEvent::WifiError).await;
+                }
+                Some(Err(e)) => {
                     tracing::error!("Connect failed: {}", e);
+                    let message = if needs_passphrase {
+                        format_iwd_error(&e)
+                    } else {
+                        let station_state =
+                            Self::get_station_state_static(&conn, device_path.as_ref()).await;
+                        tracing::debug!("Station state after failed connect: {:?}", station_state);
+                        diagnose_connect_failure(&e, &network_type, station_state.as_deref())
+                    };
                     // Query actual state from iwd to preserve existing connection
                     let actual_connected = Self::get_connected_network_static(&conn, device_path.as_ref()).await;
                     let _ = evt_tx.send(BackendEvent::WifiConnected(actual_connected)).await;
-                    let _ = evt_tx.send(BackendEvent::WifiError(format_iwd_error(&e))).await;
+                    if forget_new {
+                        forget_failed_new_network(&conn, &network, &evt_tx).await;
+                    }
+                    finish_request(
+                        &evt_tx,
+                        take_request(),
+                        Some(message),
+                        BackendEvent::WifiError,
+                    )
+                    .await;
                 }
-                Err(_) => {
+                None => {
                     tracing::error!("Connect timed out for {}", path);
+                    let station_state =
+                        Self::get_station_state_static(&conn, device_path.as_ref()).await;
+                    let message = if station_state.as_deref() == Some("connecting") && !needs_passphrase {
+                        "Connection timed out while obtaining an IP address"
+                    } else {
+                        "Connection timed out"
+                    };
                     let actual_connected = Self::get_connected_network_static(&conn, device_path.as_ref()).await;
                     let _ = evt_tx.send(BackendEvent::WifiConnected(actual_connected)).await;
-                    let _ = evt_tx.send(BackendEvent::WifiError("Connection timed out".into())).await;
+                    if forget_new {
+                        forget_failed_new_network(&conn, &network, &evt_tx).await;
+                    }
+                    finish_request(
+                        &evt_tx,
+                        take_request(),
+                        Some(message.into()),
+                        BackendEvent::WifiError,
+                    )
+                    .await;
                 }
             }
             // NOTE: we intentionally do NOT clear pending_connect here.
@@ -398,7 +1080,150 @@ impl WifiBackend {
 
         // Store abort handle for this connection task
         let mut guard = self.pending_connect.lock().await;
-        *guard = Some(handle.abort_handle());
+        *guard = Some(PendingConnect::new(handle, in_flight, request_slot));
+    }
+
+    /// Connect to a hidden network by SSID. It has no Network object until
+    /// iwd finds it, so the station state streams report the connection;
+    /// the request only says whether the attempt worked. On success iwd
+    /// saves the network as a hidden KnownNetwork.
+    pub async fn connect_hidden(&self, name: &str, request: RequestId) {
+        tracing::info!("Connecting to hidden WiFi network: {}", name);
+
+        self.abort_superseded_connect().await;
+
+        let Some(station) = self.station().await else {
+            finish_request(
+                &self.evt_tx,
+                Some(request),
+                Some("No WiFi device".into()),
+                BackendEvent::WifiError,
+            )
+            .await;
+            return;
+        };
+        let name = name.to_string();
+        let conn = self.conn.clone();
+        let evt_tx = self.evt_tx.clone();
+        let device_path = self.device_path.clone();
+        let passphrase_prompt = self.passphrase_prompt.clone();
+        self.remember.store(true, Ordering::Relaxed);
+        let in_flight = Arc::new(AtomicBool::new(true));
+        let task_in_flight = in_flight.clone();
+        let request_slot: RequestSlot = Arc::new(std::sync::Mutex::new(Some(request)));
+        let task_request = request_slot.clone();
+        let take_request = move || task_request.lock().unwrap().take();
+
+        let handle = tokio::spawn(async move {
+            let result =
+                connect_with_timeout(station.connect_hidden_network(&name), passphrase_prompt)
+                    .await;
+            task_in_flight.store(false, Ordering::SeqCst);
+            let (message, not_found) = match result {
+                Some(Ok(())) => {
+                    tracing::info!("Connected to hidden network {}", name);
+                    finish_request(&evt_tx, take_request(), None, BackendEvent::WifiError).await;
+                    return;
+                }
+                Some(Err(e)) => {
+                    tracing::error!("Hidden connect to {} failed: {}", name, e);
+                    if is_not_found(&e) {
+                        (format!("\"{}\" was not found nearby", name), true)
+                    } else {
+                        (format_iwd_error(&e), false)
+                    }
+                }
+                None => {
+                    tracing::error!("Hidden connect timed out for {}", name);
+                    ("Connection timed out".to_string(), false)
+                }
+            };
+            let actual_connected = Self::get_connected_network_static(&conn, device_path.as_ref()).await;
+            let _ = evt_tx.send(BackendEvent::WifiConnected(actual_connected)).await;
+            match take_request() {
+                Some(request) if not_found => {
+                    let _ = evt_tx
+                        .send(BackendEvent::WifiHiddenNotFound { request, error: message })
+                        .await;
+                }
+                request => {
+                    finish_request(&evt_tx, request, Some(message), BackendEvent::WifiError).await
+                }
+            }
+        });
+
+        let mut guard = self.pending_connect.lock().await;
+        *guard = Some(PendingConnect::new(handle, in_flight, request_slot));
+    }
+
+    /// Update the remember choice for the pending connect (from the passphrase dialog)
+    pub fn set_remember(&self, remember: bool) {
+        self.remember.store(remember, Ordering::Relaxed);
+    }
+
+    /// Forget "connect once" networks the station is no longer on. Called
+    /// whenever the station leaves "connected": moving straight to another
+    /// network never passes through "disconnected".
+    pub async fn forget_left_one_time_networks(&self) {
+        if self.one_time_networks.lock().await.is_empty() {
+            return;
+        }
+        let current = self.connected_known_network().await;
+        let left: Vec<_> = {
+            let mut networks = self.one_time_networks.lock().await;
+            let (kept, left): (Vec<_>, Vec<_>) =
+                networks.drain(..).partition(|path| Some(path) == current.as_ref());
+            *networks = kept;
+            left
+        };
+        if forget_known_networks(&self.conn, left).await {
+            self.send_networks().await;
+            self.send_known_networks().await;
+        }
+    }
+
+    /// Forget every "connect once" network, connected or not, before this
+    /// backend goes away (adapter switch, exit); nothing would forget them
+    /// later. Only the saved list is sent: the station may no longer be
+    /// the one shown.
+    pub async fn forget_one_time_networks(&self) {
+        let paths: Vec<_> = self.one_time_networks.lock().await.drain(..).collect();
+        if forget_known_networks(&self.conn, paths).await {
+            self.send_known_networks().await;
+        }
+    }
+
+    /// KnownNetwork of the network the station is on or moving to
+    async fn connected_known_network(&self) -> Option<OwnedObjectPath> {
+        let station = self.station().await?;
+        let path = station.connected_network().await.ok()?;
+        let network = create_network_proxy(&self.conn, path.as_str()).await.ok()?;
+        network.known_network().await.ok()
+    }
+
+    /// Re-probe while the user logs in to a captive portal, and report once
+    /// the internet is reachable. Stops if the network is left or after about
+    /// a minute; the task is also aborted on disconnect.
+    async fn wait_for_portal_login(
+        conn: &zbus::Connection,
+        device_path: Option<&OwnedObjectPath>,
+        path: &str,
+        evt_tx: &Sender<BackendEvent>,
+    ) {
+        let Some(client) = probe_client() else { return };
+        for _ in 0..PORTAL_REPROBE_ATTEMPTS {
+            tokio::time::sleep(PORTAL_REPROBE_INTERVAL).await;
+            if Self::get_connected_network_static(conn, device_path).await.as_deref() != Some(path) {
+                tracing::debug!("Left portal network, stop re-probing");
+                return;
+            }
+            if let ProbeResult::Online = probe_connectivity(&client).await {
+                tracing::info!("Captive portal login completed");
+                let _ = evt_tx.send(BackendEvent::CaptivePortalCleared).await;
+                return;
+            }
+        }
+        tracing::debug!("Still behind captive portal, stop re-probing");
     }
 
     /// Helper for connect task - get connected network without &self
@@ -416,40 +1241,124 @@ impl WifiBackend {
         station.connected_network().await.ok().map(|p| p.to_string())
     }
 
+    /// Helper for connect task - get raw Station.State without &self
+    async fn get_station_state_static(
+        conn: &zbus::Connection,
+        device_path: Option<&OwnedObjectPath>,
+    ) -> Option<String> {
+        let path = device_path?;
+        let station = StationProxy::builder(conn)
+            .path(path.clone())
+            .ok()?
+            .build()
+            .await
+            .ok()?;
+        station.state().await.ok()
+    }
+
+    /// Abort a connection attempt a new one replaces. Aborting the task
+    /// doesn't stop iwd, which may still be associating with the abandoned
+    /// network and would answer the new Connect with Busy, so an attempt
+    /// that was still in progress is disconnected first. The task is
+    /// awaited, so nothing it sends can arrive after the new attempt's
+    /// events.
+    async fn abort_superseded_connect(&self) {
+        // Async lock, so a concurrent connect can't slip its handle in between
+        let Some(prev) = self.pending_connect.lock().await.take() else {
+            return;
+        };
+        if prev.handle.is_finished() {
+            return;
+        }
+        tracing::debug!("Aborting previous connection attempt");
+        prev.handle.abort();
+        let _ = prev.handle.await;
+        // The replaced attempt didn't fail; just stop waiting on it
+        let request = prev.request.lock().unwrap().take();
+        finish_request(&self.evt_tx, request, None, BackendEvent::WifiError).await;
+
+        let Some(station) = self.station().await else { return };
+        if station.state().await.ok().as_deref() != Some("connecting") {
+            // Already connected (e.g. waiting on a captive portal); the new
+            // Connect switches networks by itself
+            return;
+        }
+        tracing::info!("Disconnecting abandoned connection attempt");
+        if let Err(e) = station.disconnect().await {
+            tracing::debug!("Disconnect of abandoned attempt failed: {}", e);
+        }
+    }
+
     /// Abort any pending connection task (connect + captive portal check)
     async fn abort_pending_connect(&self) {
         let mut guard = self.pending_connect.lock().await;
-        if let Some(handle) = guard.take() {
+        if let Some(pending) = guard.take() {
             tracing::debug!("Aborting pending connection task");
-            handle.abort();
+            pending.handle.abort();
         }
     }
 
     /// Disconnect from current WiFi network
-    pub async fn disconnect(&self) {
+    pub async fn disconnect(&self, request: RequestId) {
         self.abort_pending_connect().await;
-        let Some(station) = self.station().await else { return };
+        let Some(station) = self.station().await else {
+            finish_request(&self.evt_tx, Some(request), None, BackendEvent::WifiError).await;
+            return;
+        };
         tracing::info!("Disconnecting from WiFi");
         match station.disconnect().await {
             Ok(()) => {
                 let _ = self.evt_tx.send(BackendEvent::WifiConnected(None)).await;
+                finish_request(&self.evt_tx, Some(request), None, BackendEvent::WifiError).await;
             }
             Err(e) => {
                 tracing::error!("Disconnect failed: {}", e);
-                let _ = self.evt_tx.send(BackendEvent::WifiError(format!("Disconnect: {}", e))).await;
+                finish_request(
+                    &self.evt_tx,
+                    Some(request),
+                    Some(format!("Disconnect: {}", e)),
+                    BackendEvent::WifiError,
+                )
+                .await;
             }
         }
     }
 
+    /// Disconnect first when the saved network about to be forgotten is the
+    /// current connection. Forgetting the active network leaves iwd's station
+    /// in a half-torn-down state for a while; disconnecting makes it a plain
+    /// disconnect the UI can show. A failed disconnect doesn't stop the forget.
+    async fn disconnect_before_forget(&self, known_path: &OwnedObjectPath) {
+        let connected_known = self.connected_known_network().await;
+        if !is_connected_known(connected_known.as_ref().map(|p| p.as_str()), known_path.as_str()) {
+            return;
+        }
+        let Some(station) = self.station().await else { return };
+        tracing::info!("Disconnecting before forgetting the connected network");
+        self.abort_pending_connect().await;
+        match station.disconnect().await {
+            Ok(()) => {
+                let _ = self.evt_tx.send(BackendEvent::WifiConnected(None)).await;
+            }
+            Err(e) => tracing::warn!("Disconnect before forget failed: {}", e),
+        }
+    }
+
     /// Forget a known network
-    pub async fn forget(&self, network_path: &str) {
+    pub async fn forget(&self, network_path: &str, request: RequestId) {
         tracing::info!("Forgetting network: {}", network_path);
 
         let network = match create_network_proxy(&self.conn, network_path).await {
             Ok(n) => n,
             Err(e) => {
                 tracing::error!("{}", e);
-                let _ = self.evt_tx.send(BackendEvent::WifiError("Invalid network path".into())).await;
+                finish_request(
+                    &self.evt_tx,
+                    Some(request),
+                    Some("Invalid network path".into()),
+                    BackendEvent::WifiError,
+                )
+                .await;
                 return;
             }
         };
@@ -458,40 +1367,98 @@ impl WifiBackend {
             Ok(p) => p,
             Err(e) => {
                 tracing::error!("Network is not known: {}", e);
-                let _ = self.evt_tx.send(BackendEvent::WifiError("Network is not saved".into())).await;
+                finish_request(
+                    &self.evt_tx,
+                    Some(request),
+                    Some("Network is not saved".into()),
+                    BackendEvent::WifiError,
+                )
+                .await;
                 return;
             }
         };
 
-        let known = match create_known_network_proxy(&self.conn, known_path).await {
+        let known = match create_known_network_proxy(&self.conn, known_path.clone()).await {
             Ok(k) => k,
             Err(e) => {
                 tracing::error!("{}", e);
-                let _ = self.evt_tx.send(BackendEvent::WifiError("Failed to forget network".into())).await;
+                finish_request(
+                    &self.evt_tx,
+                    Some(request),
+                    Some("Failed to forget network".into()),
+                    BackendEvent::WifiError,
+                )
+                .await;
                 return;
             }
         };
 
+        self.disconnect_before_forget(&known_path).await;
         match known.forget().await {
             Ok(()) => {
                 tracing::info!("Forgot network: {}", network_path);
                 self.send_networks().await;
                 self.send_known_networks().await;
+                finish_request(&self.evt_tx, Some(request), None, BackendEvent::WifiError).await;
             }
             Err(e) => {
                 tracing::error!("Forget failed: {}", e);
-                let _ = self.evt_tx.send(BackendEvent::WifiError(format!("Forget: {}", e))).await;
+                finish_request(
+                    &self.evt_tx,
+                    Some(request),
+                    Some(format!("Forget: {}", e)),
+                    BackendEvent::WifiError,
+                )
+                .await;
             }
         }
     }
 
-    /// Set WiFi adapter power state
-    pub async fn set_powered(&self, powered: bool) {
+    /// Forget a saved network's stored credentials and reconnect, so iwd
+    /// falls back to the agent and prompts for the new password
+    pub async fn update_password(&self, network_path: &str) {
+        tracing::info!("Updating password for network: {}", network_path);
+
+        let known_path = match create_network_proxy(&self.conn, network_path).await {
+            Ok(network) => network.known_network().await.ok(),
+            Err(e) => {
+                tracing::error!("{}", e);
+                let _ = self.evt_tx.send(BackendEvent::WifiError("Invalid network path".into())).await;
+                return;
+            }
+        };
+
+        // Already forgotten (e.g. from another tool): just connect
+        if let Some(known_path) = known_path {
+            let result = match create_known_network_proxy(&self.conn, known_path).await {
+                Ok(known) => known.forget().await.map_err(|e| e.to_string()),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                tracing::error!("Forget before password update failed: {}", e);
+                let _ = self.evt_tx.send(BackendEvent::WifiError("Failed to reset saved password".into())).await;
+                return;
+            }
+        }
+
+        self.connect(network_path, true, false, None).await;
+    }
+
+    /// Set WiFi power state through the chosen iwd switch
+    pub async fn set_powered(&self, powered: bool, control: WifiPowerControl) {
         if !powered {
             self.abort_pending_connect().await;
         }
         let Some(ref path) = self.device_path else { return };
 
+        if control == WifiPowerControl::Adapter {
+            self.set_radio_powered(path, powered).await;
+            if !powered {
+                return;
+            }
+            // Radio is back up; make sure the interface follows
+        }
+
         let device = match create_device_proxy(&self.conn, path).await {
             Ok(d) => d,
             Err(e) => {
@@ -512,50 +1479,187 @@ impl WifiBackend {
         }
     }
 
+    /// Power the device off and on again so iwd sets it up from scratch.
+    /// The power-on is handled like any other, including the Station check.
+    pub async fn restart_device(&self) {
+        self.abort_pending_connect().await;
+        let Some(ref path) = self.device_path else { return };
+        let device = match create_device_proxy(&self.conn, path).await {
+            Ok(d) => d,
+            Err(e) => {
+                tracing::error!("{}", e);
+                let _ = self.evt_tx.send(BackendEvent::WifiError(format!("Power: {}", e))).await;
+                return;
+            }
+        };
+
+        tracing::info!("Restarting WiFi device");
+        let result = match device.set_powered(false).await {
+            Ok(()) => device.set_powered(true).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            tracing::error!("Restart failed: {}", e);
+            let _ = self.evt_tx.send(BackendEvent::WifiError(format!("Power: {}", e))).await;
+            if let Ok(actual) = device.powered().await {
+                let _ = self.evt_tx.send(BackendEvent::WifiPowered(actual)).await;
+            }
+        }
+    }
+
+    /// Set `Adapter.Powered` on the radio of the given device
+    async fn set_radio_powered(&self, device_path: &OwnedObjectPath, powered: bool) {
+        let adapter = match create_adapter_proxy_for_device(&self.conn, device_path).await {
+            Ok(a) => a,
+            Err(e) => {
+                tracing::error!("{}", e);
+                let _ = self.evt_tx.send(BackendEvent::WifiError(format!("Power: {}", e))).await;
+                return;
+            }
+        };
+
+        tracing::info!("Setting WiFi radio powered: {}", powered);
+        if let Err(e) = adapter.set_powered(powered).await {
+            tracing::error!("Set radio powered failed: {}", e);
+            let _ = self.evt_tx.send(BackendEvent::WifiError(format!("Power: {}", e))).await;
+        }
+        if let Ok(actual) = adapter.powered().await {
+            let _ = self.evt_tx.send(BackendEvent::WifiRadioPowered(actual)).await;
+        }
+    }
+
+    /// Re-send saved networks, power and the connection, for changes made
+    /// outside wlcontrol (iwctl). The network list is left to the caller,
+    /// which throttles it.
+    pub async fn refresh(&self) {
+        self.send_radio_powered().await;
+        self.send_known_networks().await;
+        self.send_connected_status().await;
+        if let Some(station) = self.station().await {
+            if let Ok(state) = station.state().await {
+                self.send_station_state(&state).await;
+            }
+        }
+    }
+
+    /// Send current radio (Adapter.Powered) state to UI
+    pub async fn send_radio_powered(&self) {
+        let Some(ref path) = self.device_path else { return };
+        if let Some(powered) = get_radio_powered(&self.conn, path).await {
+            let _ = self.evt_tx.send(BackendEvent::WifiRadioPowered(powered)).await;
+        }
+    }
+
     /// Forget a saved network using its KnownNetwork D-Bus path directly.
     /// Used for saved-offline networks that have no Network object.
-    pub async fn forget_known(&self, known_path: &str) {
+    pub async fn forget_known(&self, known_path: &str, request: RequestId) {
         tracing::info!("Forgetting known network: {}", known_path);
 
         let owned_path: OwnedObjectPath = match known_path.try_into() {
             Ok(p) => p,
             Err(e) => {
                 tracing::error!("Invalid known network path: {}", e);
-                let _ = self.evt_tx.send(BackendEvent::WifiError("Invalid path".into())).await;
+                finish_request(
+                    &self.evt_tx,
+                    Some(request),
+                    Some("Invalid path".into()),
+                    BackendEvent::WifiError,
+                )
+                .await;
                 return;
             }
         };
 
-        let known = match create_known_network_proxy(&self.conn, owned_path).await {
+        let known = match create_known_network_proxy(&self.conn, owned_path.clone()).await {
             Ok(k) => k,
             Err(e) => {
                 tracing::error!("{}", e);
-                let _ = self.evt_tx.send(BackendEvent::WifiError("Failed to forget network".into())).await;
+                finish_request(
+                    &self.evt_tx,
+                    Some(request),
+                    Some("Failed to forget network".into()),
+                    BackendEvent::WifiError,
+                )
+                .await;
                 return;
             }
         };
 
+        self.disconnect_before_forget(&owned_path).await;
         match known.forget().await {
             Ok(()) => {
                 tracing::info!("Forgot known network: {}", known_path);
                 self.send_networks().await;
                 self.send_known_networks().await;
+                finish_request(&self.evt_tx, Some(request), None, BackendEvent::WifiError).await;
             }
             Err(e) => {
                 tracing::error!("Forget known failed: {}", e);
-                let _ = self.evt_tx.send(BackendEvent::WifiError(format!("Forget: {}", e))).await;
+                finish_request(
+                    &self.evt_tx,
+                    Some(request),
+                    Some(format!("Forget: {}", e)),
+                    BackendEvent::WifiError,
+                )
+                .await;
             }
         }
     }
 
+    /// Set whether iwd joins a saved network on its own
+    pub async fn set_auto_connect(&self, known_path: &str, enabled: bool) {
+        let owned_path: OwnedObjectPath = match known_path.try_into() {
+            Ok(p) => p,
+            Err(e) => {
+                tracing::error!("Invalid known network path: {}", e);
+                return;
+            }
+        };
+        let result = match create_known_network_proxy(&self.conn, owned_path).await {
+            Ok(known) => known.set_auto_connect(enabled).await.map_err(|e| e.to_string()),
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(()) => self.send_known_networks().await,
+            Err(e) => tracing::warn!("Failed to set autoconnect for {}: {}", known_path, e),
+        }
+    }
+
     /// Send current network list to UI
     pub async fn send_networks(&self) {
+        if self.thorough_scanning.load(Ordering::SeqCst) {
+            return;
+        }
+        let generation = self.networks_generation.begin();
         let Some(station) = self.station().await else { return };
         if let Ok(networks) = get_wifi_networks(&self.conn, &station).await {
+            if !self.networks_generation.is_current(generation) {
+                tracing::debug!("Network list superseded by a newer refresh, dropped");
+                return;
+            }
+            *self.networks_sent_at.lock().unwrap() = Some(std::time::Instant::now());
+            let hash = shown_networks_hash(&networks);
+            if self.networks_hash.lock().unwrap().replace(hash) == Some(hash) {
+                let skipped = self.networks_skipped.fetch_add(1, Ordering::Relaxed) + 1;
+                tracing::debug!("Network list unchanged, not resent ({} skipped)", skipped);
+                return;
+            }
             let _ = self.evt_tx.send(BackendEvent::WifiNetworks(networks)).await;
         }
     }
 
+    /// Send an empty network list to UI (device powered off)
+    pub async fn send_no_networks(&self) {
+        self.networks_generation.begin();
+        *self.networks_hash.lock().unwrap() = None;
+        let _ = self.evt_tx.send(BackendEvent::WifiNetworks(vec![])).await;
+    }
+
+    /// Time since the network list was last sent
+    pub fn networks_age(&self) -> Option<std::time::Duration> {
+        self.networks_sent_at.lock().unwrap().map(|t| t.elapsed())
+    }
+
     /// Send all known (saved) networks to UI
     pub async fn send_known_networks(&self) {
         if let Ok(known) = get_known_networks(&self.conn).await {
@@ -570,15 +1674,124 @@ impl WifiBackend {
         let _ = self.evt_tx.send(BackendEvent::WifiConnected(connected)).await;
     }
 
+    /// Send the station state and connected network, read fresh from iwd.
+    /// A device without a Station (powered off) is reported as disconnected.
+    pub async fn send_connection_state(&self) {
+        let Some(station) = self.station().await else {
+            let _ = self.evt_tx.send(BackendEvent::WifiState(WifiStationState::Disconnected)).await;
+            let _ = self.evt_tx.send(BackendEvent::WifiConnected(None)).await;
+            return;
+        };
+        if let Ok(state) = station.state().await {
+            self.send_station_state(&state).await;
+        }
+        self.send_connected_status().await;
+    }
+
+    /// Translate an iwd Station.State string and send it to UI
+    pub async fn send_station_state(&self, state: &str) {
+        let Some(station) = self.station().await else { return };
+        let network_type = match station.connected_network().await {
+            Ok(path) => match create_network_proxy(&self.conn, path.as_str()).await {
+                Ok(network) => network.network_type().await.ok(),
+                Err(_) => None,
+            },
+            Err(_) => None,
+        };
+        let state = WifiStationState::from_iwd(state, network_type.as_deref());
+        let _ = self.evt_tx.send(BackendEvent::WifiState(state)).await;
+    }
+
+    /// Read the BSSID of the current connection from StationDiagnostic.
+    /// Returns None when not connected or diagnostics are unavailable.
+    pub async fn connected_bssid(&self) -> Option<String> {
+        let path = self.device_path.as_ref()?;
+        let diagnostic = StationDiagnosticProxy::builder(&self.conn)
+            .path(path.clone())
+            .ok()?
+            .build()
+            .await
+            .ok()?;
+        let info = diagnostic.get_diagnostics().await.ok()?;
+        match info.get("ConnectedBss").map(|v| &**v) {
+            Some(zbus::zvariant::Value::Str(bssid)) => Some(bssid.to_string()),
+            _ => None,
+        }
+    }
+
+    /// Abort the running connect task because the adapter went away.
+    /// A Connect() still in flight is reported as failed with `reason`; a
+    /// task already past it (in the portal checks) only has its request
+    /// answered, if it still holds one.
+    pub async fn abandon_connect(&self, reason: &str) {
+        let Some(pending) = self.pending_connect.lock().await.take() else {
+            return;
+        };
+        pending.abandon(&self.evt_tx, reason).await;
+    }
+
+    /// Whether iwd exports StationDebug for this device (developer mode)
+    pub async fn has_station_debug(&self) -> bool {
+        let Some(ref path) = self.device_path else { return false };
+        let introspectable = zbus::fdo::IntrospectableProxy::builder(&self.conn)
+            .destination("net.connman.iwd")
+            .and_then(|b| b.path(path.clone()));
+        let Ok(builder) = introspectable else { return false };
+        match builder.build().await {
+            Ok(proxy) => proxy
+                .introspect()
+                .await
+                .is_ok_and(|xml| xml.contains("net.connman.iwd.StationDebug")),
+            Err(_) => false,
+        }
+    }
+
+    /// Time a connection to `host` ("host" or "host:port", empty for the
+    /// default) and report it under `host` as given. Runs in its own task so
+    /// the event loop stays responsive.
+    pub fn test_latency(&self, host: String) {
+        let evt_tx = self.evt_tx.clone();
+        tokio::spawn(async move {
+            let (target, port) = latency_target(&host);
+            let event = match measure_latency(&target, port).await {
+                Ok(rtt) => {
+                    tracing::info!("Latency to {}:{}: {:?}", target, port, rtt);
+                    let ms = u32::try_from(rtt.as_millis()).unwrap_or(u32::MAX);
+                    BackendEvent::WifiLatency { host, ms }
+                }
+                Err(error) => {
+                    tracing::info!("Latency test to {}:{} failed: {}", target, port, error);
+                    BackendEvent::WifiLatencyFailed { host, error }
+                }
+            };
+            let _ = evt_tx.send(event).await;
+        });
+    }
+
+    /// Ask iwd to roam back to a specific access point of the current network.
+    /// Needs StationDebug (iwd developer mode).
+    pub async fn roam_to(&self, bssid: &str) -> Result<(), String> {
+        let path = self.device_path.as_ref().ok_or("No WiFi device")?;
+        let address = parse_bssid(bssid).ok_or("Invalid BSSID")?;
+        let debug = StationDebugProxy::builder(&self.conn)
+            .path(path.clone())
+            .map_err(|e| e.to_string())?
+            .build()
+            .await
+            .map_err(|e| e.to_string())?;
+        tracing::info!("Roaming to pinned BSSID {}", bssid);
+        debug.roam(&address).await.map_err(|e| e.to_string())
+    }
+
     /// Cancel any pending connection and cleanup
     ///
     /// Uses try_lock() because this is called from Drop (can't be async).
     /// If lock is held, the connection task will be aborted when tokio runtime shuts down anyway.
     pub fn shutdown(&self) {
         if let Ok(mut guard) = self.pending_connect.try_lock() {
-            if let Some(handle) = guard.take() {
+            if let Some(pending) = guard.take() {
                 tracing::debug!("Aborting pending connection on shutdown");
-                handle.abort();
+                pending.handle.abort();
             }
         }
     }
@@ -589,3 +1802,130 @@ impl Drop for WifiBackend {
         self.shutdown();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latency_target_takes_optional_port() {
+        assert_eq!(latency_target("example.org"), ("example.org".into(), 443));
+        assert_eq!(latency_target(" example.org:22 "), ("example.org".into(), 22));
+        assert_eq!(latency_target(""), (LATENCY_TEST_DEFAULT_HOST.into(), 443));
+        assert_eq!(latency_target("2001:db8::1"), ("2001:db8::1".into(), 443));
+        assert_eq!(latency_target("[2001:db8::1]:80"), ("2001:db8::1".into(), 80));
+        assert_eq!(latency_target("[::1]"), ("::1".into(), 443));
+    }
+
+    #[test]
+    fn only_the_connected_known_network_is_disconnected() {
+        let home = "/net/connman/iwd/686f6d65_psk";
+        assert!(is_connected_known(Some(home), home));
+        assert!(!is_connected_known(Some("/net/connman/iwd/776f726b_psk"), home));
+        assert!(!is_connected_known(None, home));
+    }
+
+    #[test]
+    fn connect_failure_names_the_error_before_the_state() {
+        let wrong_key = zbus::Error::Failure("net.connman.iwd.AuthenticationFailed".into());
+        assert_eq!(
+            diagnose_connect_failure(&wrong_key, "psk", Some("connecting")),
+            "The saved password was rejected by the network"
+        );
+        let generic = zbus::Error::Failure("net.connman.iwd.Failed".into());
+        assert_eq!(
+            diagnose_connect_failure(&generic, "psk", Some("connecting")),
+            "Connected to the access point but could not obtain an IP address"
+        );
+        assert_eq!(
+            diagnose_connect_failure(&generic, "psk", Some("disconnected")),
+            "The access point rejected the association"
+        );
+    }
+
+    #[test]
+    fn network_hash_follows_signal_in_whole_dbm() {
+        let network = |signal_strength| WifiNetworkData {
+            path: "/net/connman/iwd/0/1/686f6d65_psk".into(),
+            name: "home".into(),
+            network_type: "psk".into(),
+            signal_strength,
+            connected: false,
+            known: true,
+        };
+        let hash = |signal| shown_networks_hash(&[network(signal)]);
+        assert_eq!(hash(-6010), hash(-6090));
+        assert_ne!(hash(-6010), hash(-6210));
+    }
+
+    #[test]
+    fn newer_refresh_supersedes_older() {
+        let generation = RefreshGeneration::default();
+        let first = generation.begin();
+        let second = generation.begin();
+        assert!(!generation.is_current(first));
+        assert!(generation.is_current(second));
+    }
+
+    /// A connect task as the adapter goes away: still in Connect(), or in
+    /// the portal checks after it, holding `request` or not
+    fn pending_connect(
+        handle: JoinHandle<()>,
+        in_flight: bool,
+        request: Option<RequestId>,
+    ) -> PendingConnect {
+        PendingConnect::new(
+            handle,
+            Arc::new(AtomicBool::new(in_flight)),
+            Arc::new(std::sync::Mutex::new(request)),
+        )
+    }
+
+    fn sent_events(rx: &async_channel::Receiver<BackendEvent>) -> Vec<BackendEvent> {
+        std::iter::from_fn(|| rx.try_recv().ok()).collect()
+    }
+
+    #[tokio::test]
+    async fn adapter_removed_mid_connect_fails_the_request() {
+        let (tx, rx) = async_channel::unbounded();
+        let pending = pending_connect(tokio::spawn(std::future::pending()), true, Some(RequestId(7)));
+        pending.abandon(&tx, "Adapter removed").await;
+        let events = sent_events(&rx);
+        assert!(matches!(
+            events.as_slice(),
+            [
+                BackendEvent::WifiState(WifiStationState::Disconnected),
+                BackendEvent::WifiConnected(None),
+                BackendEvent::RequestDone { request: RequestId(7), error: Some(_) },
+            ]
+        ));
+    }
+
+    #[tokio::test]
+    async fn adapter_removed_in_portal_checks_only_answers_the_request() {
+        let (tx, rx) = async_channel::unbounded();
+        let pending = pending_connect(tokio::spawn(std::future::pending()), false, Some(RequestId(7)));
+        pending.abandon(&tx, "Adapter removed").await;
+        assert!(matches!(
+            sent_events(&rx).as_slice(),
+            [BackendEvent::RequestDone { request: RequestId(7), error: Some(_) }]
+        ));
+
+        // Already answered: nothing left to report
+        let pending = pending_connect(tokio::spawn(std::future::pending()), false, None);
+        pending.abandon(&tx, "Adapter removed").await;
+        assert!(sent_events(&rx).is_empty());
+    }
+
+    #[tokio::test]
+    async fn adapter_removed_after_the_task_ended_reports_nothing() {
+        let (tx, rx) = async_channel::unbounded();
+        let handle = tokio::spawn(async {});
+        while !handle.is_finished() {
+            tokio::task::yield_now().await;
+        }
+        let pending = pending_connect(handle, true, Some(RequestId(7)));
+        pending.abandon(&tx, "Adapter removed").await;
+        assert!(sent_events(&rx).is_empty());
+    }
+}
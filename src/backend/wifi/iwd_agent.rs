@@ -4,7 +4,7 @@
 //! iwd calls our agent when it needs credentials (e.g., WiFi password).
 
 use async_channel::Sender;
-use tokio::sync::oneshot;
+use tokio::sync::{oneshot, watch};
 use zbus::interface;
 use zbus::zvariant::ObjectPath;
 
@@ -15,6 +15,8 @@ use super::iwd_proxy::NetworkProxy;
 pub struct PassphraseRequest {
     pub network_path: String,
     pub network_name: String,
+    /// iwd security type ("psk", "wep", ...); decides what the prompt accepts
+    pub network_type: String,
     pub response_tx: oneshot::Sender<Option<String>>,
 }
 
@@ -26,11 +28,17 @@ pub struct PassphraseRequest {
 pub struct IwdAgent {
     /// Channel to send passphrase requests to the main backend loop
     request_tx: Sender<PassphraseRequest>,
+    /// True while a passphrase prompt is waiting on the user; lets the
+    /// connect task pause its timeout during typing
+    prompt_open: watch::Sender<bool>,
 }
 
 impl IwdAgent {
-    pub fn new(request_tx: Sender<PassphraseRequest>) -> Self {
-        Self { request_tx }
+    pub fn new(request_tx: Sender<PassphraseRequest>, prompt_open: watch::Sender<bool>) -> Self {
+        Self {
+            request_tx,
+            prompt_open,
+        }
     }
 }
 
@@ -45,17 +53,21 @@ impl IwdAgent {
         let network_path = network.to_string();
         tracing::info!("iwd requesting passphrase for {}", network_path);
 
-        // Get network name from iwd
-        let network_name = match NetworkProxy::builder(conn)
+        // Get network name and type from iwd
+        let proxy = match NetworkProxy::builder(conn)
             .path(network.clone())
             .ok()
             .map(|b| b.build())
         {
-            Some(fut) => match fut.await {
-                Ok(proxy) => proxy.name().await.unwrap_or_else(|_| "Unknown".into()),
-                Err(_) => "Unknown".into(),
-            },
-            None => "Unknown".into(),
+            Some(fut) => fut.await.ok(),
+            None => None,
+        };
+        let (network_name, network_type) = match proxy {
+            Some(proxy) => (
+                proxy.name().await.unwrap_or_else(|_| "Unknown".into()),
+                proxy.network_type().await.unwrap_or_else(|_| "psk".into()),
+            ),
+            None => ("Unknown".into(), "psk".into()),
         };
 
         // Create oneshot channel for response
@@ -64,16 +76,21 @@ impl IwdAgent {
         let request = PassphraseRequest {
             network_path: network_path.clone(),
             network_name,
+            network_type,
             response_tx,
         };
 
+        self.prompt_open.send_replace(true);
         if self.request_tx.send(request).await.is_err() {
+            self.prompt_open.send_replace(false);
             tracing::error!("Failed to send passphrase request to backend");
             return Err(zbus::fdo::Error::Failed("Backend channel closed".into()));
         }
 
         // Wait for response from UI
-        match response_rx.await {
+        let response = response_rx.await;
+        self.prompt_open.send_replace(false);
+        match response {
             Ok(Some(passphrase)) => {
                 tracing::info!("Got passphrase for {}", network_path);
                 Ok(passphrase)
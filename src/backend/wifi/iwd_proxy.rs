@@ -2,6 +2,8 @@
 //!
 //! iwd uses the service name `net.connman.iwd` and provides several interfaces:
 //! - Station: Main interface for scanning and connecting
+//! - StationDiagnostic: Details about the current connection (BSSID, frequency)
+//! - StationDebug: BSS-level control, only with iwd's developer mode
 //! - Network: Represents an available network
 //! - KnownNetwork: Represents a saved network
 //! - Device: Low-level adapter control
@@ -22,6 +24,9 @@ pub trait Station {
     /// Disconnect from current network
     fn disconnect(&self) -> zbus::Result<()>;
 
+    /// Connect to a network that doesn't broadcast its SSID
+    fn connect_hidden_network(&self, name: &str) -> zbus::Result<()>;
+
     /// Get list of networks ordered by signal strength
     /// Returns Vec<(object_path, signal_strength)>
     fn get_ordered_networks(&self) -> zbus::Result<Vec<(zbus::zvariant::OwnedObjectPath, i16)>>;
@@ -39,6 +44,33 @@ pub trait Station {
     fn connected_network(&self) -> zbus::Result<zbus::zvariant::OwnedObjectPath>;
 }
 
+/// net.connman.iwd.StationDiagnostic interface
+/// Object path: same as Station
+#[proxy(
+    interface = "net.connman.iwd.StationDiagnostic",
+    default_service = "net.connman.iwd",
+    gen_blocking = false
+)]
+pub trait StationDiagnostic {
+    /// Details about the current connection: "ConnectedBss", "Frequency", "RSSI", ...
+    fn get_diagnostics(
+        &self,
+    ) -> zbus::Result<std::collections::HashMap<String, zbus::zvariant::OwnedValue>>;
+}
+
+/// net.connman.iwd.StationDebug interface
+/// Object path: same as Station. Only exported when iwd runs with
+/// `--developer` (`-E`); there is no BSSID targeting in the regular API.
+#[proxy(
+    interface = "net.connman.iwd.StationDebug",
+    default_service = "net.connman.iwd",
+    gen_blocking = false
+)]
+pub trait StationDebug {
+    /// Roam to a specific BSS of the connected network
+    fn roam(&self, address: &[u8]) -> zbus::Result<()>;
+}
+
 /// net.connman.iwd.Network interface
 /// Object path: /net/connman/iwd/{phy}/{dev}/{network_id}
 #[proxy(
@@ -86,6 +118,10 @@ pub trait KnownNetwork {
     #[zbus(property, name = "Type")]
     fn network_type(&self) -> zbus::Result<String>;
 
+    /// Whether the network doesn't broadcast its SSID
+    #[zbus(property)]
+    fn hidden(&self) -> zbus::Result<bool>;
+
     /// Whether to auto-connect when in range
     #[zbus(property)]
     fn auto_connect(&self) -> zbus::Result<bool>;
@@ -152,6 +188,14 @@ pub trait Adapter {
     #[zbus(property)]
     fn vendor(&self) -> zbus::Result<String>;
 
+    /// Whether the radio is powered (false also when rfkill-blocked)
+    #[zbus(property)]
+    fn powered(&self) -> zbus::Result<bool>;
+
+    /// Power the radio up or down; all of its devices follow
+    #[zbus(property)]
+    fn set_powered(&self, value: bool) -> zbus::Result<()>;
+
     /// Supported modes
     #[zbus(property)]
     fn supported_modes(&self) -> zbus::Result<Vec<String>>;
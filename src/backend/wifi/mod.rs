@@ -1,8 +1,28 @@
+mod adapter;
+#[cfg(feature = "wifi")]
 pub mod backend;
+// The manager keeps its network state even without iwd; it just stays empty
+#[cfg_attr(not(feature = "wifi"), allow(dead_code))]
 mod network;
+#[cfg(feature = "wifi")]
+pub mod network_config;
+#[cfg(feature = "wifi")]
+pub mod network_export;
+#[cfg(feature = "wifi")]
+pub mod scan_export;
+#[cfg(feature = "wifi")]
 pub mod iwd_agent;
+#[cfg(feature = "wifi")]
 pub mod iwd_proxy;
 
-pub use backend::{find_all_iwd_devices, get_known_networks, get_wifi_networks, IwdDeviceInfo, WifiBackend};
+pub use adapter::IwdDeviceInfo;
+#[cfg(feature = "wifi")]
+pub use backend::{
+    find_all_iwd_devices, get_known_networks, get_radio_powered, get_wifi_networks, WifiBackend,
+    LATENCY_TEST_DEFAULT_HOST,
+};
+#[cfg(feature = "wifi")]
 pub use iwd_agent::{IwdAgent, PassphraseRequest};
-pub use network::{WifiNetwork, WifiNetworkState};
+pub use network::{display_ssid, WifiNetwork, WifiNetworkSection, WifiNetworkState};
+#[cfg(feature = "wifi")]
+pub use network_export::SavedNetworkEntry;
@@ -4,6 +4,9 @@ use gtk::glib;
 use std::cell::{Cell, RefCell};
 use std::sync::OnceLock;
 
+use crate::backend::signal_trend::SignalTrend;
+use crate::backend::types::WifiStationState;
+
 /// Canonical WiFi network state, derived from iwd properties + local operation flags.
 /// Local flags (connecting/disconnecting/forgetting) take priority over iwd state,
 /// giving instant UI feedback before the backend confirms.
@@ -18,6 +21,25 @@ pub enum WifiNetworkState {
     Forgetting,
 }
 
+/// Section a network in range is listed under when the list is grouped.
+/// Ordered as the sections are shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum WifiNetworkSection {
+    Connected,
+    SavedInRange,
+    Other,
+}
+
+impl WifiNetworkSection {
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::Connected => "Connected",
+            Self::SavedInRange => "Saved & In Range",
+            Self::Other => "Other Networks",
+        }
+    }
+}
+
 mod imp {
     use super::*;
 
@@ -28,13 +50,26 @@ mod imp {
         pub name: RefCell<String>,
         pub network_type: RefCell<String>, // "open", "psk", "8021x"
         pub signal_strength: Cell<i16>,    // cBm from iwd
+        // Recent samples for the trend arrow; kept here as rows get rebuilt
+        pub signal_trend: RefCell<SignalTrend>,
         pub connected: Cell<bool>,
         pub known: Cell<bool>,
         pub offline: Cell<bool>, // saved network not in range
+        pub hidden: Cell<bool>,  // saved network that doesn't broadcast its SSID
+        pub imported: Cell<bool>, // import placeholder, not saved in iwd yet
+        pub station_state: Cell<WifiStationState>, // only meaningful while connecting/connected
+        pub captive_portal: Cell<bool>, // connected, but portal login still pending
+        pub bssid: RefCell<String>,     // access point of the current connection
+        pub bssid_pinned: Cell<bool>,   // connection is pinned to `bssid`
+        pub blocked: Cell<bool>,        // failed repeatedly, so iwd likely blacklisted it
         // Local operation flags
         pub connecting: Cell<bool>,
         pub disconnecting: Cell<bool>,
         pub forgetting: Cell<bool>,
+        // When the latest operation flag was set, for the manager's watchdog
+        pub operation_since: Cell<Option<std::time::Instant>>,
+        // A row has been built for it; only the first one fades in
+        pub shown: Cell<bool>,
     }
 
     #[glib::object_subclass]
@@ -68,6 +103,25 @@ mod imp {
                     glib::ParamSpecBoolean::builder("offline")
                         .read_only()
                         .build(),
+                    glib::ParamSpecBoolean::builder("hidden")
+                        .read_only()
+                        .build(),
+                    glib::ParamSpecBoolean::builder("imported")
+                        .read_only()
+                        .build(),
+                    glib::ParamSpecString::builder("station-state")
+                        .read_only()
+                        .build(),
+                    glib::ParamSpecBoolean::builder("captive-portal")
+                        .read_only()
+                        .build(),
+                    glib::ParamSpecString::builder("bssid").read_only().build(),
+                    glib::ParamSpecBoolean::builder("bssid-pinned")
+                        .read_only()
+                        .build(),
+                    glib::ParamSpecBoolean::builder("blocked")
+                        .read_only()
+                        .build(),
                     glib::ParamSpecBoolean::builder("connecting")
                         .read_only()
                         .build(),
@@ -90,6 +144,13 @@ mod imp {
                 "connected" => self.connected.get().to_value(),
                 "known" => self.known.get().to_value(),
                 "offline" => self.offline.get().to_value(),
+                "hidden" => self.hidden.get().to_value(),
+                "imported" => self.imported.get().to_value(),
+                "station-state" => format!("{:?}", self.station_state.get()).to_value(),
+                "captive-portal" => self.captive_portal.get().to_value(),
+                "bssid" => self.bssid.borrow().to_value(),
+                "bssid-pinned" => self.bssid_pinned.get().to_value(),
+                "blocked" => self.blocked.get().to_value(),
                 "connecting" => self.connecting.get().to_value(),
                 "disconnecting" => self.disconnecting.get().to_value(),
                 "forgetting" => self.forgetting.get().to_value(),
@@ -103,6 +164,18 @@ glib::wrapper! {
     pub struct WifiNetwork(ObjectSubclass<imp::WifiNetwork>);
 }
 
+/// Make an SSID safe to show: control characters (which iwd passes through
+/// from the beacon) become U+FFFD so they can't break a label's layout, and
+/// an empty SSID gets a placeholder
+pub fn display_ssid(ssid: &str) -> String {
+    if ssid.is_empty() {
+        return "(unnamed)".to_string();
+    }
+    ssid.chars()
+        .map(|c| if c.is_control() { '\u{fffd}' } else { c })
+        .collect()
+}
+
 impl WifiNetwork {
     pub fn new(
         path: &str,
@@ -118,13 +191,15 @@ impl WifiNetwork {
         imp.name.replace(name.to_string());
         imp.network_type.replace(network_type.to_string());
         imp.signal_strength.set(signal_strength);
+        imp.signal_trend.borrow_mut().push(signal_strength as i32 / 100);
         imp.connected.set(connected);
         imp.known.set(known);
         network
     }
 
-    /// Create a saved-offline network (from KnownNetwork, not in scan results)
-    pub fn new_saved_offline(path: &str, name: &str, network_type: &str) -> Self {
+    /// Create a saved-offline network (from KnownNetwork, not in scan results).
+    /// Hidden networks never show up in scans, so they always land here.
+    pub fn new_saved_offline(path: &str, name: &str, network_type: &str, hidden: bool) -> Self {
         let network: Self = glib::Object::new();
         let imp = network.imp();
         imp.path.replace(path.to_string());
@@ -132,6 +207,15 @@ impl WifiNetwork {
         imp.network_type.replace(network_type.to_string());
         imp.known.set(true);
         imp.offline.set(true);
+        imp.hidden.set(hidden);
+        network
+    }
+
+    /// Create a placeholder for an imported network iwd hasn't saved yet.
+    /// It has no D-Bus path; it behaves like a saved-offline network.
+    pub fn new_imported(name: &str, network_type: &str, hidden: bool) -> Self {
+        let network = Self::new_saved_offline("", name, network_type, hidden);
+        network.imp().imported.set(true);
         network
     }
 
@@ -160,6 +244,13 @@ impl WifiNetwork {
         WifiNetworkState::Available
     }
 
+    /// Note that a row shows this network; true the first time only.
+    /// The list is rebuilt on every change, so this tells new networks
+    /// apart from ones that were already on screen.
+    pub fn mark_shown(&self) -> bool {
+        !self.imp().shown.replace(true)
+    }
+
     pub fn path(&self) -> String {
         self.imp().path.borrow().clone()
     }
@@ -168,6 +259,11 @@ impl WifiNetwork {
         self.imp().name.borrow().clone()
     }
 
+    /// Name for labels and messages, see `display_ssid`
+    pub fn display_name(&self) -> String {
+        display_ssid(&self.imp().name.borrow())
+    }
+
     pub fn network_type(&self) -> String {
         self.imp().network_type.borrow().clone()
     }
@@ -196,10 +292,66 @@ impl WifiNetwork {
         self.imp().offline.get()
     }
 
+    pub fn hidden(&self) -> bool {
+        self.imp().hidden.get()
+    }
+
+    pub fn imported(&self) -> bool {
+        self.imp().imported.get()
+    }
+
     pub fn forgetting(&self) -> bool {
         self.imp().forgetting.get()
     }
 
+    pub fn station_state(&self) -> WifiStationState {
+        self.imp().station_state.get()
+    }
+
+    pub fn captive_portal(&self) -> bool {
+        self.imp().captive_portal.get()
+    }
+
+    pub fn bssid(&self) -> String {
+        self.imp().bssid.borrow().clone()
+    }
+
+    pub fn bssid_pinned(&self) -> bool {
+        self.imp().bssid_pinned.get()
+    }
+
+    pub fn blocked(&self) -> bool {
+        self.imp().blocked.get()
+    }
+
+    pub fn set_bssid(&self, bssid: &str) {
+        if *self.imp().bssid.borrow() != bssid {
+            self.imp().bssid.replace(bssid.to_string());
+            self.notify("bssid");
+        }
+    }
+
+    pub fn set_bssid_pinned(&self, pinned: bool) {
+        if self.imp().bssid_pinned.get() != pinned {
+            self.imp().bssid_pinned.set(pinned);
+            self.notify("bssid-pinned");
+        }
+    }
+
+    pub fn set_blocked(&self, blocked: bool) {
+        if self.imp().blocked.get() != blocked {
+            self.imp().blocked.set(blocked);
+            self.notify("blocked");
+        }
+    }
+
+    pub fn set_captive_portal(&self, captive_portal: bool) {
+        if self.imp().captive_portal.get() != captive_portal {
+            self.imp().captive_portal.set(captive_portal);
+            self.notify("captive-portal");
+        }
+    }
+
     pub fn set_connected(&self, connected: bool) {
         if self.imp().connected.get() != connected {
             self.imp().connected.set(connected);
@@ -214,9 +366,24 @@ impl WifiNetwork {
         }
     }
 
+    /// When an operation flag was last set; None while none is
+    pub fn operation_since(&self) -> Option<std::time::Instant> {
+        self.imp().operation_since.get()
+    }
+
+    fn note_operation(&self, started: bool) {
+        let imp = self.imp();
+        if started {
+            imp.operation_since.set(Some(std::time::Instant::now()));
+        } else if !(imp.connecting.get() || imp.disconnecting.get() || imp.forgetting.get()) {
+            imp.operation_since.set(None);
+        }
+    }
+
     pub fn set_connecting(&self, connecting: bool) {
         if self.imp().connecting.get() != connecting {
             self.imp().connecting.set(connecting);
+            self.note_operation(connecting);
             self.notify("connecting");
         }
     }
@@ -224,6 +391,7 @@ impl WifiNetwork {
     pub fn set_disconnecting(&self, disconnecting: bool) {
         if self.imp().disconnecting.get() != disconnecting {
             self.imp().disconnecting.set(disconnecting);
+            self.note_operation(disconnecting);
             self.notify("disconnecting");
         }
     }
@@ -231,13 +399,22 @@ impl WifiNetwork {
     pub fn set_forgetting(&self, forgetting: bool) {
         if self.imp().forgetting.get() != forgetting {
             self.imp().forgetting.set(forgetting);
+            self.note_operation(forgetting);
             self.notify("forgetting");
         }
     }
 
+    pub fn set_station_state(&self, state: WifiStationState) {
+        if self.imp().station_state.get() != state {
+            self.imp().station_state.set(state);
+            self.notify("station-state");
+        }
+    }
+
     pub fn set_signal_strength(&self, strength: i16) {
         if self.imp().signal_strength.get() != strength {
             self.imp().signal_strength.set(strength);
+            self.imp().signal_trend.borrow_mut().push(strength as i32 / 100);
             self.notify("signal-strength");
         }
     }
@@ -246,6 +423,28 @@ impl WifiNetwork {
         self.network_type() != "open"
     }
 
+    /// Security type for people rather than iwd's "psk"/"8021x"
+    pub fn security_label(&self) -> String {
+        match self.network_type().as_str() {
+            "open" => "Open".to_string(),
+            "psk" => "WPA Personal".to_string(),
+            "8021x" => "WPA Enterprise".to_string(),
+            "wep" => "WEP".to_string(),
+            other => other.to_string(),
+        }
+    }
+
+    /// Section of the grouped network list, from iwd's connected/known flags
+    pub fn section(&self) -> WifiNetworkSection {
+        if self.connected() {
+            WifiNetworkSection::Connected
+        } else if self.known() {
+            WifiNetworkSection::SavedInRange
+        } else {
+            WifiNetworkSection::Other
+        }
+    }
+
     /// Returns icon name based on signal strength (iwd returns cBm, i.e. dBm * 100)
     pub fn signal_icon(&self) -> &'static str {
         // Convert from cBm to dBm for comparison
@@ -262,11 +461,20 @@ impl WifiNetwork {
     pub fn signal_dbm(&self) -> i16 {
         self.signal_strength() / 100
     }
+
+    /// Trend arrow icon and tooltip; None when steady or out of range
+    pub fn signal_trend(&self) -> Option<(&'static str, &'static str)> {
+        if self.offline() {
+            return None;
+        }
+        self.imp().signal_trend.borrow().indicator()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::backend::test_helpers::assert_notifies_once;
 
     fn make_network() -> WifiNetwork {
         WifiNetwork::new("/net/1", "TestWiFi", "psk", -4500, false, false)
@@ -289,8 +497,23 @@ mod tests {
 
     #[test]
     fn state_saved_offline() {
-        let n = WifiNetwork::new_saved_offline("/net/1", "TestWiFi", "psk");
+        let n = WifiNetwork::new_saved_offline("/net/1", "TestWiFi", "psk", false);
+        assert_eq!(n.state(), WifiNetworkState::SavedOffline);
+    }
+
+    #[test]
+    fn state_saved_offline_hidden() {
+        let n = WifiNetwork::new_saved_offline("/known/1", "HiddenWiFi", "psk", true);
+        assert_eq!(n.state(), WifiNetworkState::SavedOffline);
+        assert!(n.hidden());
+    }
+
+    #[test]
+    fn state_imported_placeholder() {
+        let n = WifiNetwork::new_imported("Home", "psk", false);
         assert_eq!(n.state(), WifiNetworkState::SavedOffline);
+        assert!(n.imported());
+        assert!(n.path().is_empty());
     }
 
     #[test]
@@ -300,6 +523,16 @@ mod tests {
         assert_eq!(n.state(), WifiNetworkState::Connected);
     }
 
+    #[test]
+    fn signal_trend_follows_signal_updates() {
+        let n = make_network();
+        assert_eq!(n.signal_trend(), None);
+        n.set_signal_strength(-4500);
+        n.set_signal_strength(-4600);
+        n.set_signal_strength(-3800);
+        assert_eq!(n.signal_trend().map(|(icon, _)| icon), Some("go-up-symbolic"));
+    }
+
     // --- Local operation flags override iwd state ---
 
     #[test]
@@ -345,4 +578,106 @@ mod tests {
         n.set_disconnecting(true);
         assert_eq!(n.state(), WifiNetworkState::Disconnecting);
     }
+
+    #[test]
+    fn connecting_beats_connected() {
+        let n = make_network();
+        n.set_connected(true);
+        n.set_connecting(true);
+        assert_eq!(n.state(), WifiNetworkState::Connecting);
+    }
+
+    #[test]
+    fn connected_beats_saved() {
+        let n = make_network();
+        n.set_known(true);
+        n.set_connected(true);
+        assert_eq!(n.state(), WifiNetworkState::Connected);
+    }
+
+    #[test]
+    fn connecting_overrides_saved_offline() {
+        let n = WifiNetwork::new_saved_offline("/known/1", "HiddenWiFi", "psk", true);
+        n.set_connecting(true);
+        assert_eq!(n.state(), WifiNetworkState::Connecting);
+    }
+
+    /// Set every flag, then clear them from highest priority down; each
+    /// step must fall through to the next state in the chain
+    #[test]
+    fn precedence_chain() {
+        let n = make_network();
+        n.set_known(true);
+        n.set_connected(true);
+        n.set_connecting(true);
+        n.set_disconnecting(true);
+        n.set_forgetting(true);
+        assert_eq!(n.state(), WifiNetworkState::Forgetting);
+        n.set_forgetting(false);
+        assert_eq!(n.state(), WifiNetworkState::Disconnecting);
+        n.set_disconnecting(false);
+        assert_eq!(n.state(), WifiNetworkState::Connecting);
+        n.set_connecting(false);
+        assert_eq!(n.state(), WifiNetworkState::Connected);
+        n.set_connected(false);
+        assert_eq!(n.state(), WifiNetworkState::Saved);
+        n.set_known(false);
+        assert_eq!(n.state(), WifiNetworkState::Available);
+    }
+
+    // --- Property notifications ---
+
+    #[test]
+    fn setters_notify_only_on_change() {
+        let n = make_network();
+        assert_notifies_once(&n, "connected", |n| n.set_connected(true));
+        assert_notifies_once(&n, "known", |n| n.set_known(true));
+        assert_notifies_once(&n, "connecting", |n| n.set_connecting(true));
+        assert_notifies_once(&n, "disconnecting", |n| n.set_disconnecting(true));
+        assert_notifies_once(&n, "forgetting", |n| n.set_forgetting(true));
+        assert_notifies_once(&n, "station-state", |n| {
+            n.set_station_state(WifiStationState::Authenticating)
+        });
+        assert_notifies_once(&n, "signal-strength", |n| n.set_signal_strength(-6000));
+        assert_notifies_once(&n, "bssid", |n| n.set_bssid("00:11:22:33:44:55"));
+        assert_notifies_once(&n, "bssid-pinned", |n| n.set_bssid_pinned(true));
+        assert_notifies_once(&n, "blocked", |n| n.set_blocked(true));
+        assert_notifies_once(&n, "captive-portal", |n| n.set_captive_portal(true));
+    }
+
+    // --- Grouping ---
+
+    #[test]
+    fn section_follows_connected_and_known() {
+        let n = make_network();
+        assert_eq!(n.section(), WifiNetworkSection::Other);
+        n.set_known(true);
+        assert_eq!(n.section(), WifiNetworkSection::SavedInRange);
+        n.set_connected(true);
+        assert_eq!(n.section(), WifiNetworkSection::Connected);
+    }
+
+    // --- Display names ---
+
+    #[test]
+    fn display_ssid_replaces_control_chars() {
+        assert_eq!(display_ssid("Home\nNet\u{7}"), "Home\u{fffd}Net\u{fffd}");
+    }
+
+    #[test]
+    fn display_ssid_keeps_markup_characters() {
+        // Labels showing it must not parse markup; the name stays literal
+        assert_eq!(display_ssid("<b>Cafe & Bar</b>"), "<b>Cafe & Bar</b>");
+    }
+
+    #[test]
+    fn display_ssid_empty_placeholder() {
+        assert_eq!(display_ssid(""), "(unnamed)");
+    }
+
+    #[test]
+    fn display_ssid_max_length_untouched() {
+        let ssid = "x".repeat(32);
+        assert_eq!(display_ssid(&ssid), ssid);
+    }
 }
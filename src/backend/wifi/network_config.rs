@@ -0,0 +1,375 @@
+//! Per-network settings that iwd only reads from its network files.
+//!
+//! iwd has no D-Bus property for MAC randomization or static addresses.
+//! They are the `AlwaysRandomizeAddress` key in the `[Settings]` group and
+//! the `[IPv4]` group of the network's file under /var/lib/iwd, which iwd
+//! picks up on the next connect. That directory is normally root-only, so
+//! permission problems are reported with what's needed rather than as a
+//! bare I/O error.
+
+use gtk::glib;
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+
+const IWD_STORAGE_DIR: &str = "/var/lib/iwd";
+const SETTINGS_GROUP: &str = "Settings";
+const RANDOMIZE_KEY: &str = "AlwaysRandomizeAddress";
+const IPV4_GROUP: &str = "IPv4";
+
+/// Static IPv4 settings of a saved network. iwd only applies them when it
+/// does network configuration itself (`EnableNetworkConfiguration`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaticIpv4 {
+    pub address: Ipv4Addr,
+    pub netmask: Ipv4Addr,
+    pub gateway: Option<Ipv4Addr>,
+    pub dns: Vec<Ipv4Addr>,
+}
+
+/// Entry of the static IP form a validation error is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ipv4Field {
+    Address,
+    Netmask,
+    Gateway,
+    Dns,
+}
+
+impl StaticIpv4 {
+    /// Check the values typed into the static IP form. The netmask can be
+    /// dotted or a prefix length ("24" or "/24"). Gateway and DNS may be
+    /// left empty; DNS servers are separated by spaces or commas.
+    pub fn parse(
+        address: &str,
+        netmask: &str,
+        gateway: &str,
+        dns: &str,
+    ) -> Result<Self, (Ipv4Field, &'static str)> {
+        let address: Ipv4Addr = address
+            .trim()
+            .parse()
+            .map_err(|_| (Ipv4Field::Address, "Enter an IPv4 address like 192.168.1.20"))?;
+        let netmask = parse_netmask(netmask).ok_or((
+            Ipv4Field::Netmask,
+            "Enter a netmask like 255.255.255.0 or a prefix length like 24",
+        ))?;
+        let host_bits = !u32::from(netmask);
+        let host = u32::from(address) & host_bits;
+        // The subnet's own address and its broadcast address can't be used,
+        // except in /31 and /32 where there are none
+        let reserved = host_bits > 1 && (host == 0 || host == host_bits);
+        let unusable = address.is_unspecified() || address.is_multicast() || address.is_broadcast();
+        if unusable || reserved {
+            return Err((
+                Ipv4Field::Address,
+                "This address can't be given to a device in that subnet",
+            ));
+        }
+        let gateway = match gateway.trim() {
+            "" => None,
+            text => {
+                let gateway: Ipv4Addr = text
+                    .parse()
+                    .map_err(|_| (Ipv4Field::Gateway, "Enter the gateway as an IPv4 address"))?;
+                let subnet = |a: Ipv4Addr| u32::from(a) & !host_bits;
+                if gateway == address || subnet(gateway) != subnet(address) {
+                    return Err((
+                        Ipv4Field::Gateway,
+                        "The gateway must be another address in the same subnet",
+                    ));
+                }
+                Some(gateway)
+            }
+        };
+        let dns = dns
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|s| !s.is_empty())
+            .map(str::parse)
+            .collect::<Result<Vec<Ipv4Addr>, _>>()
+            .map_err(|_| (Ipv4Field::Dns, "Enter DNS servers as IPv4 addresses"))?;
+        Ok(Self {
+            address,
+            netmask,
+            gateway,
+            dns,
+        })
+    }
+}
+
+/// Dotted netmask or prefix length; None unless the mask bits are
+/// contiguous
+fn parse_netmask(text: &str) -> Option<Ipv4Addr> {
+    let text = text.trim();
+    let prefix = text.strip_prefix('/').unwrap_or(text);
+    let mask = match prefix.parse::<u32>() {
+        Ok(len) if (1..=32).contains(&len) => u32::MAX << (32 - len),
+        Ok(_) => return None,
+        Err(_) => u32::from(text.parse::<Ipv4Addr>().ok()?),
+    };
+    (mask != 0 && mask.leading_ones() + mask.trailing_zeros() == 32).then_some(Ipv4Addr::from(mask))
+}
+
+/// File name iwd stores a network under: the SSID itself when it only has
+/// alphanumerics, space, '-' and '_', otherwise '=' and the SSID in hex
+pub fn storage_file_name(ssid: &str, network_type: &str) -> String {
+    let plain = !ssid.is_empty()
+        && ssid
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b' ' | b'-' | b'_'));
+    let stem = if plain {
+        ssid.to_string()
+    } else {
+        let hex: String = ssid.bytes().map(|b| format!("{:02x}", b)).collect();
+        format!("={}", hex)
+    };
+    format!("{}.{}", stem, network_type)
+}
+
+fn network_file(ssid: &str, network_type: &str) -> PathBuf {
+    Path::new(IWD_STORAGE_DIR).join(storage_file_name(ssid, network_type))
+}
+
+fn describe_io_error(path: &Path, e: &std::io::Error) -> String {
+    match e.kind() {
+        std::io::ErrorKind::PermissionDenied => format!(
+            "No permission to change iwd's network settings. Write access to {} is required.",
+            IWD_STORAGE_DIR
+        ),
+        std::io::ErrorKind::NotFound => format!("iwd has no settings file {}", path.display()),
+        _ => format!("Failed to access {}: {}", path.display(), e),
+    }
+}
+
+/// Replace the file at `path` with `contents` in one step. iwd watches its
+/// storage directory and a file written in place can be seen, or left by a
+/// crash, half written, passphrase and all. So the contents go to a
+/// temporary file beside it with the same mode, which is then renamed over.
+fn replace_file(path: &Path, contents: &str) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let permissions = std::fs::metadata(path)?.permissions();
+    // iwd only loads files ending in a network type, so it skips this one
+    let mut tmp_name = std::ffi::OsString::from(".");
+    tmp_name.push(path.file_name().unwrap_or_default());
+    tmp_name.push(".tmp");
+    let tmp = path.with_file_name(tmp_name);
+    let result = (|| {
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(&tmp)?;
+        file.set_permissions(permissions)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+        std::fs::rename(&tmp, path)
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    result
+}
+
+fn load_keyfile(contents: &str) -> Result<glib::KeyFile, String> {
+    let keyfile = glib::KeyFile::new();
+    keyfile
+        .load_from_data(contents, glib::KeyFileFlags::KEEP_COMMENTS)
+        .map_err(|e| format!("Unreadable iwd network file: {}", e))?;
+    Ok(keyfile)
+}
+
+/// Whether a network file's contents enable MAC randomization
+fn randomization_enabled(contents: &str) -> bool {
+    load_keyfile(contents)
+        .ok()
+        .and_then(|kf| kf.boolean(SETTINGS_GROUP, RANDOMIZE_KEY).ok())
+        .unwrap_or(false)
+}
+
+/// Network file contents with MAC randomization turned on or off, keeping
+/// everything else (including the passphrase) as it was
+fn with_randomization(contents: &str, enabled: bool) -> Result<String, String> {
+    let keyfile = load_keyfile(contents)?;
+    if enabled {
+        keyfile.set_boolean(SETTINGS_GROUP, RANDOMIZE_KEY, true);
+    } else {
+        // Missing key is iwd's default
+        let _ = keyfile.remove_key(SETTINGS_GROUP, RANDOMIZE_KEY);
+    }
+    Ok(keyfile.to_data().to_string())
+}
+
+/// Static IPv4 settings in a network file's contents; None for DHCP
+fn ipv4_from(contents: &str) -> Option<StaticIpv4> {
+    let keyfile = load_keyfile(contents).ok()?;
+    let value = |key: &str| keyfile.string(IPV4_GROUP, key).ok();
+    let address = |key: &str| value(key).and_then(|v| v.trim().parse::<Ipv4Addr>().ok());
+    Some(StaticIpv4 {
+        address: address("Address")?,
+        netmask: address("Netmask")?,
+        gateway: address("Gateway"),
+        dns: value("DNS")
+            .map(|v| v.split_whitespace().filter_map(|s| s.parse().ok()).collect())
+            .unwrap_or_default(),
+    })
+}
+
+/// Network file contents with static IPv4 settings, or with none (DHCP),
+/// keeping everything else as it was
+fn with_ipv4(contents: &str, config: Option<&StaticIpv4>) -> Result<String, String> {
+    let keyfile = load_keyfile(contents)?;
+    let Some(config) = config else {
+        let _ = keyfile.remove_group(IPV4_GROUP);
+        return Ok(keyfile.to_data().to_string());
+    };
+    // Other keys in the group (e.g. Broadcast) are left alone
+    for key in ["Gateway", "DNS"] {
+        let _ = keyfile.remove_key(IPV4_GROUP, key);
+    }
+    keyfile.set_string(IPV4_GROUP, "Address", &config.address.to_string());
+    keyfile.set_string(IPV4_GROUP, "Netmask", &config.netmask.to_string());
+    if let Some(gateway) = config.gateway {
+        keyfile.set_string(IPV4_GROUP, "Gateway", &gateway.to_string());
+    }
+    if !config.dns.is_empty() {
+        let dns: Vec<String> = config.dns.iter().map(Ipv4Addr::to_string).collect();
+        keyfile.set_string(IPV4_GROUP, "DNS", &dns.join(" "));
+    }
+    Ok(keyfile.to_data().to_string())
+}
+
+/// Read whether iwd randomizes the MAC address for a saved network
+pub fn address_randomization(ssid: &str, network_type: &str) -> Result<bool, String> {
+    let path = network_file(ssid, network_type);
+    match std::fs::read_to_string(&path) {
+        Ok(contents) => Ok(randomization_enabled(&contents)),
+        Err(e) => Err(describe_io_error(&path, &e)),
+    }
+}
+
+/// Turn MAC randomization on or off for a saved network
+pub fn set_address_randomization(
+    ssid: &str,
+    network_type: &str,
+    enabled: bool,
+) -> Result<(), String> {
+    let path = network_file(ssid, network_type);
+    let contents = std::fs::read_to_string(&path).map_err(|e| describe_io_error(&path, &e))?;
+    let updated = with_randomization(&contents, enabled)?;
+    replace_file(&path, &updated).map_err(|e| describe_io_error(&path, &e))
+}
+
+/// Read the static IPv4 settings of a saved network; None when it uses DHCP
+pub fn static_ipv4(ssid: &str, network_type: &str) -> Result<Option<StaticIpv4>, String> {
+    let path = network_file(ssid, network_type);
+    match std::fs::read_to_string(&path) {
+        Ok(contents) => Ok(ipv4_from(&contents)),
+        Err(e) => Err(describe_io_error(&path, &e)),
+    }
+}
+
+/// Give a saved network a static IPv4 configuration, or None to go back
+/// to DHCP
+pub fn set_static_ipv4(
+    ssid: &str,
+    network_type: &str,
+    config: Option<&StaticIpv4>,
+) -> Result<(), String> {
+    let path = network_file(ssid, network_type);
+    let contents = std::fs::read_to_string(&path).map_err(|e| describe_io_error(&path, &e))?;
+    let updated = with_ipv4(&contents, config)?;
+    replace_file(&path, &updated).map_err(|e| describe_io_error(&path, &e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PSK_FILE: &str = "[Security]\nPreSharedKey=abcdef\n";
+
+    #[test]
+    fn plain_ssid_is_used_as_is() {
+        assert_eq!(storage_file_name("Home WiFi_2-G", "psk"), "Home WiFi_2-G.psk");
+    }
+
+    #[test]
+    fn other_ssids_are_hex_encoded() {
+        assert_eq!(storage_file_name("Café", "open"), "=436166c3a9.open");
+        assert_eq!(storage_file_name("a.b", "8021x"), "=612e62.8021x");
+    }
+
+    #[test]
+    fn enable_adds_key_and_keeps_passphrase() {
+        let updated = with_randomization(PSK_FILE, true).unwrap();
+        assert!(randomization_enabled(&updated));
+        assert!(updated.contains("PreSharedKey=abcdef"));
+    }
+
+    #[test]
+    fn disable_removes_key() {
+        let enabled = with_randomization(PSK_FILE, true).unwrap();
+        let disabled = with_randomization(&enabled, false).unwrap();
+        assert!(!randomization_enabled(&disabled));
+        assert!(!disabled.contains(RANDOMIZE_KEY));
+        assert!(disabled.contains("PreSharedKey=abcdef"));
+    }
+
+    #[test]
+    fn missing_key_means_disabled() {
+        assert!(!randomization_enabled(PSK_FILE));
+    }
+
+    #[test]
+    fn replaced_file_keeps_its_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("wlcontrol-replace-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("Home.psk");
+        std::fs::write(&path, PSK_FILE).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o640)).unwrap();
+
+        replace_file(&path, "[Settings]\nAutoConnect=false\n").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "[Settings]\nAutoConnect=false\n");
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o640);
+        // Nothing left beside it
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn static_ip_form_accepts_prefix_length() {
+        let config = StaticIpv4::parse("10.0.0.5", "/24", "10.0.0.1", "1.1.1.1, 9.9.9.9").unwrap();
+        assert_eq!(config.netmask, Ipv4Addr::new(255, 255, 255, 0));
+        assert_eq!(config.gateway, Some(Ipv4Addr::new(10, 0, 0, 1)));
+        assert_eq!(config.dns.len(), 2);
+        assert!(StaticIpv4::parse("10.0.0.5", "255.255.0.0", "", "").is_ok());
+    }
+
+    #[test]
+    fn static_ip_form_rejects_bad_values() {
+        let field = |a, m, g, d| StaticIpv4::parse(a, m, g, d).unwrap_err().0;
+        assert_eq!(field("10.0.0", "24", "", ""), Ipv4Field::Address);
+        assert_eq!(field("10.0.0.255", "24", "", ""), Ipv4Field::Address);
+        assert_eq!(field("10.0.0.5", "255.0.255.0", "", ""), Ipv4Field::Netmask);
+        assert_eq!(field("10.0.0.5", "33", "", ""), Ipv4Field::Netmask);
+        assert_eq!(field("10.0.0.5", "24", "10.0.1.1", ""), Ipv4Field::Gateway);
+        assert_eq!(field("10.0.0.5", "24", "", "dns.example"), Ipv4Field::Dns);
+    }
+
+    #[test]
+    fn static_ip_round_trips_and_keeps_passphrase() {
+        let config = StaticIpv4::parse("10.0.0.5", "24", "10.0.0.1", "10.0.0.1").unwrap();
+        let updated = with_ipv4(PSK_FILE, Some(&config)).unwrap();
+        assert_eq!(ipv4_from(&updated), Some(config));
+        assert!(updated.contains("PreSharedKey=abcdef"));
+
+        let dhcp = with_ipv4(&updated, None).unwrap();
+        assert_eq!(ipv4_from(&dhcp), None);
+        assert!(!dhcp.contains(IPV4_GROUP));
+        assert!(dhcp.contains("PreSharedKey=abcdef"));
+    }
+}
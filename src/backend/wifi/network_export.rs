@@ -0,0 +1,149 @@
+//! Export and import of the saved network list, metadata only.
+//!
+//! iwd keeps passphrases in its own storage and has no D-Bus call to create
+//! a KnownNetwork, so an import can't recreate saved networks directly.
+//! Imported entries are kept as placeholders instead: they are listed with
+//! the saved networks, the first connect asks for the password as usual,
+//! and once iwd has saved the network its auto-connect choice is applied
+//! and the placeholder is dropped.
+
+use gtk::glib;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Current file format version
+const FORMAT_VERSION: u32 = 1;
+
+/// One saved network, as exported
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedNetworkEntry {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub network_type: String,
+    #[serde(default = "default_auto_connect")]
+    pub auto_connect: bool,
+    #[serde(default)]
+    pub hidden: bool,
+}
+
+fn default_auto_connect() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedNetworkList {
+    version: u32,
+    networks: Vec<SavedNetworkEntry>,
+}
+
+/// Serialize entries to the export file format
+pub fn to_json(networks: &[SavedNetworkEntry]) -> String {
+    let list = SavedNetworkList {
+        version: FORMAT_VERSION,
+        networks: networks.to_vec(),
+    };
+    // Plain strings and bools, serialization can't fail
+    serde_json::to_string_pretty(&list).unwrap_or_default()
+}
+
+/// Parse an export file, dropping entries without a name or with an
+/// unknown security type
+pub fn from_json(json: &str) -> Result<Vec<SavedNetworkEntry>, String> {
+    let list: SavedNetworkList =
+        serde_json::from_str(json).map_err(|e| format!("Not a saved network list: {}", e))?;
+    if list.version > FORMAT_VERSION {
+        return Err(format!("Unsupported file version {}", list.version));
+    }
+    Ok(list
+        .networks
+        .into_iter()
+        .filter(|n| !n.name.is_empty() && matches!(n.network_type.as_str(), "open" | "psk" | "8021x"))
+        .collect())
+}
+
+fn placeholders_path() -> PathBuf {
+    glib::user_config_dir()
+        .join("wlcontrol")
+        .join("imported-networks.json")
+}
+
+/// Load import placeholders not yet saved by iwd
+pub fn load_placeholders() -> Vec<SavedNetworkEntry> {
+    match std::fs::read_to_string(placeholders_path()) {
+        Ok(json) => from_json(&json).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring imported networks file: {}", e);
+            Vec::new()
+        }),
+        // Missing file is the normal case
+        Err(_) => Vec::new(),
+    }
+}
+
+pub fn save_placeholders(networks: &[SavedNetworkEntry]) {
+    let path = placeholders_path();
+    if networks.is_empty() {
+        let _ = std::fs::remove_file(&path);
+        return;
+    }
+    if let Some(dir) = path.parent() {
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            tracing::warn!("Failed to create config dir {}: {}", dir.display(), e);
+            return;
+        }
+    }
+    if let Err(e) = std::fs::write(&path, to_json(networks)) {
+        tracing::warn!("Failed to save imported networks to {}: {}", path.display(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, network_type: &str) -> SavedNetworkEntry {
+        SavedNetworkEntry {
+            name: name.into(),
+            network_type: network_type.into(),
+            auto_connect: true,
+            hidden: false,
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut office = entry("Office", "8021x");
+        office.auto_connect = false;
+        let mut attic = entry("Attic", "psk");
+        attic.hidden = true;
+        let networks = vec![entry("Home", "psk"), office, attic];
+        assert_eq!(from_json(&to_json(&networks)).unwrap(), networks);
+    }
+
+    #[test]
+    fn missing_flags_use_defaults() {
+        let json = r#"{"version": 1, "networks": [{"name": "Cafe", "type": "open"}]}"#;
+        let networks = from_json(json).unwrap();
+        assert_eq!(networks, vec![entry("Cafe", "open")]);
+    }
+
+    #[test]
+    fn drops_invalid_entries() {
+        let json = r#"{"version": 1, "networks": [
+            {"name": "", "type": "psk"},
+            {"name": "Weird", "type": "wep"},
+            {"name": "Home", "type": "psk"}
+        ]}"#;
+        assert_eq!(from_json(json).unwrap(), vec![entry("Home", "psk")]);
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(from_json("not json").is_err());
+        assert!(from_json(r#"{"networks": []}"#).is_err());
+    }
+
+    #[test]
+    fn rejects_newer_version() {
+        assert!(from_json(r#"{"version": 2, "networks": []}"#).is_err());
+    }
+}
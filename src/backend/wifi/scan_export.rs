@@ -0,0 +1,137 @@
+//! Export of the networks currently in range, for site surveys.
+//!
+//! A snapshot of what the WiFi page lists: one entry per network with its
+//! signal, security and whether it's saved, stamped with the time of the
+//! export. Written as JSON, or as CSV with the time on every row so
+//! exports from several spots can be pasted into one sheet.
+
+use serde::Serialize;
+
+use super::WifiNetwork;
+
+/// One network in range, as exported
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ScanResult {
+    pub ssid: String,
+    pub signal_dbm: i16,
+    pub security: String,
+    pub known: bool,
+    pub connected: bool,
+}
+
+impl ScanResult {
+    pub fn from_network(network: &WifiNetwork) -> Self {
+        Self {
+            ssid: network.name(),
+            signal_dbm: network.signal_dbm(),
+            security: network.security_label(),
+            known: network.known(),
+            connected: network.connected(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ScanExport<'a> {
+    timestamp: &'a str,
+    networks: &'a [ScanResult],
+}
+
+/// Results as JSON; `timestamp` is ISO 8601
+pub fn to_json(results: &[ScanResult], timestamp: &str) -> String {
+    let export = ScanExport {
+        timestamp,
+        networks: results,
+    };
+    // Plain strings, numbers and bools, serialization can't fail
+    serde_json::to_string_pretty(&export).unwrap_or_default()
+}
+
+/// Results as CSV with a header row
+pub fn to_csv(results: &[ScanResult], timestamp: &str) -> String {
+    let mut csv = String::from("timestamp,ssid,signal_dbm,security,known,connected\n");
+    for result in results {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{}\n",
+            csv_field(timestamp),
+            csv_field(&result.ssid),
+            result.signal_dbm,
+            csv_field(&result.security),
+            result.known,
+            result.connected
+        ));
+    }
+    csv
+}
+
+/// Quote a field when it holds a separator, quote or line break, or
+/// spaces at either end that a spreadsheet would drop. Anyone nearby can
+/// name a network, so text a spreadsheet would run as a formula gets a
+/// leading `'` to keep it plain text.
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    let needs_quotes = value.contains([',', '"', '\n', '\r'])
+        || value.starts_with(' ')
+        || value.ends_with(' ');
+    if needs_quotes {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(ssid: &str) -> ScanResult {
+        ScanResult {
+            ssid: ssid.into(),
+            signal_dbm: -62,
+            security: "WPA Personal".into(),
+            known: true,
+            connected: false,
+        }
+    }
+
+    #[test]
+    fn csv_has_timestamp_on_every_row() {
+        let csv = to_csv(&[result("Home"), result("Office")], "2024-05-01T10:00:00Z");
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "timestamp,ssid,signal_dbm,security,known,connected");
+        assert_eq!(lines[1], "2024-05-01T10:00:00Z,Home,-62,WPA Personal,true,false");
+        assert_eq!(lines.len(), 3);
+    }
+
+    #[test]
+    fn csv_quotes_awkward_ssids() {
+        assert_eq!(csv_field("Cafe, upstairs"), "\"Cafe, upstairs\"");
+        assert_eq!(csv_field("The \"Lab\""), "\"The \"\"Lab\"\"\"");
+        assert_eq!(csv_field(" padded"), "\" padded\"");
+        assert_eq!(csv_field("Plain"), "Plain");
+    }
+
+    #[test]
+    fn csv_defuses_formulas() {
+        assert_eq!(csv_field("=HYPERLINK(\"x\")"), "\"'=HYPERLINK(\"\"x\"\")\"");
+        assert_eq!(csv_field("+1"), "'+1");
+        assert_eq!(csv_field("-2"), "'-2");
+        assert_eq!(csv_field("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(csv_field("\tTab"), "'\tTab");
+        assert_eq!(csv_field("\rCR"), "\"'\rCR\"");
+        assert_eq!(csv_field("a=b"), "a=b");
+    }
+
+    #[test]
+    fn json_lists_networks_with_timestamp() {
+        let json = to_json(&[result("Home")], "2024-05-01T10:00:00Z");
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["timestamp"], "2024-05-01T10:00:00Z");
+        assert_eq!(value["networks"][0]["ssid"], "Home");
+        assert_eq!(value["networks"][0]["signal_dbm"], -62);
+    }
+}
@@ -1,5 +1,6 @@
 mod application;
 mod backend;
+mod settings;
 mod ui;
 
 use application::WlcontrolApplication;
@@ -0,0 +1,148 @@
+//! Persistent user preferences, stored as a key file in the XDG config dir.
+
+use gtk::glib;
+
+const GROUP: &str = "preferences";
+/// Unix time each Bluetooth device was last seen connecting, keyed by address
+const BT_LAST_CONNECTED_GROUP: &str = "bt-last-connected";
+/// Unix time each saved WiFi network was first seen, keyed by KnownNetwork path
+const WIFI_KNOWN_ADDED_GROUP: &str = "wifi-known-added";
+
+#[cfg(test)]
+thread_local! {
+    /// Tests keep settings in memory, one store per test thread, so they
+    /// neither see nor change the user's settings file
+    static TEST_STORE: std::cell::RefCell<String> = const { std::cell::RefCell::new(String::new()) };
+}
+
+#[cfg(not(test))]
+fn settings_path() -> std::path::PathBuf {
+    glib::user_config_dir().join("wlcontrol").join("settings.ini")
+}
+
+#[cfg(not(test))]
+fn load() -> glib::KeyFile {
+    let keyfile = glib::KeyFile::new();
+    // Missing file is the normal first-run case
+    let _ = keyfile.load_from_file(settings_path(), glib::KeyFileFlags::NONE);
+    keyfile
+}
+
+#[cfg(test)]
+fn load() -> glib::KeyFile {
+    let keyfile = glib::KeyFile::new();
+    TEST_STORE.with_borrow(|data| {
+        let _ = keyfile.load_from_data(data, glib::KeyFileFlags::NONE);
+    });
+    keyfile
+}
+
+#[cfg(test)]
+fn save(keyfile: &glib::KeyFile) {
+    TEST_STORE.set(keyfile.to_data().to_string());
+}
+
+#[cfg(not(test))]
+fn save(keyfile: &glib::KeyFile) {
+    let path = settings_path();
+    if let Some(dir) = path.parent() {
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            tracing::warn!("Failed to create config dir {}: {}", dir.display(), e);
+            return;
+        }
+    }
+    if let Err(e) = keyfile.save_to_file(&path) {
+        tracing::warn!("Failed to save settings to {}: {}", path.display(), e);
+    }
+}
+
+/// Read a boolean preference, falling back to `default` when unset.
+pub fn get_bool(key: &str, default: bool) -> bool {
+    load().boolean(GROUP, key).unwrap_or(default)
+}
+
+pub fn set_bool(key: &str, value: bool) {
+    let keyfile = load();
+    keyfile.set_boolean(GROUP, key, value);
+    save(&keyfile);
+}
+
+/// Read a string preference, falling back to `default` when unset.
+pub fn get_string(key: &str, default: &str) -> String {
+    load()
+        .string(GROUP, key)
+        .map(|s| s.to_string())
+        .unwrap_or_else(|_| default.to_string())
+}
+
+pub fn set_string(key: &str, value: &str) {
+    let keyfile = load();
+    keyfile.set_string(GROUP, key, value);
+    save(&keyfile);
+}
+
+/// Unix times stored in `group`, by key
+fn times(group: &str) -> std::collections::HashMap<String, i64> {
+    let keyfile = load();
+    let Ok(keys) = keyfile.keys(group) else {
+        return std::collections::HashMap::new();
+    };
+    keys.iter()
+        .filter_map(|key| {
+            let time = keyfile.int64(group, key).ok()?;
+            Some((key.to_string(), time))
+        })
+        .collect()
+}
+
+fn set_time(group: &str, key: &str, time: i64) {
+    let keyfile = load();
+    keyfile.set_int64(group, key, time);
+    save(&keyfile);
+}
+
+fn forget_time(group: &str, key: &str) {
+    let keyfile = load();
+    if keyfile.remove_key(group, key).is_ok() {
+        save(&keyfile);
+    }
+}
+
+/// All recorded Bluetooth "last connected" times, by device address
+pub fn bt_last_connected() -> std::collections::HashMap<String, i64> {
+    times(BT_LAST_CONNECTED_GROUP)
+}
+
+pub fn set_bt_last_connected(address: &str, time: i64) {
+    set_time(BT_LAST_CONNECTED_GROUP, address, time);
+}
+
+pub fn forget_bt_last_connected(address: &str) {
+    forget_time(BT_LAST_CONNECTED_GROUP, address);
+}
+
+/// When each saved WiFi network was first seen saved, by KnownNetwork path
+pub fn wifi_known_added() -> std::collections::HashMap<String, i64> {
+    times(WIFI_KNOWN_ADDED_GROUP)
+}
+
+pub fn set_wifi_known_added(path: &str, time: i64) {
+    set_time(WIFI_KNOWN_ADDED_GROUP, path, time);
+}
+
+pub fn forget_wifi_known_added(path: &str) {
+    forget_time(WIFI_KNOWN_ADDED_GROUP, path);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_test_thread_has_its_own_store() {
+        set_bool("test-key", true);
+        assert!(get_bool("test-key", false));
+        let elsewhere = std::thread::spawn(|| get_bool("test-key", false)).join().unwrap();
+        assert!(!elsewhere);
+    }
+}
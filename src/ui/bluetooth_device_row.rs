@@ -1,10 +1,10 @@
 use adw::prelude::*;
 use adw::subclass::prelude::*;
 use gtk::{gio, glib};
-use std::cell::OnceCell;
+use std::cell::{OnceCell, RefCell};
 
-use crate::backend::bluetooth::{BtDevice, BtDeviceState};
-use crate::backend::WlcontrolManager;
+use crate::backend::bluetooth::{validate_alias, BtDevice, BtDeviceState};
+use crate::backend::{BtAudioRole, WlcontrolManager};
 
 mod imp {
     use super::*;
@@ -23,12 +23,20 @@ mod imp {
         #[template_child]
         pub rssi_icon: TemplateChild<gtk::Image>,
         #[template_child]
+        pub trend_icon: TemplateChild<gtk::Image>,
+        #[template_child]
         pub connected_icon: TemplateChild<gtk::Image>,
         #[template_child]
+        pub cancel_button: TemplateChild<gtk::Button>,
+        #[template_child]
         pub menu_button: TemplateChild<gtk::MenuButton>,
 
         pub device: OnceCell<BtDevice>,
         pub action_group: OnceCell<gio::SimpleActionGroup>,
+        pub manager: glib::WeakRef<WlcontrolManager>,
+        /// Ticks the "Connected for" subtitle while connected, and the
+        /// pairing countdown while pairing, when mapped
+        pub duration_timer: RefCell<Option<glib::SourceId>>,
     }
 
     #[glib::object_subclass]
@@ -49,6 +57,13 @@ mod imp {
     impl ObjectImpl for BluetoothDeviceRow {
         fn constructed(&self) {
             self.parent_constructed();
+
+            let obj = self.obj();
+            obj.connect_map(|row| row.update_duration_timer());
+            obj.connect_unmap(|row| row.update_duration_timer());
+            crate::ui::add_context_menu_triggers(&*obj, |row: &super::BluetoothDeviceRow, at| {
+                row.open_context_menu(at)
+            });
         }
     }
     impl WidgetImpl for BluetoothDeviceRow {}
@@ -70,11 +85,6 @@ impl BluetoothDeviceRow {
 
         imp.device.set(device.clone()).unwrap();
 
-        // Set subtitle for paired devices
-        if device.paired() {
-            row.set_subtitle(&device.address());
-        }
-
         // Set device icon
         imp.device_icon.set_icon_name(Some(device.device_icon()));
 
@@ -101,6 +111,7 @@ impl BluetoothDeviceRow {
     }
 
     pub fn setup_actions(&self, manager: &WlcontrolManager, device: &BtDevice) {
+        self.imp().manager.set(Some(manager));
         let group = gio::SimpleActionGroup::new();
 
         // rename
@@ -150,6 +161,22 @@ impl BluetoothDeviceRow {
             ),
         );
 
+        // connect-role: "listen" or "call", for headsets offering both
+        let connect_role = gio::SimpleAction::new("connect-role", Some(glib::VariantTy::STRING));
+        connect_role.connect_activate(glib::clone!(
+            #[weak]
+            manager,
+            #[weak]
+            device,
+            move |_, param| {
+                let role = param.and_then(|v| v.str()).and_then(BtAudioRole::from_key);
+                if let Some(role) = role {
+                    manager.request_bt_connect_role(&device.path(), role);
+                }
+            }
+        ));
+        group.add_action(&connect_role);
+
         // copy-address
         let copy_address = gio::SimpleAction::new("copy-address", None);
         copy_address.connect_activate(glib::clone!(
@@ -164,6 +191,19 @@ impl BluetoothDeviceRow {
         ));
         group.add_action(&copy_address);
 
+        // cancel-pair (shown on the row while pairing/connecting)
+        let cancel_pair = gio::SimpleAction::new("cancel-pair", None);
+        cancel_pair.connect_activate(glib::clone!(
+            #[weak]
+            manager,
+            #[weak]
+            device,
+            move |_, _| {
+                manager.request_bt_cancel_pair(&device.address());
+            }
+        ));
+        group.add_action(&cancel_pair);
+
         // forget
         let forget = gio::SimpleAction::new("forget", None);
         forget.connect_activate(glib::clone!(
@@ -181,6 +221,29 @@ impl BluetoothDeviceRow {
 
         self.insert_action_group("row", Some(&group));
         self.imp().action_group.set(group).unwrap();
+        self.sync_ui_to_state();
+    }
+
+    /// The menu button's menu, for right-click and the Menu key. Not while
+    /// the device is busy, same as the button.
+    fn open_context_menu(&self, at: Option<(f64, f64)>) {
+        let imp = self.imp();
+        if imp.action_group.get().is_none() {
+            return;
+        }
+        let busy = matches!(
+            self.device().state(),
+            BtDeviceState::Pairing
+                | BtDeviceState::Connecting
+                | BtDeviceState::Disconnecting
+                | BtDeviceState::Removing
+        );
+        if busy {
+            return;
+        }
+        if let Some(model) = imp.menu_button.menu_model() {
+            crate::ui::popup_menu(self, &model, at);
+        }
     }
 
     fn show_rename_dialog(row: &BluetoothDeviceRow, manager: &WlcontrolManager, device: &BtDevice) {
@@ -203,6 +266,30 @@ impl BluetoothDeviceRow {
         group.add(&entry);
         dialog.set_extra_child(Some(&group));
 
+        // Apply only for a valid, changed name; explain invalid input inline
+        let current_name = device.display_name();
+        let update = glib::clone!(
+            #[weak]
+            dialog,
+            move |entry: &adw::EntryRow| {
+                let text = entry.text();
+                match validate_alias(&text) {
+                    Ok(alias) => {
+                        entry.remove_css_class("error");
+                        dialog.set_body("");
+                        dialog.set_response_enabled("apply", alias != current_name);
+                    }
+                    Err(message) => {
+                        entry.add_css_class("error");
+                        dialog.set_body(message);
+                        dialog.set_response_enabled("apply", false);
+                    }
+                }
+            }
+        );
+        update(&entry);
+        entry.connect_changed(move |entry| update(entry));
+
         glib::spawn_future_local(glib::clone!(
             #[weak]
             manager,
@@ -213,9 +300,8 @@ impl BluetoothDeviceRow {
             async move {
                 let response = dialog.choose_future(Some(&row)).await;
                 if response == "apply" {
-                    let new_name = entry.text().to_string();
-                    if !new_name.is_empty() && new_name != device.display_name() {
-                        manager.request_bt_set_alias(&device.path(), &new_name);
+                    if let Ok(alias) = validate_alias(&entry.text()) {
+                        manager.request_bt_set_alias(&device.path(), alias);
                     }
                 }
             }
@@ -279,6 +365,76 @@ impl BluetoothDeviceRow {
             self.remove_css_class("bt-busy");
         }
 
+        // The menu can open on discovered devices too, via right-click;
+        // only copying the address applies to them
+        if let Some(group) = imp.action_group.get() {
+            for name in ["rename", "auto-connect", "forget"] {
+                if let Some(action) =
+                    group.lookup_action(name).and_downcast::<gio::SimpleAction>()
+                {
+                    action.set_enabled(device.paired());
+                }
+            }
+            if let Some(action) = group
+                .lookup_action("connect-role")
+                .and_downcast::<gio::SimpleAction>()
+            {
+                action.set_enabled(device.paired() && device.dual_audio());
+            }
+        }
+
+        imp.cancel_button.set_visible(matches!(
+            state,
+            BtDeviceState::Pairing | BtDeviceState::Connecting
+        ));
+
+        // Connected devices show how long they've been up, paired ones when
+        // they were last used (or their address if never seen connecting)
+        let connected_for = if state == BtDeviceState::Connected {
+            imp.manager
+                .upgrade()
+                .and_then(|m| m.bt_connected_duration(&device.address()))
+        } else {
+            None
+        };
+        match connected_for {
+            Some(duration) => self.set_subtitle(&format!(
+                "Connected for {}",
+                crate::ui::format_duration(duration)
+            )),
+            // Pairing gives no progress of its own; count down to the
+            // timeout so a slow one doesn't look stuck. The countdown waits
+            // while a PIN or confirmation prompt is open.
+            None if state == BtDeviceState::Pairing => {
+                let left = imp.manager.upgrade().and_then(|m| m.bt_pair_time_left(&device));
+                match left {
+                    Some(left) => {
+                        let left = left.as_secs().max(1);
+                        self.set_subtitle(&format!(
+                            "Pairing… this may take up to {} second{}",
+                            left,
+                            if left == 1 { "" } else { "s" }
+                        ));
+                    }
+                    None => self.set_subtitle("Pairing…"),
+                }
+            }
+            None if device.paired() => {
+                let last_connected = imp
+                    .manager
+                    .upgrade()
+                    .and_then(|m| m.bt_last_connected(&device.address()));
+                match last_connected {
+                    Some(time) => self.set_subtitle(&format!(
+                        "Last connected: {}",
+                        crate::ui::format_last_seen(time)
+                    )),
+                    None => self.set_subtitle(&device.address()),
+                }
+            }
+            None => self.set_subtitle(""),
+        }
+
         match state {
             BtDeviceState::Discovered => {
                 imp.connected_icon.set_visible(false);
@@ -316,6 +472,48 @@ impl BluetoothDeviceRow {
                 self.set_activatable(false);
             }
         }
+
+        // Trend arrow goes with the RSSI icon
+        match device.signal_trend() {
+            Some((icon_name, tooltip)) if imp.rssi_icon.is_visible() => {
+                imp.trend_icon.set_icon_name(Some(icon_name));
+                imp.trend_icon.set_tooltip_text(Some(tooltip));
+                imp.trend_icon.set_visible(true);
+            }
+            _ => imp.trend_icon.set_visible(false),
+        }
+
+        self.update_duration_timer();
+    }
+
+    /// Run the once-a-second subtitle refresh only while it can be seen
+    fn update_duration_timer(&self) {
+        let imp = self.imp();
+        let ticking = matches!(
+            self.device().state(),
+            BtDeviceState::Connected | BtDeviceState::Pairing
+        );
+        let wanted = self.is_mapped() && ticking;
+        if !wanted {
+            if let Some(source) = imp.duration_timer.take() {
+                source.remove();
+            }
+        } else if imp.duration_timer.borrow().is_none() {
+            let source = glib::timeout_add_seconds_local(
+                1,
+                glib::clone!(
+                    #[weak(rename_to = row)]
+                    self,
+                    #[upgrade_or]
+                    glib::ControlFlow::Break,
+                    move || {
+                        row.sync_ui_to_state();
+                        glib::ControlFlow::Continue
+                    }
+                ),
+            );
+            imp.duration_timer.replace(Some(source));
+        }
     }
 
     fn update_battery_display(&self) {
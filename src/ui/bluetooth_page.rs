@@ -21,6 +21,8 @@ mod imp {
         #[template_child]
         pub discoverable_switch: TemplateChild<adw::SwitchRow>,
         #[template_child]
+        pub pairable_switch: TemplateChild<adw::SwitchRow>,
+        #[template_child]
         pub connected_group: TemplateChild<adw::PreferencesGroup>,
         #[template_child]
         pub connected_listbox: TemplateChild<gtk::ListBox>,
@@ -68,6 +70,9 @@ mod imp {
         #[template_callback]
         fn on_scan_clicked(&self, _button: &gtk::Button) {
             if let Some(manager) = self.manager.get() {
+                if manager.bt_scan_starting() {
+                    return;
+                }
                 if manager.bt_discovering() {
                     manager.request_bt_stop_scan();
                 } else {
@@ -115,18 +120,25 @@ impl BluetoothPage {
         let imp = self.imp();
         imp.manager.set(manager.clone()).unwrap();
 
-        // Spin the refresh icon while discovering
+        // Pulse the refresh icon until discovery starts, then spin it
         let scan_button = imp.scan_button.clone();
-        manager.connect_notify_local(
-            Some("bt-discovering"),
-            move |manager, _| {
-                if manager.bt_discovering() {
-                    scan_button.add_css_class("scanning");
-                } else {
-                    scan_button.remove_css_class("scanning");
-                }
-            },
-        );
+        let update_scanning = move |manager: &WlcontrolManager, _: &glib::ParamSpec| {
+            let starting = manager.bt_scan_starting();
+            if starting {
+                scan_button.add_css_class("scan-starting");
+                scan_button.set_tooltip_text(Some("Starting scan…"));
+            } else {
+                scan_button.remove_css_class("scan-starting");
+                scan_button.set_tooltip_text(Some("Scan for devices"));
+            }
+            if manager.bt_discovering() && !starting {
+                scan_button.add_css_class("scanning");
+            } else {
+                scan_button.remove_css_class("scanning");
+            }
+        };
+        manager.connect_notify_local(Some("bt-discovering"), update_scanning.clone());
+        manager.connect_notify_local(Some("bt-scan-starting"), update_scanning);
 
         // Bind adapter power state
         manager
@@ -134,12 +146,21 @@ impl BluetoothPage {
             .sync_create()
             .bidirectional()
             .build();
+        manager
+            .bind_property("airplane-mode", &*imp.adapter_switch, "sensitive")
+            .sync_create()
+            .invert_boolean()
+            .build();
 
         // Disable controls when BT is off
         manager
             .bind_property("bt-powered", &*imp.discoverable_switch, "sensitive")
             .sync_create()
             .build();
+        manager
+            .bind_property("bt-powered", &*imp.pairable_switch, "sensitive")
+            .sync_create()
+            .build();
         manager
             .bind_property("bt-powered", &*imp.scan_button, "sensitive")
             .sync_create()
@@ -151,6 +172,17 @@ impl BluetoothPage {
             .sync_create()
             .bidirectional()
             .build();
+        manager
+            .bind_property("bt-pairable", &*imp.pairable_switch, "active")
+            .sync_create()
+            .bidirectional()
+            .build();
+        // Discoverable mode times out in BlueZ; confirm it when the page shows
+        self.connect_map(glib::clone!(
+            #[weak]
+            manager,
+            move |_| manager.request_bt_adapter_state()
+        ));
 
         // Create filtered models for different device states
         let devices = manager.bt_devices();
@@ -241,6 +273,23 @@ impl BluetoothPage {
             ),
         );
 
+        // A second audio device connected; offer to drop the first
+        manager.connect_closure(
+            "bt-audio-conflict",
+            false,
+            glib::closure_local!(
+                #[weak(rename_to = page)]
+                self,
+                move |manager: WlcontrolManager,
+                      _address: String,
+                      name: String,
+                      previous_address: String,
+                      previous_name: String| {
+                    page.show_audio_conflict_dialog(&manager, &name, &previous_address, &previous_name);
+                }
+            ),
+        );
+
         // Handle all BT pairing interactions
         let page = self.clone();
         let pairing_dialog: Rc<RefCell<Option<adw::AlertDialog>>> = Rc::new(RefCell::new(None));
@@ -401,6 +450,9 @@ impl BluetoothPage {
                 move |item| {
                     let device = item.downcast_ref::<BtDevice>().unwrap();
                     let row = BluetoothDeviceRow::new(device);
+                    // Devices are added one at a time, so every new row is a
+                    // device that just appeared or moved to this list
+                    crate::ui::fade_in_row(&row);
 
                     row.connect_activated(glib::clone!(
                         #[weak]
@@ -429,8 +481,47 @@ impl BluetoothPage {
         );
     }
 
+    fn show_audio_conflict_dialog(
+        &self,
+        manager: &WlcontrolManager,
+        name: &str,
+        previous_address: &str,
+        previous_name: &str,
+    ) {
+        let dialog = adw::AlertDialog::builder()
+            .heading(format!("Switch Audio to {}?", name))
+            .body(format!(
+                "\"{}\" is also connected, so audio may keep playing there. \
+                 Disconnect it to use \"{}\".",
+                previous_name, name
+            ))
+            .build();
+
+        dialog.add_response("keep", "Keep Both");
+        dialog.add_response("switch", "Disconnect Previous");
+        dialog.set_response_appearance("switch", adw::ResponseAppearance::Suggested);
+        dialog.set_default_response(Some("switch"));
+        dialog.set_close_response("keep");
+
+        let previous_address = previous_address.to_string();
+        glib::spawn_future_local(glib::clone!(
+            #[weak(rename_to = page)]
+            self,
+            #[weak]
+            manager,
+            async move {
+                let response = dialog.choose_future(Some(&page)).await;
+                if response == "switch" {
+                    manager.request_bt_disconnect(&previous_address);
+                }
+            }
+        ));
+    }
+
     pub fn show_toast(&self, message: &str) {
         let toast = adw::Toast::new(message);
+        // Messages can contain device names, which are not markup
+        toast.set_use_markup(false);
         self.imp().toast_overlay.add_toast(toast);
     }
 }
@@ -1,13 +1,191 @@
 mod window;
+#[cfg(feature = "wifi")]
 mod wifi_page;
+#[cfg(feature = "bluetooth")]
 mod bluetooth_page;
+#[cfg(feature = "wifi")]
 mod wifi_network_row;
+#[cfg(feature = "bluetooth")]
 mod bluetooth_device_row;
+#[cfg(feature = "wifi")]
 mod password_dialog;
+mod preferences_dialog;
 
 pub use window::WlcontrolWindow;
+#[cfg(feature = "wifi")]
 pub use wifi_page::WifiPage;
+#[cfg(feature = "bluetooth")]
 pub use bluetooth_page::BluetoothPage;
+#[cfg(feature = "wifi")]
 pub use wifi_network_row::WifiNetworkRow;
+#[cfg(feature = "bluetooth")]
 pub use bluetooth_device_row::BluetoothDeviceRow;
+#[cfg(feature = "wifi")]
 pub use password_dialog::PasswordDialog;
+pub use preferences_dialog::PreferencesDialog;
+
+/// Call `open` on right-click, touch long-press, or the Menu key (and
+/// Shift+F10) on a row, with the pointer position when there is one. Lets
+/// a row's menu be reached even while its menu button is hidden.
+pub(crate) fn add_context_menu_triggers<R: gtk::prelude::IsA<gtk::Widget>>(
+    row: &R,
+    open: impl Fn(&R, Option<(f64, f64)>) + Clone + 'static,
+) {
+    use gtk::{gdk, glib, prelude::*};
+
+    let click = gtk::GestureClick::builder()
+        .button(gdk::BUTTON_SECONDARY)
+        .build();
+    let on_click = open.clone();
+    click.connect_pressed(move |gesture, _, x, y| {
+        if let Some(row) = gesture.widget().and_downcast::<R>() {
+            gesture.set_state(gtk::EventSequenceState::Claimed);
+            on_click(&row, Some((x, y)));
+        }
+    });
+    row.add_controller(click);
+
+    let long_press = gtk::GestureLongPress::builder().touch_only(true).build();
+    let on_long_press = open.clone();
+    long_press.connect_pressed(move |gesture, x, y| {
+        if let Some(row) = gesture.widget().and_downcast::<R>() {
+            gesture.set_state(gtk::EventSequenceState::Claimed);
+            on_long_press(&row, Some((x, y)));
+        }
+    });
+    row.add_controller(long_press);
+
+    let keys = gtk::EventControllerKey::new();
+    keys.connect_key_pressed(move |controller, key, _, modifiers| {
+        let menu_key = key == gdk::Key::Menu
+            || (key == gdk::Key::F10 && modifiers.contains(gdk::ModifierType::SHIFT_MASK));
+        match controller.widget().and_downcast::<R>() {
+            Some(row) if menu_key => {
+                open(&row, None);
+                glib::Propagation::Stop
+            }
+            _ => glib::Propagation::Proceed,
+        }
+    });
+    row.add_controller(keys);
+}
+
+/// Show a menu model as a popover on `widget`, pointing at `at` or at the
+/// whole widget. The popover resolves actions from `widget` and is removed
+/// once closed.
+pub(crate) fn popup_menu(
+    widget: &impl gtk::prelude::IsA<gtk::Widget>,
+    model: &gtk::gio::MenuModel,
+    at: Option<(f64, f64)>,
+) {
+    use gtk::{gdk, glib, prelude::*};
+
+    let popover = gtk::PopoverMenu::from_model(Some(model));
+    popover.set_parent(widget);
+    if let Some((x, y)) = at {
+        popover.set_pointing_to(Some(&gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+        popover.set_has_arrow(false);
+    }
+    // The chosen item's action runs after "closed"; unparent afterwards
+    popover.connect_closed(|popover| {
+        let popover = popover.clone();
+        glib::idle_add_local_once(move || popover.unparent());
+    });
+    popover.popup();
+}
+
+/// How long a newly listed row takes to fade in, in milliseconds
+const ROW_FADE_IN_MS: u32 = 200;
+
+/// Fade a newly listed row in once it is first shown. libadwaita skips
+/// the animation when animations are turned off in the system settings.
+pub(crate) fn fade_in_row(row: &impl gtk::prelude::IsA<gtk::Widget>) {
+    use gtk::prelude::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    let target = adw::PropertyAnimationTarget::new(row, "opacity");
+    let animation = adw::TimedAnimation::builder()
+        .widget(row)
+        .value_from(0.0)
+        .value_to(1.0)
+        .duration(ROW_FADE_IN_MS)
+        .target(&target)
+        .build();
+    // An unmapped widget's animation ends at once, so wait for the row to
+    // be shown; later maps (switching pages) don't fade it again
+    let handler = Rc::new(RefCell::new(None));
+    let handler_ref = handler.clone();
+    let id = row.connect_map(move |row| {
+        animation.play();
+        if let Some(id) = handler_ref.borrow_mut().take() {
+            row.disconnect(id);
+        }
+    });
+    handler.replace(Some(id));
+}
+
+/// Apply the "color-scheme" preference: "light" or "dark" force a style,
+/// anything else follows the system
+pub(crate) fn apply_color_scheme(key: &str) {
+    let scheme = match key {
+        "light" => adw::ColorScheme::ForceLight,
+        "dark" => adw::ColorScheme::ForceDark,
+        _ => adw::ColorScheme::Default,
+    };
+    adw::StyleManager::default().set_color_scheme(scheme);
+}
+
+/// Compact elapsed time for subtitles, e.g. "42s", "5m", "1h23m"
+pub(crate) fn format_duration(duration: std::time::Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        _ => format!("{}h{}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+/// One-line connection summary for the header, e.g.
+/// "HomeNet · Jabra connected" or "Not connected"
+pub(crate) fn status_summary(wifi: Option<&str>, bt: &[String]) -> String {
+    let mut parts: Vec<String> = wifi.map(str::to_string).into_iter().collect();
+    match bt {
+        [] => {}
+        [one] => parts.push(one.clone()),
+        [first, second] => parts.push(format!("{}, {}", first, second)),
+        _ => parts.push(format!("{} devices", bt.len())),
+    }
+    if parts.is_empty() {
+        "Not connected".to_string()
+    } else {
+        format!("{} connected", parts.join(" · "))
+    }
+}
+
+/// Day-level time since a Unix timestamp, e.g. "today", "yesterday",
+/// "3 days ago", or the date for anything older than a week
+pub(crate) fn format_last_seen(time: i64) -> String {
+    let (Ok(now), Ok(then)) = (
+        gtk::glib::DateTime::now_local(),
+        gtk::glib::DateTime::from_unix_local(time),
+    ) else {
+        return String::new();
+    };
+    // Count calendar days in local time, not 24h periods
+    let offset = now.utc_offset().as_seconds();
+    let day = |t: i64| (t + offset).div_euclid(86_400);
+    match day(now.to_unix()) - day(time) {
+        ..=0 => "today".to_string(),
+        1 => "yesterday".to_string(),
+        days @ 2..=6 => format!("{} days ago", days),
+        _ if then.year() == now.year() => then
+            .format("%b %-d")
+            .map(|s| s.to_string())
+            .unwrap_or_default(),
+        _ => then
+            .format("%b %-d, %Y")
+            .map(|s| s.to_string())
+            .unwrap_or_default(),
+    }
+}
@@ -1,6 +1,28 @@
 use adw::prelude::*;
 use adw::subclass::prelude::*;
 use gtk::glib;
+use std::cell::RefCell;
+
+/// Whether a passphrase has a usable format for the network's security type.
+/// WPA-PSK takes 8-63 bytes of passphrase or a 64-digit hex key; other types
+/// only need something.
+fn passphrase_valid(network_type: &str, passphrase: &str) -> bool {
+    let is_hex = |len: usize| {
+        passphrase.len() == len && passphrase.bytes().all(|b| b.is_ascii_hexdigit())
+    };
+    match network_type {
+        "psk" => (8..=63).contains(&passphrase.len()) || is_hex(64),
+        _ => !passphrase.is_empty(),
+    }
+}
+
+/// Expected passphrase format shown under the entry
+fn format_hint(network_type: &str) -> Option<&'static str> {
+    match network_type {
+        "psk" => Some("8 to 63 characters, or a 64-digit hex key"),
+        _ => None,
+    }
+}
 
 mod imp {
     use super::*;
@@ -10,6 +32,12 @@ mod imp {
     pub struct PasswordDialog {
         #[template_child]
         pub password_entry: TemplateChild<adw::PasswordEntryRow>,
+        #[template_child]
+        pub hint_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub remember_check: TemplateChild<gtk::CheckButton>,
+
+        pub network_type: RefCell<String>,
     }
 
     #[glib::object_subclass]
@@ -31,16 +59,14 @@ mod imp {
         fn constructed(&self) {
             self.parent_constructed();
 
-            // WPA/WPA2 requires minimum 8 characters
-            const MIN_PASSWORD_LENGTH: usize = 8;
-
-            // Enable connect button only when password meets minimum length
+            // Enable connect button only when the password fits the security type
             let dialog = self.obj();
             self.password_entry.connect_text_notify(glib::clone!(
                 #[weak]
                 dialog,
                 move |entry| {
-                    let valid = entry.text().len() >= MIN_PASSWORD_LENGTH;
+                    let network_type = dialog.imp().network_type.borrow();
+                    let valid = passphrase_valid(&network_type, &entry.text());
                     dialog.set_response_enabled("connect", valid);
                 }
             ));
@@ -62,9 +88,15 @@ glib::wrapper! {
 }
 
 impl PasswordDialog {
-    pub fn new(network_name: &str) -> Self {
+    pub fn new(network_name: &str, network_type: &str) -> Self {
         let dialog: Self = glib::Object::new();
         dialog.set_body(&format!("Enter password for \"{}\"", network_name));
+        dialog.imp().network_type.replace(network_type.to_string());
+        let hint_label = &dialog.imp().hint_label;
+        match format_hint(network_type) {
+            Some(hint) => hint_label.set_label(hint),
+            None => hint_label.set_visible(false),
+        }
         dialog
     }
 
@@ -72,13 +104,43 @@ impl PasswordDialog {
         self.imp().password_entry.text().to_string()
     }
 
-    pub async fn run(self, parent: &impl IsA<gtk::Widget>) -> Option<String> {
+    /// Show the dialog and wait for the user. Returns the entered password
+    /// (None if cancelled) and whether to remember the network.
+    pub async fn run(self, parent: &impl IsA<gtk::Widget>) -> (Option<String>, bool) {
         let entry = self.imp().password_entry.clone();
+        let remember_check = self.imp().remember_check.clone();
         let response = self.choose_future(Some(parent)).await;
+        let remember = remember_check.is_active();
         if response == "connect" {
-            Some(entry.text().to_string())
+            (Some(entry.text().to_string()), remember)
         } else {
-            None
+            (None, remember)
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn psk_takes_passphrase_or_hex_key() {
+        assert!(!passphrase_valid("psk", "short"));
+        assert!(passphrase_valid("psk", "password"));
+        assert!(!passphrase_valid("psk", &"x".repeat(64)));
+        assert!(passphrase_valid("psk", &"ab".repeat(32)));
+    }
+
+    #[test]
+    fn psk_length_counts_bytes() {
+        // 32 characters, but 64 bytes of UTF-8
+        assert!(!passphrase_valid("psk", &"é".repeat(32)));
+        assert!(passphrase_valid("psk", &"é".repeat(31)));
+    }
+
+    #[test]
+    fn other_types_take_any_length() {
+        assert!(passphrase_valid("8021x", "pin"));
+        assert!(!passphrase_valid("8021x", ""));
+    }
+}
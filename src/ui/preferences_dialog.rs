@@ -0,0 +1,176 @@
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gtk::glib;
+
+use crate::backend::{BtAgentCapability, WlcontrolManager};
+
+mod imp {
+    use super::*;
+
+    #[derive(Default, gtk::CompositeTemplate)]
+    #[template(resource = "/dev/neoden/wlcontrol/ui/preferences-dialog.ui")]
+    pub struct PreferencesDialog {
+        #[template_child]
+        pub run_in_background_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub color_scheme_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub wifi_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub wifi_scan_on_focus_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub thorough_scan_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub group_networks_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub warn_open_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub forget_failed_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub bluetooth_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub bt_scan_on_focus_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub keep_discovered_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub connect_on_pair_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub pause_scan_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub show_unnamed_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub agent_capability_row: TemplateChild<adw::ComboRow>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PreferencesDialog {
+        const NAME: &'static str = "PreferencesDialog";
+        type Type = super::PreferencesDialog;
+        type ParentType = adw::PreferencesDialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for PreferencesDialog {}
+    impl WidgetImpl for PreferencesDialog {}
+    impl AdwDialogImpl for PreferencesDialog {}
+    impl PreferencesDialogImpl for PreferencesDialog {}
+}
+
+glib::wrapper! {
+    pub struct PreferencesDialog(ObjectSubclass<imp::PreferencesDialog>)
+        @extends gtk::Widget, adw::Dialog, adw::PreferencesDialog,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+impl PreferencesDialog {
+    pub fn new(manager: &WlcontrolManager) -> Self {
+        let dialog: Self = glib::Object::new();
+        let imp = dialog.imp();
+
+        manager
+            .bind_property("run-in-background", &*imp.run_in_background_row, "active")
+            .sync_create()
+            .bidirectional()
+            .build();
+        manager
+            .bind_property("wifi-scan-on-focus", &*imp.wifi_scan_on_focus_row, "active")
+            .sync_create()
+            .bidirectional()
+            .build();
+        manager
+            .bind_property("wifi-thorough-scan", &*imp.thorough_scan_row, "active")
+            .sync_create()
+            .bidirectional()
+            .build();
+        manager
+            .bind_property("wifi-group-networks", &*imp.group_networks_row, "active")
+            .sync_create()
+            .bidirectional()
+            .build();
+        manager
+            .bind_property("wifi-warn-open", &*imp.warn_open_row, "active")
+            .sync_create()
+            .bidirectional()
+            .build();
+        manager
+            .bind_property("wifi-forget-failed-new", &*imp.forget_failed_row, "active")
+            .sync_create()
+            .bidirectional()
+            .build();
+        manager
+            .bind_property("bt-scan-on-focus", &*imp.bt_scan_on_focus_row, "active")
+            .sync_create()
+            .bidirectional()
+            .build();
+        manager
+            .bind_property("bt-keep-discovered", &*imp.keep_discovered_row, "active")
+            .sync_create()
+            .bidirectional()
+            .build();
+        manager
+            .bind_property("bt-connect-on-pair", &*imp.connect_on_pair_row, "active")
+            .sync_create()
+            .bidirectional()
+            .build();
+        manager
+            .bind_property("bt-pause-scan-on-connect", &*imp.pause_scan_row, "active")
+            .sync_create()
+            .bidirectional()
+            .build();
+        manager
+            .bind_property("bt-show-unnamed", &*imp.show_unnamed_row, "active")
+            .sync_create()
+            .bidirectional()
+            .build();
+
+        // Nothing to configure for a radio the build leaves out
+        #[cfg(not(feature = "wifi"))]
+        imp.wifi_group.set_visible(false);
+        #[cfg(not(feature = "bluetooth"))]
+        imp.bluetooth_group.set_visible(false);
+
+        // Applied here rather than through the manager: it's UI-only
+        let scheme = match crate::settings::get_string("color-scheme", "auto").as_str() {
+            "light" => 1,
+            "dark" => 2,
+            _ => 0,
+        };
+        imp.color_scheme_row.set_selected(scheme);
+        imp.color_scheme_row.connect_selected_notify(|row| {
+            let key = match row.selected() {
+                1 => "light",
+                2 => "dark",
+                _ => "auto",
+            };
+            crate::settings::set_string("color-scheme", key);
+            crate::ui::apply_color_scheme(key);
+        });
+
+        imp.agent_capability_row
+            .set_selected(match manager.bt_agent_capability() {
+                BtAgentCapability::KeyboardDisplay => 0,
+                BtAgentCapability::DisplayYesNo => 1,
+                BtAgentCapability::NoInputNoOutput => 2,
+            });
+        imp.agent_capability_row.connect_selected_notify(glib::clone!(
+            #[weak]
+            manager,
+            move |row| {
+                manager.set_bt_agent_capability(match row.selected() {
+                    1 => BtAgentCapability::DisplayYesNo,
+                    2 => BtAgentCapability::NoInputNoOutput,
+                    _ => BtAgentCapability::KeyboardDisplay,
+                });
+            }
+        ));
+
+        dialog
+    }
+}
@@ -1,11 +1,23 @@
 use adw::prelude::*;
 use adw::subclass::prelude::*;
 use gtk::{gio, glib};
-use std::cell::OnceCell;
+use std::cell::{OnceCell, RefCell};
+use std::rc::Rc;
 
-use crate::backend::wifi::{WifiNetwork, WifiNetworkState};
+use crate::backend::wifi::network_config::{Ipv4Field, StaticIpv4};
+use crate::backend::wifi::{WifiNetwork, WifiNetworkState, LATENCY_TEST_DEFAULT_HOST};
 use crate::backend::WlcontrolManager;
 
+const STATIC_IP_BODY: &str =
+    "Used instead of DHCP from the next connection. iwd only applies it when it \
+     configures the network itself.";
+
+const LATENCY_BODY: &str =
+    "Times a connection to a host from this computer. It takes whichever \
+     route the system picks, which is this network unless another connection \
+     is preferred. Enter a host name or address, with \":port\" for a port \
+     other than 443.";
+
 mod imp {
     use super::*;
 
@@ -15,6 +27,8 @@ mod imp {
         #[template_child]
         pub signal_icon: TemplateChild<gtk::Image>,
         #[template_child]
+        pub trend_icon: TemplateChild<gtk::Image>,
+        #[template_child]
         pub security_icon: TemplateChild<gtk::Image>,
         #[template_child]
         pub connected_icon: TemplateChild<gtk::Image>,
@@ -22,6 +36,15 @@ mod imp {
         pub menu_button: TemplateChild<gtk::MenuButton>,
 
         pub network: OnceCell<WifiNetwork>,
+        pub pin_bssid_action: OnceCell<gio::SimpleAction>,
+        pub randomize_mac_action: OnceCell<gio::SimpleAction>,
+        pub static_ip_action: OnceCell<gio::SimpleAction>,
+        pub retry_action: OnceCell<gio::SimpleAction>,
+        pub test_connection_action: OnceCell<gio::SimpleAction>,
+        pub forget_action: OnceCell<gio::SimpleAction>,
+        pub manager: glib::WeakRef<WlcontrolManager>,
+        /// Ticks the "Connected for" subtitle while connected and mapped
+        pub duration_timer: RefCell<Option<glib::SourceId>>,
     }
 
     #[glib::object_subclass]
@@ -42,6 +65,13 @@ mod imp {
     impl ObjectImpl for WifiNetworkRow {
         fn constructed(&self) {
             self.parent_constructed();
+
+            let obj = self.obj();
+            obj.connect_map(|row| row.update_duration_timer());
+            obj.connect_unmap(|row| row.update_duration_timer());
+            crate::ui::add_context_menu_triggers(&*obj, |row: &super::WifiNetworkRow, at| {
+                row.open_context_menu(at)
+            });
         }
     }
     impl WidgetImpl for WifiNetworkRow {}
@@ -89,6 +119,7 @@ impl WifiNetworkRow {
         network: &WifiNetwork,
         is_saved_offline: bool,
     ) {
+        self.imp().manager.set(Some(manager));
         let group = gio::SimpleActionGroup::new();
 
         // copy-name
@@ -105,6 +136,80 @@ impl WifiNetworkRow {
         ));
         group.add_action(&copy_name);
 
+        // pin-bssid: keep the connection on the current access point
+        let pin_bssid = gio::SimpleAction::new_stateful("pin-bssid", None, &false.to_variant());
+        pin_bssid.connect_activate(glib::clone!(
+            #[weak]
+            manager,
+            move |action, _| {
+                let pinned = action.state().and_then(|s| s.get::<bool>()).unwrap_or(false);
+                manager.request_wifi_pin_bssid(!pinned);
+            }
+        ));
+        group.add_action(&pin_bssid);
+
+        // randomize-mac: iwd's per-network MAC randomization, stored in its
+        // network file rather than over D-Bus
+        let randomized = network.known()
+            && !network.imported()
+            && manager.wifi_address_randomization(network).unwrap_or(false);
+        let randomize_mac =
+            gio::SimpleAction::new_stateful("randomize-mac", None, &randomized.to_variant());
+        randomize_mac.connect_activate(glib::clone!(
+            #[weak]
+            manager,
+            #[weak]
+            network,
+            move |action, _| {
+                let enabled = !action.state().and_then(|s| s.get::<bool>()).unwrap_or(false);
+                if manager.set_wifi_address_randomization(&network, enabled) {
+                    action.set_state(&enabled.to_variant());
+                }
+            }
+        ));
+        group.add_action(&randomize_mac);
+
+        // static-ip: iwd's [IPv4] settings, also only in its network file
+        let static_ip = gio::SimpleAction::new("static-ip", None);
+        static_ip.connect_activate(glib::clone!(
+            #[weak(rename_to = row)]
+            self,
+            #[weak]
+            manager,
+            #[weak]
+            network,
+            move |_, _| {
+                Self::show_static_ip_dialog(&row, &manager, &network);
+            }
+        ));
+        group.add_action(&static_ip);
+
+        // retry: connect a network iwd has likely blacklisted after failures
+        let retry = gio::SimpleAction::new("retry", None);
+        retry.connect_activate(glib::clone!(
+            #[weak]
+            manager,
+            #[weak]
+            network,
+            move |_, _| {
+                manager.request_wifi_clear_and_retry(&network.path());
+            }
+        ));
+        group.add_action(&retry);
+
+        // test-connection: round-trip time to a host, while connected
+        let test_connection = gio::SimpleAction::new("test-connection", None);
+        test_connection.connect_activate(glib::clone!(
+            #[weak(rename_to = row)]
+            self,
+            #[weak]
+            manager,
+            move |_, _| {
+                Self::show_latency_dialog(&row, &manager);
+            }
+        ));
+        group.add_action(&test_connection);
+
         // forget
         let forget = gio::SimpleAction::new("forget", None);
         forget.connect_activate(glib::clone!(
@@ -121,6 +226,34 @@ impl WifiNetworkRow {
         group.add_action(&forget);
 
         self.insert_action_group("row", Some(&group));
+        self.imp().pin_bssid_action.set(pin_bssid).unwrap();
+        self.imp().randomize_mac_action.set(randomize_mac).unwrap();
+        self.imp().static_ip_action.set(static_ip).unwrap();
+        self.imp().retry_action.set(retry).unwrap();
+        self.imp().test_connection_action.set(test_connection).unwrap();
+        self.imp().forget_action.set(forget).unwrap();
+        self.sync_ui_to_state();
+    }
+
+    /// The menu button's menu, for right-click and the Menu key. Not while
+    /// connecting, disconnecting or forgetting, same as the button.
+    fn open_context_menu(&self, at: Option<(f64, f64)>) {
+        let imp = self.imp();
+        if imp.forget_action.get().is_none() {
+            return;
+        }
+        let busy = matches!(
+            self.network().state(),
+            WifiNetworkState::Connecting
+                | WifiNetworkState::Disconnecting
+                | WifiNetworkState::Forgetting
+        );
+        if busy {
+            return;
+        }
+        if let Some(model) = imp.menu_button.menu_model() {
+            crate::ui::popup_menu(self, &model, at);
+        }
     }
 
     fn show_forget_dialog(
@@ -133,7 +266,7 @@ impl WifiNetworkRow {
             .heading("Forget Network?")
             .body(format!(
                 "\"{}\" will be removed and you will need to enter the password again.",
-                network.name()
+                network.display_name()
             ))
             .build();
 
@@ -153,7 +286,9 @@ impl WifiNetworkRow {
             async move {
                 let response = dialog.choose_future(Some(&row)).await;
                 if response == "forget" {
-                    if is_saved_offline {
+                    if network.imported() {
+                        manager.remove_imported_network(&network.name(), &network.network_type());
+                    } else if is_saved_offline {
                         manager.request_wifi_forget_known(&network.path());
                     } else {
                         manager.request_wifi_forget(&network.path());
@@ -163,6 +298,224 @@ impl WifiNetworkRow {
         ));
     }
 
+    fn show_static_ip_dialog(
+        row: &WifiNetworkRow,
+        manager: &WlcontrolManager,
+        network: &WifiNetwork,
+    ) {
+        let current = manager.wifi_static_ipv4(network);
+        let dialog = adw::AlertDialog::builder()
+            .heading("Static IP")
+            .body(STATIC_IP_BODY)
+            .build();
+
+        dialog.add_response("cancel", "Cancel");
+        if current.is_some() {
+            dialog.add_response("dhcp", "Use DHCP");
+        }
+        dialog.add_response("apply", "Apply");
+        dialog.set_response_appearance("apply", adw::ResponseAppearance::Suggested);
+        dialog.set_default_response(Some("apply"));
+        dialog.set_close_response("cancel");
+
+        let address = adw::EntryRow::builder().title("Address").build();
+        let netmask = adw::EntryRow::builder().title("Netmask").build();
+        let gateway = adw::EntryRow::builder().title("Gateway (optional)").build();
+        let dns = adw::EntryRow::builder().title("DNS Servers (optional)").build();
+        if let Some(config) = &current {
+            address.set_text(&config.address.to_string());
+            netmask.set_text(&config.netmask.to_string());
+            if let Some(gateway_address) = config.gateway {
+                gateway.set_text(&gateway_address.to_string());
+            }
+            let servers: Vec<String> = config.dns.iter().map(|a| a.to_string()).collect();
+            dns.set_text(&servers.join(" "));
+        }
+
+        let group = adw::PreferencesGroup::new();
+        for entry in [&address, &netmask, &gateway, &dns] {
+            group.add(entry);
+        }
+        dialog.set_extra_child(Some(&group));
+
+        // Apply only for valid settings; point out the entry that's wrong
+        // once something has been typed into it
+        let update = glib::clone!(
+            #[weak]
+            dialog,
+            #[weak]
+            address,
+            #[weak]
+            netmask,
+            #[weak]
+            gateway,
+            #[weak]
+            dns,
+            move |_: &adw::EntryRow| {
+                let entries = [&address, &netmask, &gateway, &dns];
+                for entry in entries {
+                    entry.remove_css_class("error");
+                }
+                let parsed = StaticIpv4::parse(
+                    &address.text(),
+                    &netmask.text(),
+                    &gateway.text(),
+                    &dns.text(),
+                );
+                match parsed {
+                    Ok(_) => {
+                        dialog.set_body(STATIC_IP_BODY);
+                        dialog.set_response_enabled("apply", true);
+                    }
+                    Err((field, message)) => {
+                        let entry = match field {
+                            Ipv4Field::Address => &address,
+                            Ipv4Field::Netmask => &netmask,
+                            Ipv4Field::Gateway => &gateway,
+                            Ipv4Field::Dns => &dns,
+                        };
+                        if entry.text().is_empty() {
+                            dialog.set_body(STATIC_IP_BODY);
+                        } else {
+                            entry.add_css_class("error");
+                            dialog.set_body(message);
+                        }
+                        dialog.set_response_enabled("apply", false);
+                    }
+                }
+            }
+        );
+        update(&address);
+        for entry in [&address, &netmask, &gateway, &dns] {
+            entry.connect_changed(update.clone());
+        }
+
+        glib::spawn_future_local(glib::clone!(
+            #[weak]
+            manager,
+            #[weak]
+            network,
+            #[weak]
+            row,
+            async move {
+                let response = dialog.choose_future(Some(&row)).await;
+                if response == "apply" {
+                    let parsed = StaticIpv4::parse(
+                        &address.text(),
+                        &netmask.text(),
+                        &gateway.text(),
+                        &dns.text(),
+                    );
+                    if let Ok(config) = parsed {
+                        manager.set_wifi_static_ipv4(&network, Some(&config));
+                    }
+                } else if response == "dhcp" {
+                    manager.set_wifi_static_ipv4(&network, None);
+                }
+            }
+        ));
+    }
+
+    /// Test the round-trip time to a host, right away with the default host
+    /// and again on request; the host can be changed in between
+    fn show_latency_dialog(row: &WifiNetworkRow, manager: &WlcontrolManager) {
+        let dialog = adw::AlertDialog::builder()
+            .heading("Test Connection")
+            .body(LATENCY_BODY)
+            .build();
+        dialog.add_response("close", "Close");
+        dialog.set_close_response("close");
+
+        let host = adw::EntryRow::builder()
+            .title("Host")
+            .text(LATENCY_TEST_DEFAULT_HOST)
+            .show_apply_button(true)
+            .build();
+        let result = adw::ActionRow::builder().title("Round Trip").build();
+        let again = gtk::Button::builder()
+            .icon_name("view-refresh-symbolic")
+            .tooltip_text("Test Again")
+            .valign(gtk::Align::Center)
+            .css_classes(["flat"])
+            .build();
+        result.add_suffix(&again);
+
+        let group = adw::PreferencesGroup::new();
+        group.add(&host);
+        group.add(&result);
+        dialog.set_extra_child(Some(&group));
+
+        // One test at a time, remembered by the host it was asked for; a
+        // result for another host, or while none is running, belongs to an
+        // earlier dialog
+        let testing: Rc<RefCell<Option<String>>> = Rc::new(RefCell::new(None));
+        let run = glib::clone!(
+            #[weak]
+            manager,
+            #[weak]
+            host,
+            #[weak]
+            result,
+            #[weak]
+            again,
+            #[strong]
+            testing,
+            move || {
+                if testing.borrow().is_some() {
+                    return;
+                }
+                let requested = host.text().trim().to_string();
+                testing.replace(Some(requested.clone()));
+                again.set_sensitive(false);
+                result.set_subtitle("Testing…");
+                manager.request_wifi_latency_test(&requested);
+            }
+        );
+
+        let handler = manager.connect_closure(
+            "wifi-latency",
+            false,
+            glib::closure_local!(
+                #[weak]
+                result,
+                #[weak]
+                again,
+                #[strong]
+                testing,
+                move |_manager: WlcontrolManager, host: String, ms: u32, error: String| {
+                    if testing.borrow().as_deref() != Some(host.as_str()) {
+                        return;
+                    }
+                    testing.replace(None);
+                    if error.is_empty() {
+                        result.set_subtitle(&format!("{} ms", ms));
+                    } else {
+                        result.set_subtitle(&error);
+                    }
+                    again.set_sensitive(true);
+                }
+            ),
+        );
+        let handler = RefCell::new(Some(handler));
+        dialog.connect_closed(glib::clone!(
+            #[weak]
+            manager,
+            move |_| {
+                if let Some(handler) = handler.take() {
+                    manager.disconnect(handler);
+                }
+            }
+        ));
+
+        let on_apply = run.clone();
+        host.connect_apply(move |_| on_apply());
+        let on_click = run.clone();
+        again.connect_clicked(move |_| on_click());
+
+        dialog.present(Some(row));
+        run();
+    }
+
     /// Derive all UI widget states from the network's canonical state.
     /// Exhaustive match ensures adding a new state is a compile error
     /// until every UI element is accounted for.
@@ -172,17 +525,46 @@ impl WifiNetworkRow {
         let imp = self.imp();
 
         // Orthogonal to state: always update
-        self.set_title(&network.name());
+        if network.hidden() {
+            self.set_title(&format!("{} (hidden)", network.display_name()));
+        } else {
+            self.set_title(&network.display_name());
+        }
         imp.signal_icon.set_icon_name(Some(network.signal_icon()));
         imp.security_icon.set_visible(network.is_secured());
+        // Rebuilt on every change, so it never shows another state's details
+        self.set_tooltip_markup(Some(&Self::tooltip_markup(network, state)));
+        let bssid = network.bssid();
+        if let Some(action) = imp.pin_bssid_action.get() {
+            action.set_state(&network.bssid_pinned().to_variant());
+            action.set_enabled(state == WifiNetworkState::Connected && !bssid.is_empty());
+        }
+        if let Some(action) = imp.randomize_mac_action.get() {
+            action.set_enabled(network.known() && !network.imported());
+        }
+        if let Some(action) = imp.static_ip_action.get() {
+            action.set_enabled(network.known() && !network.imported());
+        }
+        if let Some(action) = imp.retry_action.get() {
+            action.set_enabled(state == WifiNetworkState::Saved && network.blocked());
+        }
+        if let Some(action) = imp.test_connection_action.get() {
+            let established = !network.station_state().is_establishing();
+            action.set_enabled(state == WifiNetworkState::Connected && established);
+        }
+        // The menu can open on networks that aren't saved, via right-click
+        if let Some(action) = imp.forget_action.get() {
+            action.set_enabled(network.known() || network.imported());
+        }
 
-        // Busy states
+        // Busy states (including connected while auth/DHCP is still running)
+        let station_state = network.station_state();
         let busy = matches!(
             state,
             WifiNetworkState::Connecting
                 | WifiNetworkState::Disconnecting
                 | WifiNetworkState::Forgetting
-        );
+        ) || (state == WifiNetworkState::Connected && station_state.is_establishing());
         if busy {
             self.add_css_class("wifi-busy");
         } else {
@@ -200,26 +582,53 @@ impl WifiNetworkRow {
                 imp.connected_icon.set_visible(false);
                 imp.menu_button.set_visible(true);
                 imp.signal_icon.set_visible(true);
-                self.set_subtitle("Saved");
+                if network.blocked() {
+                    self.set_subtitle("Temporarily blocked by iwd");
+                } else {
+                    self.set_subtitle("Saved");
+                }
                 self.set_activatable(true);
             }
             WifiNetworkState::SavedOffline => {
                 imp.connected_icon.set_visible(false);
                 imp.menu_button.set_visible(true);
                 imp.signal_icon.set_visible(false);
-                self.set_subtitle("Saved");
-                self.set_activatable(false);
+                if network.imported() {
+                    self.set_subtitle("Imported, password needed");
+                } else {
+                    self.set_subtitle("Saved");
+                }
+                // Hidden networks are tried directly, others searched for first
+                self.set_activatable(true);
             }
             WifiNetworkState::Connecting => {
                 imp.connected_icon.set_visible(false);
                 imp.menu_button.set_visible(false);
-                self.set_subtitle("");
+                self.set_subtitle(station_state.label().unwrap_or(""));
                 self.set_activatable(false);
             }
             WifiNetworkState::Connected => {
-                imp.connected_icon.set_visible(true);
+                imp.connected_icon.set_visible(!station_state.is_establishing());
                 imp.menu_button.set_visible(true);
-                self.set_subtitle("Connected");
+                let subtitle = match station_state.label() {
+                    Some(label) => label.to_string(),
+                    None if network.captive_portal() => "Login required".to_string(),
+                    None if network.bssid_pinned() => {
+                        format!("Connected, pinned to {}", network.bssid())
+                    }
+                    None => match self
+                        .imp()
+                        .manager
+                        .upgrade()
+                        .and_then(|m| m.wifi_connected_duration(&network.path()))
+                    {
+                        Some(duration) => {
+                            format!("Connected for {}", crate::ui::format_duration(duration))
+                        }
+                        None => "Connected".to_string(),
+                    },
+                };
+                self.set_subtitle(&subtitle);
                 self.set_activatable(true);
             }
             WifiNetworkState::Disconnecting => {
@@ -235,5 +644,69 @@ impl WifiNetworkRow {
                 self.set_activatable(false);
             }
         }
+
+        // Trend arrow goes with the signal icon
+        match network.signal_trend() {
+            Some((icon_name, tooltip)) if imp.signal_icon.is_visible() => {
+                imp.trend_icon.set_icon_name(Some(icon_name));
+                imp.trend_icon.set_tooltip_text(Some(tooltip));
+                imp.trend_icon.set_visible(true);
+            }
+            _ => imp.trend_icon.set_visible(false),
+        }
+
+        self.update_duration_timer();
+    }
+
+    /// Name, security, signal and status, one per line
+    fn tooltip_markup(network: &WifiNetwork, state: WifiNetworkState) -> String {
+        let mut lines = vec![
+            format!("<b>{}</b>", glib::markup_escape_text(&network.display_name())),
+            format!("Security: {}", glib::markup_escape_text(&network.security_label())),
+        ];
+        if state != WifiNetworkState::SavedOffline {
+            lines.push(format!("Signal: {} dBm", network.signal_dbm()));
+        }
+        let status = if network.connected() {
+            "Connected"
+        } else if network.imported() {
+            "Imported"
+        } else if network.known() {
+            "Saved"
+        } else {
+            "Not saved"
+        };
+        lines.push(format!("Status: {}", status));
+        let bssid = network.bssid();
+        if !bssid.is_empty() {
+            lines.push(format!("Access point: {}", glib::markup_escape_text(&bssid)));
+        }
+        lines.join("\n")
+    }
+
+    /// Run the once-a-second subtitle refresh only while it can be seen
+    fn update_duration_timer(&self) {
+        let imp = self.imp();
+        let wanted = self.is_mapped() && self.network().state() == WifiNetworkState::Connected;
+        if !wanted {
+            if let Some(source) = imp.duration_timer.take() {
+                source.remove();
+            }
+        } else if imp.duration_timer.borrow().is_none() {
+            let source = glib::timeout_add_seconds_local(
+                1,
+                glib::clone!(
+                    #[weak(rename_to = row)]
+                    self,
+                    #[upgrade_or]
+                    glib::ControlFlow::Break,
+                    move || {
+                        row.sync_ui_to_state();
+                        glib::ControlFlow::Continue
+                    }
+                ),
+            );
+            imp.duration_timer.replace(Some(source));
+        }
     }
 }
@@ -1,12 +1,16 @@
 use adw::prelude::*;
 use adw::subclass::prelude::*;
 use gtk::{gio, glib};
-use std::cell::OnceCell;
+use std::cell::{OnceCell, RefCell};
 
-use crate::backend::wifi::{WifiNetwork, WifiNetworkState};
-use crate::backend::WlcontrolManager;
+use crate::backend::wifi::scan_export::{self, ScanResult};
+use crate::backend::wifi::{display_ssid, WifiNetwork, WifiNetworkState};
+use crate::backend::{RequestId, WifiPowerControl, WifiSavedSort, WlcontrolManager};
 use crate::ui::{PasswordDialog, WifiNetworkRow};
 
+/// iwd network configuration reference, covering 802.1X provisioning files
+const IWD_NETWORK_CONFIG_URL: &str = "https://man.archlinux.org/man/iwd.network.5";
+
 mod imp {
     use super::*;
 
@@ -20,8 +24,12 @@ mod imp {
         #[template_child]
         pub adapter_switch: TemplateChild<adw::SwitchRow>,
         #[template_child]
+        pub power_control_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
         pub scan_button: TemplateChild<gtk::Button>,
         #[template_child]
+        pub networks_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
         pub networks_listbox: TemplateChild<gtk::ListBox>,
         #[template_child]
         pub saved_group: TemplateChild<adw::PreferencesGroup>,
@@ -31,8 +39,20 @@ mod imp {
         pub saved_listbox: TemplateChild<gtk::ListBox>,
 
         pub manager: OnceCell<WlcontrolManager>,
+        /// Open passphrase prompt, closed if the backend drops the request
+        pub password_dialog: RefCell<Option<PasswordDialog>>,
         /// Suppress adapter_combo "selected" handler during programmatic updates
         pub updating_combo: std::cell::Cell<bool>,
+        /// Status line and power state per adapter, in dropdown order
+        pub adapter_statuses: RefCell<Vec<(String, bool)>>,
+        /// Device path per dropdown entry, as of the last rebuild
+        pub adapter_paths: RefCell<Vec<String>>,
+        /// Adapters changed while the dropdown was open; rebuild on close
+        pub adapter_rebuild_pending: std::cell::Cell<bool>,
+        /// Request of the hidden network dialog's connect, while it runs
+        pub hidden_pending: RefCell<Option<RequestId>>,
+        /// Shown in the empty network list; says so when the adapter is off
+        pub networks_placeholder: OnceCell<gtk::Label>,
     }
 
     #[glib::object_subclass]
@@ -66,8 +86,9 @@ mod imp {
         fn constructed(&self) {
             self.parent_constructed();
 
-            self.networks_listbox
-                .set_placeholder(Some(&Self::create_placeholder("No networks found")));
+            let placeholder = Self::create_placeholder("No networks found");
+            self.networks_listbox.set_placeholder(Some(&placeholder));
+            self.networks_placeholder.set(placeholder).unwrap();
         }
     }
 
@@ -96,19 +117,44 @@ impl WifiPage {
         let imp = self.imp();
         imp.manager.set(manager.clone()).unwrap();
 
-        // Spinning animation and disable scan button while scanning
+        // Spinning animation and disable scan button while scanning,
+        // including the pauses between thorough scan cycles. The active
+        // adapter being off also disables it: there is no Station to scan.
         let scan_button = imp.scan_button.clone();
-        manager.connect_notify_local(
-            Some("wifi-scanning"),
-            move |manager, _| {
-                let scanning = manager.wifi_scanning();
+        let update_scanning = glib::clone!(
+            #[weak(rename_to = page)]
+            self,
+            move |manager: &WlcontrolManager, _: &glib::ParamSpec| {
+                let scanning = manager.wifi_scanning() || manager.wifi_scan_cycle().is_some();
                 if scanning {
                     scan_button.add_css_class("scanning");
                 } else {
                     scan_button.remove_css_class("scanning");
                 }
-                scan_button.set_sensitive(!scanning);
-            },
+                scan_button.set_sensitive(!scanning && manager.wifi_powered());
+                page.update_scan_age(manager);
+            }
+        );
+        manager.connect_notify_local(Some("wifi-scanning"), update_scanning.clone());
+        manager.connect_notify_local(Some("wifi-scan-cycle"), update_scanning.clone());
+        manager.connect_notify_local(Some("wifi-powered"), update_scanning);
+
+        // iwd may hand out results from minutes ago; keep their age visible
+        self.update_scan_age(manager);
+        glib::timeout_add_seconds_local(
+            1,
+            glib::clone!(
+                #[weak(rename_to = page)]
+                self,
+                #[weak]
+                manager,
+                #[upgrade_or]
+                glib::ControlFlow::Break,
+                move || {
+                    page.update_scan_age(&manager);
+                    glib::ControlFlow::Continue
+                }
+            ),
         );
 
         // Bind adapter power state
@@ -117,9 +163,130 @@ impl WifiPage {
             .sync_create()
             .bidirectional()
             .build();
+        manager
+            .bind_property("airplane-mode", &*imp.adapter_switch, "sensitive")
+            .sync_create()
+            .invert_boolean()
+            .build();
 
-        // Adapter selector DropDown
+        // Show both power switches in the toggle tooltip
+        let adapter_switch = imp.adapter_switch.clone();
+        let update_power_tooltip = move |manager: &WlcontrolManager| {
+            let on_off = |b: bool| if b { "on" } else { "off" };
+            adapter_switch.set_tooltip_text(Some(&format!(
+                "Interface: {} · Radio: {}",
+                on_off(manager.wifi_powered()),
+                on_off(manager.wifi_radio_powered())
+            )));
+        };
+        update_power_tooltip(manager);
+        let update = update_power_tooltip.clone();
+        manager.connect_notify_local(Some("wifi-powered"), move |manager, _| update(manager));
+        manager.connect_notify_local(Some("wifi-radio-powered"), move |manager, _| {
+            update_power_tooltip(manager)
+        });
+
+        // Which switch the WiFi toggle drives
+        imp.power_control_row.set_selected(match manager.wifi_power_control() {
+            WifiPowerControl::Device => 0,
+            WifiPowerControl::Adapter => 1,
+        });
+        imp.power_control_row.connect_selected_notify(glib::clone!(
+            #[weak]
+            manager,
+            move |row| {
+                manager.set_wifi_power_control(match row.selected() {
+                    1 => WifiPowerControl::Adapter,
+                    _ => WifiPowerControl::Device,
+                });
+            }
+        ));
+
+        // Adapter selector DropDown; the popup lists each adapter's state
+        // under its name, dimmed when it's off
+        let factory = gtk::SignalListItemFactory::new();
+        factory.connect_setup(|_, item| {
+            let item = item.downcast_ref::<gtk::ListItem>().unwrap();
+            let name = gtk::Label::builder().xalign(0.0).build();
+            let status = gtk::Label::builder().xalign(0.0).build();
+            status.add_css_class("caption");
+            status.add_css_class("dim-label");
+            let content = gtk::Box::new(gtk::Orientation::Vertical, 2);
+            content.append(&name);
+            content.append(&status);
+            item.set_child(Some(&content));
+        });
+        factory.connect_bind(glib::clone!(
+            #[weak(rename_to = page)]
+            self,
+            move |_, item| {
+                let item = item.downcast_ref::<gtk::ListItem>().unwrap();
+                let Some(content) = item.child() else { return };
+                let name = content.first_child().and_downcast::<gtk::Label>().unwrap();
+                let status = content.last_child().and_downcast::<gtk::Label>().unwrap();
+                let text = item
+                    .item()
+                    .and_downcast::<gtk::StringObject>()
+                    .map(|s| s.string().to_string())
+                    .unwrap_or_default();
+                name.set_label(&text);
+                let statuses = page.imp().adapter_statuses.borrow();
+                let (status_text, powered) = statuses
+                    .get(item.position() as usize)
+                    .cloned()
+                    .unwrap_or_default();
+                status.set_label(&status_text);
+                if powered {
+                    name.remove_css_class("dim-label");
+                } else {
+                    name.add_css_class("dim-label");
+                }
+            }
+        ));
+        imp.adapter_dropdown.set_list_factory(Some(&factory));
         self.rebuild_adapter_dropdown(manager);
+        // Rebuilds are held back while the popup is open, so entries don't
+        // move under the pointer during hot-plug; catch up once it closes
+        if let Some(popover) = self.adapter_popover() {
+            popover.connect_closed(glib::clone!(
+                #[weak(rename_to = page)]
+                self,
+                #[weak]
+                manager,
+                move |_| {
+                    if page.imp().adapter_rebuild_pending.take() {
+                        page.rebuild_adapter_dropdown(&manager);
+                    }
+                }
+            ));
+        }
+        let rebuild = glib::clone!(
+            #[weak(rename_to = page)]
+            self,
+            move |manager: &WlcontrolManager| page.rebuild_adapter_dropdown(manager)
+        );
+        let on_powered = rebuild.clone();
+        manager.connect_notify_local(
+            Some("wifi-powered"),
+            glib::clone!(
+                #[weak(rename_to = page)]
+                self,
+                move |manager, _| {
+                    on_powered(manager);
+                    page.update_networks_placeholder(manager);
+                }
+            ),
+        );
+        self.update_networks_placeholder(manager);
+        manager.connect_closure(
+            "wifi-network-updated",
+            false,
+            glib::closure_local!(move |manager: WlcontrolManager| {
+                if manager.wifi_adapters().len() > 1 {
+                    rebuild(&manager);
+                }
+            }),
+        );
 
         manager.connect_closure(
             "wifi-adapters-changed",
@@ -145,18 +312,90 @@ impl WifiPage {
                     if page.imp().updating_combo.get() {
                         return;
                     }
+                    // Map through the list the dropdown was built from; the
+                    // manager's may have changed since
                     let idx = dropdown.selected() as usize;
-                    let adapters = manager.wifi_adapters();
-                    if let Some(info) = adapters.get(idx) {
-                        manager.set_active_wifi_adapter(&info.device_path);
+                    let Some(path) = page.imp().adapter_paths.borrow().get(idx).cloned() else {
+                        return;
+                    };
+                    let present = manager.wifi_adapters().iter().any(|a| a.device_path == path);
+                    let active = manager.active_wifi_device_path();
+                    if present && active.as_deref() != Some(path.as_str()) {
+                        manager.set_active_wifi_adapter(&path);
                     }
                 }
             ),
         );
 
-        // Bind main network list (all scan results)
+        // Bind main network list (all scan results). Grouped, it's sorted by
+        // section, keeping the backend's order within each, and every section
+        // gets a header; ungrouped the sorter leaves the order alone
+        let section_sorter = gtk::CustomSorter::new(glib::clone!(
+            #[weak]
+            manager,
+            #[upgrade_or]
+            gtk::Ordering::Equal,
+            move |a, b| {
+                if !manager.wifi_group_networks() {
+                    return gtk::Ordering::Equal;
+                }
+                let a = a.downcast_ref::<WifiNetwork>().unwrap();
+                let b = b.downcast_ref::<WifiNetwork>().unwrap();
+                a.section().cmp(&b.section()).into()
+            }
+        ));
+        let sorted_networks = gtk::SortListModel::new(
+            Some(manager.wifi_networks()),
+            Some(section_sorter.clone()),
+        );
+        imp.networks_listbox.set_header_func(glib::clone!(
+            #[weak]
+            manager,
+            move |row, before| {
+                if !manager.wifi_group_networks() {
+                    row.set_header(None::<&gtk::Widget>);
+                    return;
+                }
+                let section = |row: &gtk::ListBoxRow| {
+                    row.downcast_ref::<WifiNetworkRow>().map(|r| r.network().section())
+                };
+                let Some(current) = section(row) else { return };
+                if before.and_then(section) == Some(current) {
+                    row.set_header(None::<&gtk::Widget>);
+                    return;
+                }
+                let header = gtk::Label::builder()
+                    .label(current.title())
+                    .xalign(0.0)
+                    .margin_top(12)
+                    .margin_bottom(6)
+                    .margin_start(12)
+                    .build();
+                header.add_css_class("heading");
+                header.add_css_class("dim-label");
+                row.set_header(Some(&header));
+            }
+        ));
+        // Connected/known flags change on the objects themselves, so re-sort
+        // and redo the headers on updates and when grouping is toggled
+        let networks_listbox = imp.networks_listbox.clone();
+        let regroup = move || {
+            section_sorter.changed(gtk::SorterChange::Different);
+            networks_listbox.invalidate_headers();
+        };
+        let on_update = regroup.clone();
+        manager.connect_closure(
+            "wifi-network-updated",
+            false,
+            glib::closure_local!(move |manager: WlcontrolManager| {
+                if manager.wifi_group_networks() {
+                    on_update();
+                }
+            }),
+        );
+        manager.connect_notify_local(Some("wifi-group-networks"), move |_, _| regroup());
         imp.networks_listbox.bind_model(
-            Some(&manager.wifi_networks()),
+            Some(&sorted_networks),
             glib::clone!(
                 #[weak]
                 manager,
@@ -168,9 +407,43 @@ impl WifiPage {
             ),
         );
 
-        // Bind saved networks list (known networks not in range)
+        // Bind saved networks list (known networks not in range), in the
+        // order picked from the group's sort menu
+        let sorter = gtk::CustomSorter::new(glib::clone!(
+            #[weak]
+            manager,
+            #[upgrade_or]
+            gtk::Ordering::Equal,
+            move |a, b| {
+                let a = a.downcast_ref::<WifiNetwork>().unwrap();
+                let b = b.downcast_ref::<WifiNetwork>().unwrap();
+                manager.compare_saved_networks(a, b).into()
+            }
+        ));
+        let sorted_saved =
+            gtk::SortListModel::new(Some(manager.saved_networks()), Some(sorter.clone()));
+        manager.connect_notify_local(Some("wifi-saved-sort"), move |_, _| {
+            sorter.changed(gtk::SorterChange::Different);
+        });
+        let saved_sort = gio::SimpleAction::new_stateful(
+            "saved-sort",
+            Some(glib::VariantTy::STRING),
+            &manager.wifi_saved_sort().as_key().to_variant(),
+        );
+        saved_sort.connect_activate(glib::clone!(
+            #[weak]
+            manager,
+            move |action, target| {
+                let Some(key) = target.and_then(|t| t.get::<String>()) else { return };
+                action.set_state(&key.to_variant());
+                manager.set_wifi_saved_sort(WifiSavedSort::from_key(&key));
+            }
+        ));
+        let actions = gio::SimpleActionGroup::new();
+        actions.add_action(&saved_sort);
+        self.insert_action_group("wifi", Some(&actions));
         imp.saved_listbox.bind_model(
-            Some(&manager.saved_networks()),
+            Some(&sorted_saved),
             glib::clone!(
                 #[weak]
                 manager,
@@ -182,18 +455,27 @@ impl WifiPage {
             ),
         );
 
-        // Show/hide saved group based on item count
+        // Show/hide saved group based on item count. It also stays up when the
+        // saved networks couldn't be read, so that isn't mistaken for having none
         let saved_group = imp.saved_group.clone();
-        let update_saved_visibility = move |store: &gio::ListStore| {
-            saved_group.set_visible(store.n_items() > 0);
+        let update_saved_visibility = move |manager: &WlcontrolManager| {
+            let unreadable = manager.wifi_known_unreadable();
+            saved_group.set_visible(manager.saved_networks().n_items() > 0 || unreadable);
+            saved_group.set_description(unreadable.then_some("Couldn't read saved networks"));
         };
-        let saved_store = manager.saved_networks();
-        update_saved_visibility(&saved_store);
-        saved_store.connect_items_changed(move |store, _, _, _| {
-            update_saved_visibility(store);
+        update_saved_visibility(manager);
+        manager.saved_networks().connect_items_changed(glib::clone!(
+            #[weak]
+            manager,
+            #[strong]
+            update_saved_visibility,
+            move |_, _, _, _| update_saved_visibility(&manager)
+        ));
+        manager.connect_notify_local(Some("wifi-known-unreadable"), move |manager, _| {
+            update_saved_visibility(manager)
         });
 
-        // Toggle button expands/collapses saved listbox
+        // Toggle button expands/collapses saved listbox (remembered across launches)
         let saved_listbox = imp.saved_listbox.clone();
         imp.saved_toggle.connect_toggled(move |button| {
             let expanded = button.is_active();
@@ -203,7 +485,10 @@ impl WifiPage {
             } else {
                 "pan-end-symbolic"
             });
+            crate::settings::set_bool("saved-networks-expanded", expanded);
         });
+        imp.saved_toggle
+            .set_active(crate::settings::get_bool("saved-networks-expanded", false));
 
         // Handle WiFi errors
         manager.connect_closure(
@@ -218,6 +503,90 @@ impl WifiPage {
             ),
         );
 
+        // Powered device without a Station stays useless until iwd sets it
+        // up again; keep the toast until the user retries or dismisses it
+        manager.connect_closure(
+            "wifi-station-missing",
+            false,
+            glib::closure_local!(
+                #[weak(rename_to = page)]
+                self,
+                move |manager: WlcontrolManager| {
+                    let toast = adw::Toast::new("WiFi hardware did not initialize");
+                    toast.set_timeout(0);
+                    toast.set_button_label(Some("Retry"));
+                    toast.connect_button_clicked(glib::clone!(
+                        #[weak]
+                        manager,
+                        move |_| manager.request_wifi_restart()
+                    ));
+                    page.imp().toast_overlay.add_toast(toast);
+                }
+            ),
+        );
+
+        // A hidden connect from the dialog that found no such network asks
+        // again with the name kept, so a typo can be fixed; other failures
+        // are toasts
+        manager.connect_closure(
+            "wifi-hidden-done",
+            false,
+            glib::closure_local!(
+                #[weak(rename_to = page)]
+                self,
+                move |_manager: WlcontrolManager,
+                      request: u64,
+                      name: String,
+                      error: String,
+                      not_found: bool| {
+                    let from_dialog = {
+                        let mut pending = page.imp().hidden_pending.borrow_mut();
+                        let matches = *pending == Some(RequestId(request));
+                        if matches {
+                            pending.take();
+                        }
+                        matches
+                    };
+                    if error.is_empty() {
+                        return;
+                    }
+                    if from_dialog && not_found {
+                        page.show_hidden_network_dialog(&name, Some(&error));
+                    } else {
+                        page.show_toast(&error);
+                    }
+                }
+            ),
+        );
+
+        // Saved password rejected: offer to replace it rather than a toast
+        manager.connect_closure(
+            "wifi-auth-failed",
+            false,
+            glib::closure_local!(
+                #[weak(rename_to = page)]
+                self,
+                move |manager: WlcontrolManager, path: String, name: String| {
+                    page.show_auth_failed_dialog(&manager, &path, &name);
+                }
+            ),
+        );
+
+        // Roaming between access points: subtle, informational only
+        manager.connect_closure(
+            "wifi-roamed",
+            false,
+            glib::closure_local!(
+                #[weak(rename_to = page)]
+                self,
+                move |_manager: WlcontrolManager, bssid: String| {
+                    let toast = adw::Toast::new(&format!("Roamed to {}", bssid));
+                    toast.set_timeout(2);
+                    page.imp().toast_overlay.add_toast(toast);
+                }
+            ),
+        );
+
         // Handle captive portal
         manager.connect_closure(
             "captive-portal",
@@ -246,21 +615,40 @@ impl WifiPage {
             glib::closure_local!(
                 #[watch]
                 page,
-                move |manager: WlcontrolManager, _network_path: String, network_name: String| {
-                    let dialog = PasswordDialog::new(&network_name);
+                move |manager: WlcontrolManager,
+                      _network_path: String,
+                      network_name: String,
+                      network_type: String| {
+                    let dialog = PasswordDialog::new(&display_ssid(&network_name), &network_type);
+                    page.imp().password_dialog.replace(Some(dialog.clone()));
                     glib::spawn_future_local(glib::clone!(
                         #[weak]
                         manager,
                         #[weak]
                         page,
                         async move {
-                            let response = dialog.run(&page).await;
-                            manager.send_passphrase_response(response);
+                            let (passphrase, remember) = dialog.run(&page).await;
+                            page.imp().password_dialog.take();
+                            manager.send_passphrase_response(passphrase, remember);
                         }
                     ));
                 }
             ),
         );
+
+        manager.connect_closure(
+            "passphrase-cancelled",
+            false,
+            glib::closure_local!(
+                #[weak(rename_to = page)]
+                self,
+                move |_manager: WlcontrolManager| {
+                    if let Some(dialog) = page.imp().password_dialog.take() {
+                        dialog.close();
+                    }
+                }
+            ),
+        );
     }
 
     fn create_network_row(
@@ -269,24 +657,25 @@ impl WifiPage {
         is_saved_offline: bool,
     ) -> WifiNetworkRow {
         let row = WifiNetworkRow::new(network);
+        if network.mark_shown() {
+            crate::ui::fade_in_row(&row);
+        }
         row.connect_activated(glib::clone!(
             #[weak]
             manager,
             #[weak]
             network,
-            move |_| {
-                match network.state() {
-                    WifiNetworkState::Connected => {
-                        manager.request_wifi_disconnect();
-                    }
-                    WifiNetworkState::Available | WifiNetworkState::Saved => {
-                        manager.request_wifi_connect(&network.path());
-                    }
-                    // SavedOffline and in-progress states: ignore clicks
-                    WifiNetworkState::SavedOffline
-                    | WifiNetworkState::Connecting
-                    | WifiNetworkState::Disconnecting
-                    | WifiNetworkState::Forgetting => {}
+            move |row| {
+                let joining = matches!(
+                    network.state(),
+                    WifiNetworkState::Available
+                        | WifiNetworkState::Saved
+                        | WifiNetworkState::SavedOffline
+                );
+                if joining && !network.is_secured() && manager.wifi_warn_open() {
+                    Self::show_open_network_warning(row, &manager, &network);
+                } else {
+                    Self::activate_network(row, &manager, &network);
                 }
             }
         ));
@@ -294,26 +683,245 @@ impl WifiPage {
         row
     }
 
+    /// What clicking a network row does, depending on its state
+    fn activate_network(row: &WifiNetworkRow, manager: &WlcontrolManager, network: &WifiNetwork) {
+        match network.state() {
+            WifiNetworkState::Connected => {
+                manager.request_wifi_disconnect();
+            }
+            // Enterprise networks need provisioning before iwd can join them
+            WifiNetworkState::Available if network.network_type() == "8021x" => {
+                Self::show_enterprise_network_dialog(row, manager, network);
+            }
+            // Open networks never reach the password dialog, so ask here
+            WifiNetworkState::Available if !network.is_secured() => {
+                Self::show_open_network_dialog(row, manager, network);
+            }
+            // Secured networks get the choice in the password dialog
+            WifiNetworkState::Available | WifiNetworkState::Saved => {
+                manager.request_wifi_connect(&network.path(), true);
+            }
+            WifiNetworkState::SavedOffline if network.hidden() => {
+                manager.request_wifi_connect_hidden(&network.name());
+            }
+            // Not in the last scan; scan and connect if it turns up
+            WifiNetworkState::SavedOffline => {
+                manager.request_wifi_search_and_connect(&network.name(), &network.network_type());
+                let page = row.ancestor(WifiPage::static_type()).and_downcast::<WifiPage>();
+                if let Some(page) = page {
+                    page.show_toast(&format!("Searching for {}…", network.display_name()));
+                }
+            }
+            // In-progress states: ignore clicks
+            WifiNetworkState::Connecting
+            | WifiNetworkState::Disconnecting
+            | WifiNetworkState::Forgetting => {}
+        }
+    }
+
+    /// Warn that a network is unencrypted before joining it (opt-in
+    /// preference); accepting continues as a normal click would
+    fn show_open_network_warning(
+        row: &WifiNetworkRow,
+        manager: &WlcontrolManager,
+        network: &WifiNetwork,
+    ) {
+        let dialog = adw::AlertDialog::builder()
+            .heading("Unencrypted Network")
+            .body(format!(
+                "\"{}\" is not encrypted. Others nearby can see what is sent over it. \
+                 Connect anyway?",
+                network.display_name()
+            ))
+            .build();
+
+        dialog.add_response("cancel", "Cancel");
+        dialog.add_response("connect", "Connect");
+        dialog.set_response_appearance("connect", adw::ResponseAppearance::Destructive);
+        dialog.set_default_response(Some("cancel"));
+        dialog.set_close_response("cancel");
+
+        glib::spawn_future_local(glib::clone!(
+            #[weak]
+            manager,
+            #[weak]
+            network,
+            #[weak]
+            row,
+            async move {
+                if dialog.choose_future(Some(&row)).await == "connect" {
+                    Self::activate_network(&row, &manager, &network);
+                }
+            }
+        ));
+    }
+
+    fn show_auth_failed_dialog(&self, manager: &WlcontrolManager, path: &str, name: &str) {
+        let dialog = adw::AlertDialog::builder()
+            .heading("Password Rejected")
+            .body(format!(
+                "The saved password for \"{}\" was rejected. It may have been changed on the router.",
+                name
+            ))
+            .build();
+
+        dialog.add_response("cancel", "Cancel");
+        dialog.add_response("update", "Update Password");
+        dialog.set_response_appearance("update", adw::ResponseAppearance::Suggested);
+        dialog.set_default_response(Some("update"));
+        dialog.set_close_response("cancel");
+
+        let path = path.to_string();
+        glib::spawn_future_local(glib::clone!(
+            #[weak]
+            manager,
+            #[weak(rename_to = page)]
+            self,
+            async move {
+                if dialog.choose_future(Some(&page)).await == "update" {
+                    manager.request_wifi_update_password(&path);
+                }
+            }
+        ));
+    }
+
+    /// Explain that an unprovisioned 802.1X network likely needs credentials
+    /// set up for iwd before connecting can succeed
+    fn show_enterprise_network_dialog(
+        row: &WifiNetworkRow,
+        manager: &WlcontrolManager,
+        network: &WifiNetwork,
+    ) {
+        let dialog = adw::AlertDialog::builder()
+            .heading("Enterprise Network")
+            .body(format!(
+                "\"{}\" uses enterprise (802.1X) authentication. iwd can only connect \
+                 once the network's credentials are provisioned in /var/lib/iwd.",
+                network.display_name()
+            ))
+            .build();
+
+        dialog.add_response("cancel", "Cancel");
+        dialog.add_response("help", "Setup Guide");
+        dialog.add_response("connect", "Try Anyway");
+        dialog.set_default_response(Some("help"));
+        dialog.set_close_response("cancel");
+
+        glib::spawn_future_local(glib::clone!(
+            #[weak]
+            manager,
+            #[weak]
+            network,
+            #[weak]
+            row,
+            async move {
+                match dialog.choose_future(Some(&row)).await.as_str() {
+                    "connect" => manager.request_wifi_connect(&network.path(), true),
+                    "help" => {
+                        let launcher = gtk::UriLauncher::new(IWD_NETWORK_CONFIG_URL);
+                        let window = row.root().and_downcast::<gtk::Window>();
+                        launcher.launch(window.as_ref(), gio::Cancellable::NONE, |result| {
+                            if let Err(e) = result {
+                                tracing::error!("Failed to open browser: {}", e);
+                            }
+                        });
+                    }
+                    _ => {}
+                }
+            }
+        ));
+    }
+
+    /// Ask whether to keep an open network saved before joining it
+    fn show_open_network_dialog(
+        row: &WifiNetworkRow,
+        manager: &WlcontrolManager,
+        network: &WifiNetwork,
+    ) {
+        let dialog = adw::AlertDialog::builder()
+            .heading("Connect to Open Network?")
+            .body(format!(
+                "Remember \"{}\" to rejoin it automatically, or connect just this once.",
+                network.display_name()
+            ))
+            .build();
+
+        dialog.add_response("cancel", "Cancel");
+        dialog.add_response("once", "Connect Once");
+        dialog.add_response("remember", "Remember");
+        dialog.set_response_appearance("remember", adw::ResponseAppearance::Suggested);
+        dialog.set_default_response(Some("remember"));
+        dialog.set_close_response("cancel");
+
+        glib::spawn_future_local(glib::clone!(
+            #[weak]
+            manager,
+            #[weak]
+            network,
+            #[weak]
+            row,
+            async move {
+                match dialog.choose_future(Some(&row)).await.as_str() {
+                    "remember" => manager.request_wifi_connect(&network.path(), true),
+                    "once" => manager.request_wifi_connect(&network.path(), false),
+                    _ => {}
+                }
+            }
+        ));
+    }
+
+    /// The dropdown's popup; GtkDropDown keeps it as a child widget
+    fn adapter_popover(&self) -> Option<gtk::Popover> {
+        let mut child = self.imp().adapter_dropdown.first_child();
+        while let Some(widget) = child {
+            if let Some(popover) = widget.downcast_ref::<gtk::Popover>() {
+                return Some(popover.clone());
+            }
+            child = widget.next_sibling();
+        }
+        None
+    }
+
     fn rebuild_adapter_dropdown(&self, manager: &WlcontrolManager) {
         let imp = self.imp();
+        if self.adapter_popover().is_some_and(|p| p.is_visible()) {
+            imp.adapter_rebuild_pending.set(true);
+            return;
+        }
         let adapters = manager.wifi_adapters();
 
         imp.adapter_dropdown.set_visible(adapters.len() > 1);
 
+        let active_path = manager.active_wifi_device_path();
+        let connected = manager
+            .wifi_networks()
+            .iter::<WifiNetwork>()
+            .flatten()
+            .find(|n| n.connected());
         let model = gtk::StringList::new(&[]);
+        let mut statuses = Vec::new();
         for info in &adapters {
-            let label = if info.adapter_model.is_empty() {
-                info.device_name.clone()
-            } else {
-                info.adapter_model.clone()
+            model.append(&info.label());
+
+            // Only the active adapter's state is followed live
+            let active = active_path.as_deref() == Some(info.device_path.as_str());
+            let powered = if active { manager.wifi_powered() } else { info.powered };
+            let status = match &connected {
+                _ if !powered => "Off".to_string(),
+                Some(network) if active => format!("Connected to {}", network.display_name()),
+                None if active => "In use, not connected".to_string(),
+                _ => "Not in use".to_string(),
             };
-            model.append(&label);
+            statuses.push((status, powered));
         }
+        imp.adapter_statuses.replace(statuses);
+        imp.adapter_paths
+            .replace(adapters.iter().map(|a| a.device_path.clone()).collect());
 
         imp.updating_combo.set(true);
         imp.adapter_dropdown.set_model(Some(&model));
 
-        if let Some(active_path) = manager.active_wifi_device_path() {
+        if let Some(active_path) = active_path {
             if let Some(idx) = adapters.iter().position(|a| a.device_path == active_path) {
                 imp.adapter_dropdown.set_selected(idx as u32);
             }
@@ -321,8 +929,250 @@ impl WifiPage {
         imp.updating_combo.set(false);
     }
 
+    /// An adapter that is present but off lists nothing; say why, rather
+    /// than suggesting there are no networks around
+    fn update_networks_placeholder(&self, manager: &WlcontrolManager) {
+        let Some(placeholder) = self.imp().networks_placeholder.get() else {
+            return;
+        };
+        if manager.wifi_powered() {
+            placeholder.set_label("No networks found");
+        } else {
+            placeholder.set_label("WiFi is turned off");
+        }
+    }
+
+    /// Show how fresh the network list is in the group description
+    fn update_scan_age(&self, manager: &WlcontrolManager) {
+        let description = if let Some((cycle, cycles)) = manager.wifi_scan_cycle() {
+            format!("Thorough scan {} of {}…", cycle, cycles)
+        } else if manager.wifi_scanning() {
+            "Scanning…".to_string()
+        } else {
+            match manager.wifi_scan_age().map(|age| age.as_secs()) {
+                None => "Cached results, scan for a fresh list".to_string(),
+                Some(0..=4) => "Last scanned just now".to_string(),
+                Some(secs @ 5..=59) => format!("Last scanned {} seconds ago", secs),
+                Some(60..=119) => "Last scanned 1 minute ago".to_string(),
+                Some(secs) => format!("Last scanned {} minutes ago", secs / 60),
+            }
+        };
+        self.imp().networks_group.set_description(Some(&description));
+    }
+
+    /// Save the saved network list (names and settings, no passwords) to a file
+    pub fn export_saved_networks(&self) {
+        let Some(manager) = self.imp().manager.get() else {
+            return;
+        };
+        let json = manager.export_saved_networks();
+        let dialog = gtk::FileDialog::builder()
+            .title("Export Saved Networks")
+            .initial_name("wifi-networks.json")
+            .default_filter(&Self::json_filter())
+            .modal(true)
+            .build();
+
+        glib::spawn_future_local(glib::clone!(
+            #[weak(rename_to = page)]
+            self,
+            async move {
+                let window = page.root().and_downcast::<gtk::Window>();
+                // Err means the dialog was cancelled
+                let Ok(file) = dialog.save_future(window.as_ref()).await else {
+                    return;
+                };
+                let Some(path) = file.path() else {
+                    return;
+                };
+                match std::fs::write(&path, json) {
+                    Ok(()) => page.show_toast("Saved networks exported (passwords not included)"),
+                    Err(e) => page.show_toast(&format!("Export failed: {}", e)),
+                }
+            }
+        ));
+    }
+
+    /// Save the networks in range with their signal and security, for site
+    /// surveys. CSV unless the chosen file name ends in .json.
+    pub fn export_scan_results(&self) {
+        let Some(manager) = self.imp().manager.get() else {
+            return;
+        };
+        let store = manager.wifi_networks();
+        let results: Vec<ScanResult> = (0..store.n_items())
+            .filter_map(|i| store.item(i).and_downcast::<WifiNetwork>())
+            .map(|network| ScanResult::from_network(&network))
+            .collect();
+        if results.is_empty() {
+            self.show_toast("No networks in range to export");
+            return;
+        }
+        let Ok(now) = glib::DateTime::now_local() else {
+            return;
+        };
+        let timestamp = now.format_iso8601().map(|t| t.to_string()).unwrap_or_default();
+        let file_stamp = now.format("%Y%m%d-%H%M").map(|t| t.to_string()).unwrap_or_default();
+
+        let csv_filter = Self::csv_filter();
+        let filters = gio::ListStore::new::<gtk::FileFilter>();
+        filters.append(&csv_filter);
+        filters.append(&Self::json_filter());
+        let dialog = gtk::FileDialog::builder()
+            .title("Export Scan Results")
+            .initial_name(format!("wifi-scan-{}.csv", file_stamp))
+            .filters(&filters)
+            .default_filter(&csv_filter)
+            .modal(true)
+            .build();
+
+        glib::spawn_future_local(glib::clone!(
+            #[weak(rename_to = page)]
+            self,
+            async move {
+                let window = page.root().and_downcast::<gtk::Window>();
+                // Err means the dialog was cancelled
+                let Ok(file) = dialog.save_future(window.as_ref()).await else {
+                    return;
+                };
+                let Some(path) = file.path() else {
+                    return;
+                };
+                let contents = if path.extension().is_some_and(|ext| ext == "json") {
+                    scan_export::to_json(&results, &timestamp)
+                } else {
+                    scan_export::to_csv(&results, &timestamp)
+                };
+                match std::fs::write(&path, contents) {
+                    Ok(()) => page.show_toast(&format!(
+                        "Exported {} network{}",
+                        results.len(),
+                        if results.len() == 1 { "" } else { "s" }
+                    )),
+                    Err(e) => page.show_toast(&format!("Export failed: {}", e)),
+                }
+            }
+        ));
+    }
+
+    /// Add networks from an export file as placeholders in the saved list
+    pub fn import_saved_networks(&self) {
+        let Some(manager) = self.imp().manager.get().cloned() else {
+            return;
+        };
+        let dialog = gtk::FileDialog::builder()
+            .title("Import Saved Networks")
+            .default_filter(&Self::json_filter())
+            .modal(true)
+            .build();
+
+        glib::spawn_future_local(glib::clone!(
+            #[weak(rename_to = page)]
+            self,
+            async move {
+                let window = page.root().and_downcast::<gtk::Window>();
+                let Ok(file) = dialog.open_future(window.as_ref()).await else {
+                    return;
+                };
+                let Some(path) = file.path() else {
+                    return;
+                };
+                let result = std::fs::read_to_string(&path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|json| manager.import_saved_networks(&json));
+                match result {
+                    Ok(0) => page.show_toast("No new networks to import"),
+                    Ok(n) => page.show_toast(&format!(
+                        "Imported {} network{} — enter each password on first connect",
+                        n,
+                        if n == 1 { "" } else { "s" }
+                    )),
+                    Err(e) => page.show_toast(&format!("Import failed: {}", e)),
+                }
+            }
+        ));
+    }
+
+    fn csv_filter() -> gtk::FileFilter {
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some("CSV files"));
+        filter.add_mime_type("text/csv");
+        filter.add_suffix("csv");
+        filter
+    }
+
+    fn json_filter() -> gtk::FileFilter {
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some("JSON files"));
+        filter.add_mime_type("application/json");
+        filter.add_suffix("json");
+        filter
+    }
+
+    /// Ask for the name of a hidden network and join it. iwd asks for the
+    /// passphrase itself and saves the network as hidden once connected.
+    /// `error` is shown when asking again after no network had that name.
+    pub fn show_hidden_network_dialog(&self, name: &str, error: Option<&str>) {
+        let Some(manager) = self.imp().manager.get() else {
+            return;
+        };
+        let dialog = adw::AlertDialog::builder()
+            .heading("Connect to Hidden Network")
+            .body(match error {
+                Some(error) => format!("{}\n\nNetwork names are case-sensitive.", error),
+                None => "Enter the name (SSID) of a network that doesn't broadcast it.".into(),
+            })
+            .build();
+
+        dialog.add_response("cancel", "Cancel");
+        dialog.add_response("connect", "Connect");
+        dialog.set_response_appearance("connect", adw::ResponseAppearance::Suggested);
+        dialog.set_default_response(Some("connect"));
+        dialog.set_close_response("cancel");
+
+        let entry = adw::EntryRow::builder().title("Network Name").text(name).build();
+        if error.is_some() {
+            entry.add_css_class("error");
+        }
+        let group = adw::PreferencesGroup::new();
+        group.add(&entry);
+        dialog.set_extra_child(Some(&group));
+
+        // SSIDs are 1 to 32 bytes
+        let update = glib::clone!(
+            #[weak]
+            dialog,
+            move |entry: &adw::EntryRow| {
+                let len = entry.text().len();
+                dialog.set_response_enabled("connect", (1..=32).contains(&len));
+            }
+        );
+        update(&entry);
+        entry.connect_changed(move |entry| {
+            entry.remove_css_class("error");
+            update(entry);
+        });
+
+        glib::spawn_future_local(glib::clone!(
+            #[weak]
+            manager,
+            #[weak(rename_to = page)]
+            self,
+            async move {
+                if dialog.choose_future(Some(&page)).await != "connect" {
+                    return;
+                }
+                let name = entry.text().to_string();
+                let request = manager.request_wifi_connect_hidden(&name);
+                page.imp().hidden_pending.replace(Some(request));
+            }
+        ));
+    }
+
     pub fn show_toast(&self, message: &str) {
         let toast = adw::Toast::new(message);
+        // Messages can contain network names, which are not markup
+        toast.set_use_markup(false);
         self.imp().toast_overlay.add_toast(toast);
     }
 }
@@ -3,8 +3,14 @@ use adw::subclass::prelude::*;
 use gtk::{gio, glib};
 use std::cell::OnceCell;
 
+use crate::backend::bluetooth::BtDevice;
+use crate::backend::wifi::{WifiNetwork, WifiNetworkState};
 use crate::backend::WlcontrolManager;
-use crate::ui::{BluetoothPage, WifiPage};
+#[cfg(feature = "bluetooth")]
+use crate::ui::BluetoothPage;
+use crate::ui::PreferencesDialog;
+#[cfg(feature = "wifi")]
+use crate::ui::WifiPage;
 
 mod imp {
     use super::*;
@@ -13,15 +19,19 @@ mod imp {
     #[template(resource = "/dev/neoden/wlcontrol/ui/window.ui")]
     pub struct WlcontrolWindow {
         #[template_child]
-        pub wifi_stack_page: TemplateChild<adw::ViewStackPage>,
+        pub stack: TemplateChild<adw::ViewStack>,
         #[template_child]
-        pub bt_stack_page: TemplateChild<adw::ViewStackPage>,
+        pub airplane_button: TemplateChild<gtk::ToggleButton>,
         #[template_child]
-        pub wifi_page: TemplateChild<WifiPage>,
+        pub status_label: TemplateChild<gtk::Label>,
         #[template_child]
-        pub bluetooth_page: TemplateChild<BluetoothPage>,
+        pub last_bt_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub backend_banner: TemplateChild<adw::Banner>,
 
         pub manager: OnceCell<WlcontrolManager>,
+        #[cfg(feature = "wifi")]
+        pub wifi_page: OnceCell<WifiPage>,
     }
 
     #[glib::object_subclass]
@@ -31,8 +41,6 @@ mod imp {
         type ParentType = adw::ApplicationWindow;
 
         fn class_init(klass: &mut Self::Class) {
-            WifiPage::ensure_type();
-            BluetoothPage::ensure_type();
             klass.bind_template();
         }
 
@@ -67,22 +75,228 @@ impl WlcontrolWindow {
             .build();
 
         window.imp().manager.set(manager.clone()).unwrap();
-        window.imp().wifi_page.set_manager(manager);
-        window.imp().bluetooth_page.set_manager(manager);
+        #[cfg(feature = "wifi")]
+        window.add_wifi_page(manager);
+        #[cfg(feature = "bluetooth")]
+        window.add_bluetooth_page(manager);
 
-        // Bind page visibility to backend availability
         manager
-            .bind_property("wifi-available", &*window.imp().wifi_stack_page, "visible")
+            .bind_property("airplane-mode", &*window.imp().airplane_button, "active")
+            .sync_create()
+            .bidirectional()
+            .build();
+        manager
+            .bind_property("bt-available", &*window.imp().last_bt_button, "visible")
             .sync_create()
             .build();
         manager
-            .bind_property("bt-available", &*window.imp().bt_stack_page, "visible")
+            .bind_property("backend-stopped", &*window.imp().backend_banner, "revealed")
             .sync_create()
             .build();
 
+        window.setup_actions();
+
+        window.connect_is_active_notify(glib::clone!(
+            #[weak]
+            manager,
+            move |window| {
+                if window.is_active() {
+                    manager.scan_on_focus();
+                }
+            }
+        ));
+
+        window.watch_connected(&manager.wifi_networks());
+        window.watch_connected(&manager.bt_devices());
+        manager.connect_notify_local(
+            Some("bt-powered"),
+            glib::clone!(
+                #[weak]
+                window,
+                move |_, _| window.update_last_bt_action()
+            ),
+        );
+        window.update_connection_status();
+
         window
     }
 
+    /// Add the WiFi page, shown while iwd has an adapter
+    #[cfg(feature = "wifi")]
+    fn add_wifi_page(&self, manager: &WlcontrolManager) {
+        let page = glib::Object::new::<WifiPage>();
+        page.set_manager(manager);
+        let stack_page = self.imp().stack.add_titled_with_icon(
+            &page,
+            Some("wifi"),
+            "WiFi",
+            "network-wireless-symbolic",
+        );
+        manager
+            .bind_property("wifi-available", &stack_page, "visible")
+            .sync_create()
+            .build();
+        self.imp().wifi_page.set(page).unwrap();
+    }
+
+    /// Add the Bluetooth page after the WiFi one, shown while BlueZ has an adapter
+    #[cfg(feature = "bluetooth")]
+    fn add_bluetooth_page(&self, manager: &WlcontrolManager) {
+        let page = glib::Object::new::<BluetoothPage>();
+        page.set_manager(manager);
+        let stack_page = self.imp().stack.add_titled_with_icon(
+            &page,
+            Some("bluetooth"),
+            "Bluetooth",
+            "bluetooth-symbolic",
+        );
+        manager
+            .bind_property("bt-available", &stack_page, "visible")
+            .sync_create()
+            .build();
+    }
+
+    /// Refresh the header status whenever the list changes or any item's
+    /// `connected` flips
+    fn watch_connected(&self, store: &gio::ListStore) {
+        let watch_item = glib::clone!(
+            #[weak(rename_to = window)]
+            self,
+            move |item: &glib::Object| {
+                item.connect_notify_local(
+                    Some("connected"),
+                    glib::clone!(
+                        #[weak]
+                        window,
+                        move |_, _| window.update_connection_status()
+                    ),
+                );
+            }
+        );
+        for item in store.iter::<glib::Object>().flatten() {
+            watch_item(&item);
+        }
+        store.connect_items_changed(glib::clone!(
+            #[weak(rename_to = window)]
+            self,
+            move |store, position, _removed, added| {
+                for i in position..position + added {
+                    if let Some(item) = store.item(i) {
+                        watch_item(&item);
+                    }
+                }
+                window.update_connection_status();
+            }
+        ));
+    }
+
+    fn update_connection_status(&self) {
+        self.update_status_summary();
+        self.update_last_bt_action();
+    }
+
+    /// Enable the quick toggle only when there's a device to toggle, and
+    /// say in the tooltip what it will do
+    fn update_last_bt_action(&self) {
+        let manager = self.manager();
+        let device = manager.last_bt_device();
+        if let Some(action) = self
+            .lookup_action("toggle-last-bt")
+            .and_downcast::<gio::SimpleAction>()
+        {
+            action.set_enabled(manager.bt_powered() && device.is_some());
+        }
+        let tooltip = match device {
+            Some(device) if device.connected() => format!("Disconnect {}", device.display_name()),
+            Some(device) => format!("Connect {}", device.display_name()),
+            None => "No recently used Bluetooth device".to_string(),
+        };
+        self.imp().last_bt_button.set_tooltip_text(Some(&tooltip));
+    }
+
+    fn update_status_summary(&self) {
+        let manager = self.manager();
+        let wifi = manager
+            .wifi_networks()
+            .iter::<WifiNetwork>()
+            .flatten()
+            .find(|n| n.state() == WifiNetworkState::Connected)
+            .map(|n| n.display_name());
+        let bt: Vec<String> = manager
+            .bt_devices()
+            .iter::<BtDevice>()
+            .flatten()
+            .filter(|d| d.connected())
+            .map(|d| d.display_name())
+            .collect();
+
+        let summary = crate::ui::status_summary(wifi.as_deref(), &bt);
+        let label = &self.imp().status_label;
+        label.set_label(&summary);
+        // Full text when the label is ellipsized
+        label.set_tooltip_text(Some(&summary));
+    }
+
+    fn setup_actions(&self) {
+        let preferences = gio::ActionEntry::builder("preferences")
+            .activate(|window: &Self, _, _| {
+                let dialog = PreferencesDialog::new(window.manager());
+                dialog.present(Some(window));
+            })
+            .build();
+        let toggle_last_bt = gio::ActionEntry::builder("toggle-last-bt")
+            .activate(|window: &Self, _, _| {
+                window.manager().toggle_last_bt_device();
+            })
+            .build();
+        // Re-reads the visible page's state, after changes made with
+        // iwctl or bluetoothctl
+        let refresh = gio::ActionEntry::builder("refresh")
+            .activate(|window: &Self, _, _| {
+                let manager = window.manager();
+                match window.imp().stack.visible_child_name().as_deref() {
+                    Some("bluetooth") => manager.request_bt_refresh(),
+                    _ => manager.request_wifi_refresh(),
+                }
+            })
+            .build();
+        self.add_action_entries([preferences, toggle_last_bt, refresh]);
+        #[cfg(feature = "wifi")]
+        self.setup_wifi_actions();
+    }
+
+    /// Actions of the WiFi page; the menu hides their items when missing
+    #[cfg(feature = "wifi")]
+    fn setup_wifi_actions(&self) {
+        let export_networks = gio::ActionEntry::builder("export-networks")
+            .activate(|window: &Self, _, _| {
+                window.wifi_page().export_saved_networks();
+            })
+            .build();
+        let export_scan = gio::ActionEntry::builder("export-scan")
+            .activate(|window: &Self, _, _| {
+                window.wifi_page().export_scan_results();
+            })
+            .build();
+        let connect_hidden = gio::ActionEntry::builder("connect-hidden")
+            .activate(|window: &Self, _, _| {
+                window.imp().stack.set_visible_child_name("wifi");
+                window.wifi_page().show_hidden_network_dialog("", None);
+            })
+            .build();
+        let import_networks = gio::ActionEntry::builder("import-networks")
+            .activate(|window: &Self, _, _| {
+                window.wifi_page().import_saved_networks();
+            })
+            .build();
+        self.add_action_entries([connect_hidden, export_networks, import_networks, export_scan]);
+    }
+
+    #[cfg(feature = "wifi")]
+    fn wifi_page(&self) -> &WifiPage {
+        self.imp().wifi_page.get().unwrap()
+    }
+
     pub fn manager(&self) -> &WlcontrolManager {
         self.imp().manager.get().unwrap()
     }